@@ -0,0 +1,146 @@
+use std::{io::Write, path::Path};
+
+use color_eyre::Result;
+use shared::{ViewModel, types::CaseTree};
+
+use crate::{
+    core::{self, Core},
+    persistence,
+};
+
+/// Writes the result of a non-interactive command, either as the `ViewModel`
+/// serialized to JSON, or as its text.
+///
+/// # Errors
+///
+/// Can error if serializing or writing to `w` fails.
+pub fn write_view(view: &ViewModel, json: bool, w: &mut impl Write) -> Result<()> {
+    if json {
+        serde_json::to_writer(&mut *w, view)?;
+        writeln!(w)?;
+    } else {
+        writeln!(w, "{}", view.text)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the `CaseTree`, either serialized to JSON, or formatted for people.
+///
+/// # Errors
+///
+/// Can error if serializing or writing to `w` fails.
+pub fn write_tree(tree: &CaseTree, json: bool, w: &mut impl Write) -> Result<()> {
+    if json {
+        serde_json::to_writer(&mut *w, tree)?;
+        writeln!(w)?;
+    } else {
+        let mut formatted = String::new();
+        tree.write_formatted(&mut formatted)?;
+        write!(w, "{formatted}")?;
+    }
+
+    Ok(())
+}
+
+/// Loads the `CaseTree` saved in `data_dir` into `core`, and writes it like
+/// `write_tree`.
+///
+/// # Errors
+///
+/// Can error if loading the saved tree, serializing or writing to `w` fails.
+pub async fn list(core: &Core, data_dir: &Path, json: bool, w: &mut impl Write) -> Result<()> {
+    core::load(core, persistence::tree_file_in(data_dir)).await?;
+
+    write_tree(&core::core_tree(core), json, w)
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::{
+        ViewModel,
+        types::{CaseNode, CaseTree, Group, Task},
+    };
+
+    use super::{list, write_tree, write_view};
+    use crate::{core, persistence};
+
+    fn fixture() -> CaseTree {
+        let mut tree = CaseTree::new(CaseNode::Group(Group::new("root")));
+        let root_id = tree.root_id().unwrap().clone();
+        tree.insert(CaseNode::Task(Task::new("report")), &root_id)
+            .unwrap();
+
+        tree
+    }
+
+    fn formatted(tree: &CaseTree) -> String {
+        let mut s = String::new();
+        tree.write_formatted(&mut s).unwrap();
+        s
+    }
+
+    #[test]
+    fn test_write_tree_json() {
+        let tree = fixture();
+        let mut out = vec![];
+
+        write_tree(&tree, true, &mut out).unwrap();
+
+        let parsed: CaseTree = serde_json::from_slice(&out).unwrap();
+        assert_eq!(formatted(&parsed), formatted(&tree));
+    }
+
+    #[test]
+    fn test_write_tree_text() {
+        let tree = fixture();
+        let mut out = vec![];
+
+        write_tree(&tree, false, &mut out).unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), formatted(&tree));
+    }
+
+    #[tokio::test]
+    async fn test_list_saved_tree() {
+        let data_dir = std::env::temp_dir().join(format!("case-cli-list-{}", std::process::id()));
+        let tree = fixture();
+        persistence::save_tree(persistence::tree_file_in(&data_dir), tree.clone())
+            .await
+            .unwrap();
+        let mut out = vec![];
+
+        list(&core::new(), &data_dir, true, &mut out).await.unwrap();
+
+        let parsed: CaseTree = serde_json::from_slice(&out).unwrap();
+        assert_eq!(formatted(&parsed), formatted(&tree));
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+
+    #[tokio::test]
+    async fn test_list_nothing_saved() {
+        let data_dir = std::env::temp_dir().join(format!("case-cli-empty-{}", std::process::id()));
+        let mut out = vec![];
+
+        list(&core::new(), &data_dir, true, &mut out).await.unwrap();
+
+        let parsed: CaseTree = serde_json::from_slice(&out).unwrap();
+        assert!(parsed.root_id().is_none());
+    }
+
+    #[test]
+    fn test_write_view_json() {
+        let view = ViewModel {
+            text: "3 (2026-01-10)".to_owned(),
+            confirmed: true,
+            ..ViewModel::default()
+        };
+        let mut out = vec![];
+
+        write_view(&view, true, &mut out).unwrap();
+
+        let parsed: ViewModel = serde_json::from_slice(&out).unwrap();
+        assert_eq!(parsed.text, view.text);
+        assert!(parsed.confirmed);
+    }
+}
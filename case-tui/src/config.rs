@@ -1,16 +1,61 @@
-use crate::get_config_file;
+use crate::{config_file_in, get_config_dir};
 use color_eyre::Result;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use derive_deref::{Deref, DerefMut};
-use serde::{Deserialize, de::Deserializer};
-use std::{collections::HashMap, fs};
-
-use crate::{Action, Mode};
+use serde::{
+    Deserialize,
+    de::{self, Deserializer},
+};
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+use tracing::{info, warn};
+
+use crate::{
+    Action, DEFAULT_EMPTY_MESSAGE, Mode,
+    sse::{DEDUP_WINDOW, INACTIVITY_TIMEOUT},
+};
 
 const CONFIG: &str = include_str!("../.config/config.toml");
 
-#[derive(Clone, Debug, Deserialize, Default)]
-pub struct AppConfig {}
+/// Set to `1` or `true` to fail on a malformed config instead of falling back to defaults.
+const STRICT_ENV_VAR: &str = "CASE_CONFIG_STRICT";
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    /// Shown in the Explorer while the tree is empty.
+    pub empty_message: String,
+    /// Reload the config whenever its file changes, see `ConfigWatcher`.
+    pub watch_config: bool,
+    /// How many seconds an SSE stream may stay silent before reconnecting.
+    pub sse_timeout_secs: u64,
+    /// How many of the most recent SSE event ids are remembered to drop
+    /// replayed events.
+    pub sse_dedup_window: usize,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            empty_message: DEFAULT_EMPTY_MESSAGE.to_owned(),
+            watch_config: false,
+            sse_timeout_secs: INACTIVITY_TIMEOUT.as_secs(),
+            sse_dedup_window: DEDUP_WINDOW,
+        }
+    }
+}
+
+impl AppConfig {
+    /// How long an SSE stream may stay silent before it is considered stalled.
+    #[must_use]
+    pub const fn sse_timeout(&self) -> Duration {
+        Duration::from_secs(self.sse_timeout_secs)
+    }
+}
 
 #[derive(Clone, Debug, Default, Deserialize)]
 pub struct Config {
@@ -24,13 +69,42 @@ impl Config {
     /// # Errors
     /// # Panics
     pub fn new() -> Result<Self> {
+        Self::new_in(&get_config_dir())
+    }
+
+    /// Loads the config from `config_dir` instead of the process-wide config
+    /// directory.
+    ///
+    /// A malformed config falls back to the defaults, unless the
+    /// `CASE_CONFIG_STRICT` environment variable is set, see `Config::load`.
+    ///
+    /// # Errors
+    /// # Panics
+    pub fn new_in(config_dir: &Path) -> Result<Self> {
+        let strict = env::var(STRICT_ENV_VAR).is_ok_and(|strict| strict == "1" || strict == "true");
+
+        Self::load(config_dir, strict)
+    }
+
+    /// Loads the config from `config_dir`.
+    ///
+    /// A config file that can't be read or parsed is logged and replaced by the
+    /// defaults, unless `strict` is set.
+    ///
+    /// # Errors
+    /// Can error if `strict` is set and the config file is malformed.
+    /// # Panics
+    pub fn load(config_dir: &Path, strict: bool) -> Result<Self> {
         let default_config: Self = toml::from_str(CONFIG).unwrap();
 
-        let mut cfg = if let Some(path) = get_config_file() {
-            let config_str = fs::read_to_string(path)?;
-            toml::from_str(&config_str)?
-        } else {
-            default_config.clone()
+        let mut cfg = match config_file_in(config_dir).map(|path| Self::read(&path)) {
+            Some(Ok(cfg)) => cfg,
+            Some(Err(e)) if strict => return Err(e),
+            Some(Err(e)) => {
+                warn!("ignoring malformed config, using the defaults instead: {e}");
+                default_config.clone()
+            }
+            None => default_config.clone(),
         };
 
         for (mode, default_bindings) in default_config.keybindings.iter() {
@@ -44,6 +118,87 @@ impl Config {
 
         Ok(cfg)
     }
+
+    fn read(path: &Path) -> Result<Self> {
+        let config_str = fs::read_to_string(path)?;
+        Ok(toml::from_str(&config_str)?)
+    }
+}
+
+/// How often `ConfigWatcher` looks at the config file at most.
+pub const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Reloads the `Config` when its file changes, by polling the modification
+/// time of the file, e.g. on every `Tick`.
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    config_dir: PathBuf,
+    interval: Duration,
+    modified: Option<SystemTime>,
+    last_poll: Option<Instant>,
+}
+
+impl ConfigWatcher {
+    /// Watches the config file in `config_dir`, starting from its current
+    /// contents.
+    #[must_use]
+    pub fn new(config_dir: impl Into<PathBuf>) -> Self {
+        let config_dir = config_dir.into();
+
+        Self {
+            modified: modified_in(&config_dir),
+            config_dir,
+            interval: CONFIG_POLL_INTERVAL,
+            last_poll: None,
+        }
+    }
+
+    /// Sets how often the config file is looked at, at most.
+    #[must_use]
+    pub const fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Returns the reloaded `Config` if the file changed since the last poll.
+    ///
+    /// A malformed file is logged and returns `None`, so the last good config
+    /// stays in use until the file is fixed.
+    pub fn poll(&mut self) -> Option<Config> {
+        let now = Instant::now();
+        if self
+            .last_poll
+            .is_some_and(|last_poll| now.duration_since(last_poll) < self.interval)
+        {
+            return None;
+        }
+        self.last_poll = Some(now);
+
+        let modified = modified_in(&self.config_dir);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+
+        match Config::load(&self.config_dir, true) {
+            Ok(config) => {
+                info!("reloaded the config");
+                Some(config)
+            }
+            Err(e) => {
+                warn!("ignoring malformed config, keeping the current one: {e}");
+                None
+            }
+        }
+    }
+}
+
+/// Returns when the config file in `config_dir` was last modified, if there
+/// is one.
+fn modified_in(config_dir: &Path) -> Option<SystemTime> {
+    config_file_in(config_dir)
+        .and_then(|path| fs::metadata(path).ok())
+        .and_then(|metadata| metadata.modified().ok())
 }
 
 #[derive(Clone, Debug, Default, Deref, DerefMut)]
@@ -61,11 +216,12 @@ impl<'de> Deserialize<'de> for KeyBindings {
             .map(|(mode, inner_map)| {
                 let converted_inner_map = inner_map
                     .into_iter()
-                    .map(|(key_str, cmd)| (parse_key_sequence(&key_str).unwrap(), cmd))
-                    .collect();
-                (mode, converted_inner_map)
+                    .map(|(key_str, cmd)| Ok((parse_key_sequence(&key_str)?, cmd)))
+                    .collect::<Result<HashMap<_, _>, String>>()?;
+                Ok((mode, converted_inner_map))
             })
-            .collect();
+            .collect::<Result<HashMap<_, _>, String>>()
+            .map_err(de::Error::custom)?;
 
         Ok(Self(keybindings))
     }
@@ -258,6 +414,168 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_config_in() -> Result<()> {
+        let base = std::env::temp_dir().join(format!("case-config-test-{}", std::process::id()));
+        let config_dir = crate::config_dir_in(&base);
+        fs::create_dir_all(&config_dir)?;
+        fs::write(
+            config_dir.join("config.toml"),
+            "[keybindings.Home]\n\"<x>\" = \"Quit\"\n",
+        )?;
+
+        let c = Config::new_in(&config_dir)?;
+        fs::remove_dir_all(base)?;
+
+        assert_eq!(
+            c.keybindings
+                .get(&Mode::Home)
+                .unwrap()
+                .get(&parse_key_sequence("<x>").unwrap_or_default())
+                .unwrap(),
+            &Action::Quit
+        );
+        // Defaults are still merged in.
+        assert!(c.keybindings.contains_key(&Mode::Explorer));
+        assert_eq!(c.config.empty_message, DEFAULT_EMPTY_MESSAGE);
+        Ok(())
+    }
+
+    /// Writes `config` into a fresh config directory named after `name`.
+    fn config_dir_with(name: &str, config: &str) -> std::path::PathBuf {
+        let base = std::env::temp_dir().join(format!("case-{name}-test-{}", std::process::id()));
+        let config_dir = crate::config_dir_in(&base);
+        fs::create_dir_all(&config_dir).unwrap();
+        fs::write(config_dir.join("config.toml"), config).unwrap();
+
+        config_dir
+    }
+
+    #[test]
+    fn test_load_good_config() -> Result<()> {
+        let config_dir = config_dir_with("good-config", "empty_message = \"Nothing here\"\n");
+
+        let c = Config::load(&config_dir, true)?;
+        fs::remove_dir_all(config_dir)?;
+
+        assert_eq!(c.config.empty_message, "Nothing here");
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_malformed_config() -> Result<()> {
+        #[derive(Clone, Default)]
+        struct Captured(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+        impl std::io::Write for Captured {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let config_dir = config_dir_with(
+            "malformed-config",
+            "[keybindings.Home]\n\"<nope>\" = \"Quit\"\n",
+        );
+
+        let captured = Captured::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer({
+                let captured = captured.clone();
+                move || captured.clone()
+            })
+            .finish();
+        let c = tracing::subscriber::with_default(subscriber, || Config::load(&config_dir, false))?;
+        fs::remove_dir_all(config_dir)?;
+
+        // Falls back to the defaults.
+        assert_eq!(c.config.empty_message, DEFAULT_EMPTY_MESSAGE);
+        assert!(c.keybindings.get(&Mode::Home).is_none_or(HashMap::is_empty));
+        assert_eq!(
+            c.keybindings
+                .get(&Mode::Explorer)
+                .unwrap()
+                .get(&parse_key_sequence("<q>").unwrap_or_default()),
+            Some(&Action::Quit)
+        );
+
+        let log = String::from_utf8(captured.0.lock().unwrap().clone())?;
+        assert!(log.contains("ignoring malformed config"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_malformed_config_strict() {
+        let config_dir = config_dir_with("strict-config", "empty_message = ");
+
+        assert!(Config::load(&config_dir, true).is_err());
+        fs::remove_dir_all(config_dir).unwrap();
+    }
+
+    #[test]
+    fn test_watcher_reloads() -> Result<()> {
+        let config_dir = config_dir_with("watched-config", "empty_message = \"Nothing here\"\n");
+        let path = config_dir.join("config.toml");
+        // Bumps the modification time, which may not move on quick writes.
+        let touch = |offset| -> Result<()> {
+            fs::File::options()
+                .write(true)
+                .open(&path)?
+                .set_modified(SystemTime::now() + Duration::from_secs(offset))?;
+            Ok(())
+        };
+
+        let mut watcher = ConfigWatcher::new(&config_dir).with_interval(Duration::ZERO);
+        assert!(watcher.poll().is_none());
+
+        fs::write(&path, "empty_message = \"All done\"\n")?;
+        touch(10)?;
+        let reloaded = watcher.poll().expect("the change is picked up");
+        assert_eq!(reloaded.config.empty_message, "All done");
+        assert!(watcher.poll().is_none());
+
+        // A malformed change keeps the last good config.
+        fs::write(&path, "empty_message = ")?;
+        touch(20)?;
+        assert!(watcher.poll().is_none());
+
+        fs::remove_dir_all(config_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_message() -> Result<()> {
+        let c: Config = toml::from_str("empty_message = \"Nothing here\"\n")?;
+
+        assert_eq!(c.config.empty_message, "Nothing here");
+        Ok(())
+    }
+
+    #[test]
+    fn test_sse_timeout() -> Result<()> {
+        let c: Config = toml::from_str("")?;
+        assert_eq!(c.config.sse_timeout(), INACTIVITY_TIMEOUT);
+
+        let c: Config = toml::from_str("sse_timeout_secs = 5\n")?;
+        assert_eq!(c.config.sse_timeout(), Duration::from_secs(5));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sse_dedup_window() -> Result<()> {
+        let c: Config = toml::from_str("")?;
+        assert_eq!(c.config.sse_dedup_window, DEDUP_WINDOW);
+
+        let c: Config = toml::from_str("sse_dedup_window = 8\n")?;
+        assert_eq!(c.config.sse_dedup_window, 8);
+        Ok(())
+    }
+
     #[test]
     fn test_simple_keys() {
         assert_eq!(
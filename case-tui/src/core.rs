@@ -1,27 +1,66 @@
 use color_eyre::{Result, eyre::eyre};
 use crossbeam_channel::Sender;
-use futures::TryStreamExt;
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, LazyLock, Mutex},
+};
 use tokio::spawn;
+use tokio_util::sync::CancellationToken;
 use tracing::debug;
 
-use shared::{Counter, Effect, Event};
+use shared::{Counter, Effect, Event, http::HttpError, types::CaseTree};
 
-use crate::{http, sse};
+use crate::{AppConfig, http, persistence, sse};
 
 pub type Core = Arc<shared::Core<Counter>>;
 
+/// Cancellation tokens for the running SSE subscriptions, keyed by url.
+static SUBSCRIPTIONS: LazyLock<Mutex<HashMap<String, CancellationToken>>> =
+    LazyLock::new(Mutex::default);
+
+/// The settings effects are processed with, see `configure`.
+static CONFIG: LazyLock<Mutex<AppConfig>> = LazyLock::new(Mutex::default);
+
 #[must_use]
 pub fn new() -> Core {
     Arc::new(shared::Core::new())
 }
 
+/// Returns a snapshot of the `CaseTree` currently held by the core.
+#[must_use]
+pub fn core_tree(core: &Core) -> CaseTree {
+    core.view().tree
+}
+
+/// Loads the `CaseTree` saved at `path` into the core, if anything was saved.
+///
+/// # Errors
+///
+/// Can error if the file can't be read, or doesn't hold a `CaseTree`.
+pub async fn load(core: &Core, path: PathBuf) -> Result<()> {
+    let Some(json) = persistence::load_tree(path).await? else {
+        return Ok(());
+    };
+
+    // Nothing is on screen yet, so the render asked for can be dropped.
+    let _ = core.process_event(Event::LoadTree(json));
+
+    core.view()
+        .error
+        .map_or(Ok(()), |e| Err(eyre!("failed to load the saved tree: {e}")))
+}
+
 /// # Errors
 ///
 /// Can error if processing an effect fails.
 pub fn update(core: &Core, event: Event, tx: &Sender<Effect>) -> Result<()> {
     debug!("event: {:?}", event);
 
+    if matches!(event, Event::StopWatch) {
+        cancel_subscriptions();
+    }
+
     for effect in core.process_event(event) {
         process_effect(core, effect, tx)?;
     }
@@ -35,8 +74,9 @@ pub fn process_effect(core: &Core, effect: Effect, tx: &Sender<Effect>) -> Resul
     debug!("effect: {:?}", effect);
 
     match effect {
-        render @ Effect::Render(_) => {
-            tx.send(render).map_err(|e| eyre!("{e:?}"))?;
+        // Both are up to the shell's effect handler, notifications need no answer.
+        effect @ (Effect::Render(_) | Effect::Notify(_)) => {
+            tx.send(effect).map_err(|e| eyre!("{e:?}"))?;
         }
 
         Effect::Http(mut request) => {
@@ -45,7 +85,9 @@ pub fn process_effect(core: &Core, effect: Effect, tx: &Sender<Effect>) -> Resul
                 let tx = tx.clone();
 
                 async move {
-                    let response = http::request(&request.operation).await;
+                    let response = http::request(&request.operation)
+                        .await
+                        .map_err(HttpError::from);
 
                     for effect in core.resolve(&mut request, response.into())? {
                         process_effect(&core, effect, &tx)?;
@@ -62,13 +104,26 @@ pub fn process_effect(core: &Core, effect: Effect, tx: &Sender<Effect>) -> Resul
                 let operation = request.operation.clone();
 
                 async move {
-                    let mut stream = sse::request(&operation).await?;
+                    let token = subscribe(&operation.url);
+                    let config = config();
+
+                    // Reconnects until the user stops watching, so the core's
+                    // request never completes on its own.
+                    sse::watch(
+                        || sse::request(&operation),
+                        &token,
+                        config.sse_timeout(),
+                        sse::Backoff::default(),
+                        sse::Dedup::new(config.sse_dedup_window),
+                        |response| {
+                            for effect in core.resolve(&mut request, response)? {
+                                process_effect(&core, effect, &tx)?;
+                            }
+                            Ok(())
+                        },
+                    )
+                    .await?;
 
-                    while let Ok(Some(response)) = stream.try_next().await {
-                        for effect in core.resolve(&mut request, response)? {
-                            process_effect(&core, effect, &tx)?;
-                        }
-                    }
                     Result::<()>::Ok(())
                 }
             });
@@ -76,3 +131,83 @@ pub fn process_effect(core: &Core, effect: Effect, tx: &Sender<Effect>) -> Resul
     }
     Ok(())
 }
+
+/// Processes the effects from now on with the settings of `config`, e.g. the
+/// SSE inactivity timeout. Subscriptions already running keep theirs.
+///
+/// # Panics
+/// Panics if the lock on the settings was poisoned.
+pub fn configure(config: &AppConfig) {
+    *CONFIG.lock().expect("config lock poisoned") = config.clone();
+}
+
+/// Returns the settings effects are currently processed with.
+fn config() -> AppConfig {
+    CONFIG.lock().expect("config lock poisoned").clone()
+}
+
+/// Registers a new SSE subscription for `url`, cancelling any previous one.
+fn subscribe(url: &str) -> CancellationToken {
+    let token = CancellationToken::new();
+
+    let previous = SUBSCRIPTIONS
+        .lock()
+        .expect("subscriptions lock poisoned")
+        .insert(url.to_owned(), token.clone());
+    if let Some(previous) = previous {
+        previous.cancel();
+    }
+
+    token
+}
+
+/// Cancels every running SSE subscription, so the shell stops reading their streams.
+///
+/// # Panics
+/// Panics if the lock on the subscriptions was poisoned.
+pub fn cancel_subscriptions() {
+    for (_, token) in SUBSCRIPTIONS
+        .lock()
+        .expect("subscriptions lock poisoned")
+        .drain()
+    {
+        token.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossbeam_channel::unbounded;
+    use shared::{Event, types::CaseNode};
+
+    use super::{cancel_subscriptions, core_tree, new, subscribe, update};
+
+    #[test]
+    fn test_subscriptions() {
+        let first = subscribe("http://localhost/sse");
+        let second = subscribe("http://localhost/sse");
+
+        // Re-subscribing to the same url replaces the old subscription.
+        assert!(first.is_cancelled());
+        assert!(!second.is_cancelled());
+
+        cancel_subscriptions();
+        assert!(second.is_cancelled());
+    }
+
+    #[test]
+    fn test_core_tree() {
+        let core = new();
+        let (tx, rx) = unbounded();
+
+        update(&core, Event::CreateTask("write tests".to_owned()), &tx).unwrap();
+        assert!(rx.try_recv().is_ok());
+
+        let tree = core_tree(&core);
+        assert!(
+            tree.iter().any(
+                |(_, node)| matches!(node, CaseNode::Task(task) if task.name() == "write tests")
+            )
+        );
+    }
+}
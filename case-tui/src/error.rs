@@ -0,0 +1,115 @@
+use reqwest::header::ToStrError;
+use shared::http::HttpError;
+use thiserror::Error;
+
+/// The various failures of the TUI's http, sse, render and persistence layers.
+///
+/// Converts into a `color_eyre::Report` through `?`, for the top level.
+///
+/// There is no blanket conversion from `std::io::Error`, whether one is a
+/// render, save or load failure depends on where it came from.
+#[derive(Error, Debug)]
+pub enum TuiError {
+    /// The server took too long to respond.
+    #[error("request timed out")]
+    Timeout,
+
+    /// The connection to the server could not be established, or was lost.
+    #[error("connection failed: {0}")]
+    Connection(String),
+
+    /// The request could not be built, e.g. because of an invalid method or url.
+    #[error("invalid request: {0}")]
+    Request(String),
+
+    /// The response could not be decoded.
+    #[error("failed to decode response: {0}")]
+    Decode(String),
+
+    /// Drawing to the terminal failed.
+    #[error("failed to render: {0}")]
+    Render(std::io::Error),
+
+    /// Saving the user's data to disk failed.
+    #[error("failed to save: {0}")]
+    Save(std::io::Error),
+
+    /// Reading the user's saved data from disk failed.
+    #[error("failed to load: {0}")]
+    Load(std::io::Error),
+}
+
+impl From<reqwest::Error> for TuiError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            Self::Timeout
+        } else if e.is_decode() || e.is_body() {
+            Self::Decode(e.to_string())
+        } else if e.is_builder() {
+            Self::Request(e.to_string())
+        } else {
+            Self::Connection(e.to_string())
+        }
+    }
+}
+
+impl From<ToStrError> for TuiError {
+    fn from(e: ToStrError) -> Self {
+        Self::Decode(e.to_string())
+    }
+}
+
+impl From<TuiError> for HttpError {
+    fn from(e: TuiError) -> Self {
+        match e {
+            TuiError::Request(_) => Self::Url(e.to_string()),
+            TuiError::Decode(_) => Self::Json(e.to_string()),
+            TuiError::Timeout
+            | TuiError::Connection(_)
+            | TuiError::Render(_)
+            | TuiError::Save(_)
+            | TuiError::Load(_) => Self::Io(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, thread, time::Duration};
+
+    use reqwest::{Client, header::HeaderValue};
+
+    use super::TuiError;
+
+    #[tokio::test]
+    async fn test_timeout() {
+        // Accepts connections, but never responds.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            let (_stream, _) = listener.accept().unwrap();
+            thread::sleep(Duration::from_secs(1));
+        });
+
+        let error = Client::builder()
+            .timeout(Duration::from_millis(50))
+            .build()
+            .unwrap()
+            .get(url)
+            .send()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(TuiError::from(error), TuiError::Timeout));
+    }
+
+    #[test]
+    fn test_decode() {
+        let error = HeaderValue::from_bytes(b"\xff")
+            .unwrap()
+            .to_str()
+            .unwrap_err();
+
+        assert!(matches!(TuiError::from(error), TuiError::Decode(_)));
+    }
+}
@@ -1,5 +1,9 @@
 use directories::ProjectDirs;
-use std::{env, path::PathBuf, sync::LazyLock};
+use std::{
+    env,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
 
 static PROJECT_NAME: LazyLock<String> = LazyLock::new(|| "CASE".to_owned());
 
@@ -25,17 +29,23 @@ pub fn get_config_dir() -> PathBuf {
     })
 }
 
+/// Returns the config directory for an instance rooted at `base`, instead of
+/// the process-wide one.
+#[must_use]
+pub fn config_dir_in(base: &Path) -> PathBuf {
+    base.join(".config")
+}
+
 /// Will return the config file if it exists in the config directory.
 #[must_use]
 pub fn get_config_file() -> Option<PathBuf> {
-    let file = {
-        let mut dir = get_config_dir();
-        dir.push("config.toml");
-
-        dir
-    };
+    config_file_in(&get_config_dir())
+}
 
-    file.canonicalize().ok()
+/// Will return the config file if it exists in the given config directory.
+#[must_use]
+pub fn config_file_in(config_dir: &Path) -> Option<PathBuf> {
+    config_dir.join("config.toml").canonicalize().ok()
 }
 
 /// Returns the directory that holds data for the app.
@@ -48,6 +58,13 @@ pub fn get_data_dir() -> PathBuf {
     })
 }
 
+/// Returns the data directory for an instance rooted at `base`, instead of
+/// the process-wide one.
+#[must_use]
+pub fn data_dir_in(base: &Path) -> PathBuf {
+    base.join(".data")
+}
+
 fn project_directory() -> Option<ProjectDirs> {
     ProjectDirs::from("com", "suri", env!("CARGO_PKG_NAME"))
 }
@@ -1,5 +1,8 @@
 use std::fs;
-use std::fs::OpenOptions;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 use tracing_subscriber::{EnvFilter, fmt, prelude::*};
 
@@ -9,6 +12,24 @@ const LOG_FILE_NAME: &str = "case.log";
 
 const LOG_ENV_VAR: &str = "CASE_LOG_LEVEL";
 
+/// Size based rotation settings for the logfile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogRotation {
+    /// The size in bytes the logfile may grow to before it is rotated.
+    pub max_bytes: u64,
+    /// How many rotated logfiles (`case.log.1`, `case.log.2`, ...) to keep.
+    pub generations: usize,
+}
+
+impl Default for LogRotation {
+    fn default() -> Self {
+        Self {
+            max_bytes: 5 * 1024 * 1024,
+            generations: 3,
+        }
+    }
+}
+
 /// Inits logging for the application.
 ///
 /// Creates a logfile in the `data_dir` for the application, respecting
@@ -20,17 +41,19 @@ const LOG_ENV_VAR: &str = "CASE_LOG_LEVEL";
 /// Can error if any part of initialization fails, however
 /// such a case is unlikely.
 pub fn init_logging() -> crate::Result<()> {
-    let directory = get_data_dir();
-    fs::create_dir_all(directory.clone())?;
+    init_logging_in(&get_data_dir(), LogRotation::default())
+}
 
-    let log_path = directory.join(LOG_FILE_NAME);
+/// Inits logging for the application, creating the logfile in `directory`
+/// instead of the `data_dir`, and rotating it according to `rotation`.
+///
+/// # Errors
+/// Can error if any part of initialization fails, e.g. if logging was
+/// already initialized.
+pub fn init_logging_in(directory: &Path, rotation: LogRotation) -> crate::Result<()> {
+    fs::create_dir_all(directory)?;
 
-    let log_file = OpenOptions::new()
-        .read(true)
-        .create(true)
-        .truncate(false)
-        .append(true)
-        .open(log_path)?;
+    let log_file = RotatingFile::open(directory.join(LOG_FILE_NAME), rotation)?;
 
     let env_filter = EnvFilter::builder().with_default_directive(tracing::Level::INFO.into());
 
@@ -43,7 +66,7 @@ pub fn init_logging() -> crate::Result<()> {
 
     let file_subscriber = fmt::layer()
         .with_file(true)
-        .with_writer(log_file)
+        .with_writer(Mutex::new(log_file))
         .with_target(false)
         .with_ansi(false)
         .with_filter(env_filter);
@@ -54,3 +77,126 @@ pub fn init_logging() -> crate::Result<()> {
 
     Ok(())
 }
+
+/// An append-only logfile that rotates itself once it grows past
+/// `LogRotation::max_bytes`.
+struct RotatingFile {
+    path: PathBuf,
+    rotation: LogRotation,
+    file: File,
+    len: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, rotation: LogRotation) -> io::Result<Self> {
+        let file = Self::open_file(&path)?;
+        let len = file.metadata()?.len();
+
+        Ok(Self {
+            path,
+            rotation,
+            file,
+            len,
+        })
+    }
+
+    fn open_file(path: &Path) -> io::Result<File> {
+        OpenOptions::new()
+            .read(true)
+            .create(true)
+            .truncate(false)
+            .append(true)
+            .open(path)
+    }
+
+    fn generation(&self, n: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{n}"));
+        path.into()
+    }
+
+    /// Shifts every rotated logfile up a generation, dropping the oldest, and
+    /// starts a fresh logfile.
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.rotation.generations == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            for n in (1..self.rotation.generations).rev() {
+                let from = self.generation(n);
+                if from.exists() {
+                    fs::rename(from, self.generation(n + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.generation(1))?;
+        }
+
+        self.file = Self::open_file(&self.path)?;
+        self.len = 0;
+
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.len > 0 && self.len + buf.len() as u64 > self.rotation.max_bytes {
+            self.rotate()?;
+        }
+
+        let written = self.file.write(buf)?;
+        self.len += written as u64;
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, io::Write, process};
+
+    use super::{LOG_FILE_NAME, LogRotation, RotatingFile, init_logging_in};
+    use crate::data_dir_in;
+
+    #[test]
+    fn test_init_logging_in() {
+        let base = env::temp_dir().join(format!("case-logging-test-{}", process::id()));
+        let directory = data_dir_in(&base);
+
+        init_logging_in(&directory, LogRotation::default()).unwrap();
+        tracing::info!("logging into a temp dir");
+
+        let log = fs::read_to_string(directory.join(LOG_FILE_NAME)).unwrap();
+        assert!(log.contains("logging into a temp dir"));
+
+        fs::remove_dir_all(base).unwrap();
+    }
+
+    #[test]
+    fn test_rotation() {
+        let directory = env::temp_dir().join(format!("case-rotation-test-{}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+
+        let rotation = LogRotation {
+            max_bytes: 10,
+            generations: 2,
+        };
+        let mut file = RotatingFile::open(directory.join(LOG_FILE_NAME), rotation).unwrap();
+
+        // Every line fills the logfile, so each following line rotates it.
+        for line in ["line 0...\n", "line 1...\n", "line 2...\n", "line 3...\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+
+        let read = |name: &str| fs::read_to_string(directory.join(name)).unwrap();
+        assert_eq!(read("case.log"), "line 3...\n");
+        assert_eq!(read("case.log.1"), "line 2...\n");
+        assert_eq!(read("case.log.2"), "line 1...\n");
+        assert!(!directory.join("case.log.3").exists());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
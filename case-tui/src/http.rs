@@ -1,8 +1,7 @@
 use reqwest::{Client, Method};
-use shared::http::{
-    HttpError, Result,
-    protocol::{HttpHeader, HttpRequest, HttpResponse},
-};
+use shared::http::protocol::{HttpHeader, HttpRequest, HttpResponse};
+
+use crate::TuiError;
 
 pub async fn request(
     HttpRequest {
@@ -11,53 +10,45 @@ pub async fn request(
         headers,
         body,
     }: &HttpRequest,
-) -> Result<HttpResponse> {
+) -> Result<HttpResponse, TuiError> {
     let client = Client::new();
     let method =
-        Method::from_bytes(method.as_bytes()).map_err(|e| HttpError::Url(e.to_string()))?;
+        Method::from_bytes(method.as_bytes()).map_err(|e| TuiError::Request(e.to_string()))?;
 
-    let headers = headers.iter().map(|header| {
-        let name = reqwest::header::HeaderName::from_bytes(header.name.as_bytes())
-            .expect("Invalid header name");
-        let value = reqwest::header::HeaderValue::from_bytes(header.value.as_bytes())
-            .expect("Invalid header value");
+    let headers = headers
+        .iter()
+        .map(|header| -> Result<_, TuiError> {
+            let name = reqwest::header::HeaderName::from_bytes(header.name.as_bytes())
+                .map_err(|e| TuiError::Request(e.to_string()))?;
+            let value = reqwest::header::HeaderValue::from_bytes(header.value.as_bytes())
+                .map_err(|e| TuiError::Request(e.to_string()))?;
 
-        (name, value)
-    });
+            Ok((name, value))
+        })
+        .collect::<Result<reqwest::header::HeaderMap<_>, _>>()?;
 
     let request = client
         .request(method, url)
-        .headers(headers.collect::<reqwest::header::HeaderMap<_>>())
+        .headers(headers)
         .body(body.clone())
-        .build()
-        .map_err(|e| HttpError::Url(e.to_string()))?;
+        .build()?;
 
-    let response = client
-        .execute(request)
-        .await
-        .map_err(|e| HttpError::Io(e.to_string()))?;
+    let response = client.execute(request).await?;
 
     let headers = response
         .headers()
         .iter()
-        .map(|(name, value)| {
-            value
-                .to_str()
-                .map(|v| HttpHeader {
-                    name: name.to_string(),
-                    value: v.to_string(),
-                })
-                .map_err(|e| HttpError::Io(e.to_string()))
+        .map(|(name, value)| -> Result<_, TuiError> {
+            Ok(HttpHeader {
+                name: name.to_string(),
+                value: value.to_str()?.to_string(),
+            })
         })
-        .collect::<Result<Vec<HttpHeader>>>()?;
+        .collect::<Result<Vec<HttpHeader>, _>>()?;
 
     Ok(HttpResponse {
         status: response.status().as_u16(),
         headers,
-        body: response
-            .bytes()
-            .await
-            .map_err(|e| HttpError::Io(e.to_string()))?
-            .to_vec(),
+        body: response.bytes().await?.to_vec(),
     })
 }
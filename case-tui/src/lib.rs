@@ -1,5 +1,9 @@
+pub mod cli;
 pub mod core;
 
+mod error;
+pub use error::TuiError;
+
 mod http;
 mod sse;
 
@@ -13,17 +17,25 @@ pub use widgets::*;
 mod tui;
 pub use tui::{Event as TuiEvent, Tui};
 
+mod term;
+pub use term::{install_panic_hook, restore};
+
 mod config;
 pub use config::*;
 
-pub use color_eyre::{Result, eyre::eyre};
+mod save_state;
+pub use save_state::{SaveState, SaveStatus};
 
-#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub enum Mode {
-    #[default]
-    Home,
-    Explorer,
-}
+pub mod persistence;
+
+mod overdue;
+pub use overdue::OverdueRefresh;
+
+mod redraw;
+pub use redraw::Redraw;
+
+pub use color_eyre::{Result, eyre::eyre};
+pub use shared::Mode;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Action {
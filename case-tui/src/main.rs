@@ -1,20 +1,34 @@
+use std::io::stdout;
 use std::sync::Arc;
 
+use case::Config;
+use case::ConfigWatcher;
+use case::Editor;
+use case::Mode;
+use case::OverdueRefresh;
+use case::Redraw;
+use case::SaveState;
+use case::Toasts;
 use case::Tui;
 use case::TuiEvent;
 use case::TuiViewModel;
+use case::cli;
 use case::core;
 use case::core::Core;
 use case::core::update;
+use case::get_config_dir;
+use case::get_data_dir;
+use case::persistence;
 use clap::Parser;
-use color_eyre::{Result, eyre::eyre};
+use color_eyre::Result;
 use crossbeam_channel::Receiver;
 use crossbeam_channel::Sender;
 use crossbeam_channel::unbounded;
 
-use shared::{Effect, Event};
+use shared::{Effect, Event, ViewModel};
 use tokio::sync::Mutex;
 use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::task::JoinHandle;
 
 #[derive(Parser, Clone)]
 enum Command {
@@ -22,32 +36,37 @@ enum Command {
     Inc,
     Dec,
     Watch,
-}
-
-impl From<Command> for Event {
-    fn from(cmd: Command) -> Self {
-        match cmd {
-            Command::Get => Self::Get,
-            Command::Inc => Self::Increment,
-            Command::Dec => Self::Decrement,
-            Command::Watch => Self::StartWatch,
-        }
-    }
+    /// Prints the task tree.
+    List,
 }
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    /// Runs a single command instead of the TUI.
     #[command(subcommand)]
-    cmd: Command,
+    cmd: Option<Command>,
+
+    /// Prints machine-readable JSON instead of text.
+    #[arg(long, global = true)]
+    json: bool,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     color_eyre::install()?;
+    case::install_panic_hook();
     case::init_logging()?;
 
+    let args = Args::parse();
+    if let Some(cmd) = args.cmd {
+        return run_command(cmd, args.json).await;
+    }
+
+    let config = Arc::new(Config::new()?);
+    core::configure(&config.config);
     let core = core::new();
+    core::load(&core, persistence::tree_file_in(&get_data_dir())).await?;
     let (tx, rx) = unbounded::<Effect>();
 
     let mut tui = Tui::new()?;
@@ -64,6 +83,9 @@ async fn main() -> Result<()> {
     // Do we just slap the TUI inside an Arc<Mutex<>>.
 
     let tui = Arc::new(Mutex::new(tui));
+    let save_state = Arc::new(std::sync::Mutex::new(SaveState::default()));
+    let redraw = Arc::new(Redraw::default());
+    let toasts = Arc::new(std::sync::Mutex::new(Toasts::default()));
 
     // This is the TUI event handler.
     let event_handler = tokio::spawn({
@@ -76,41 +98,146 @@ async fn main() -> Result<()> {
             .expect("The event_rx should not be taken yet.");
 
         let tx = tx.clone();
+        let save_state = save_state.clone();
+        let config = config.clone();
+        let redraw = redraw.clone();
+        let toasts = toasts.clone();
 
-        event_handler(core, tui, tui_event_rx, tx)
+        event_handler(
+            core,
+            tui,
+            tui_event_rx,
+            tx,
+            save_state,
+            config,
+            redraw,
+            toasts,
+        )
     });
 
     let effect_handler = tokio::spawn({
-        let core = core.clone();
-        let tui = tui.clone();
+        let redraw = redraw.clone();
+        let toasts = toasts.clone();
 
-        effect_handler(core, tui, rx)
+        async move { effect_handler(&rx, &redraw, &toasts) }
     });
 
     let res = tokio::select! {
-        result = event_handler => result.unwrap(),
-        result = effect_handler => result.unwrap(),
+        result = event_handler => result?,
+        result = effect_handler => result.map_err(Into::into),
     };
 
     tui.lock().await.exit()?;
     res // If res is Result<(), E>, this propagates the error
 }
 
+/// Runs `cmd` without the TUI, printing its result to stdout.
+///
+/// Commands that talk to the server wait for the confirmed answer, `Watch`
+/// keeps printing every confirmed update.
+async fn run_command(cmd: Command, json: bool) -> Result<()> {
+    let core = core::new();
+
+    let event = match cmd {
+        Command::Get => Event::Get,
+        Command::Inc => Event::Increment,
+        Command::Dec => Event::Decrement,
+        Command::Watch => Event::StartWatch,
+        Command::List => return cli::list(&core, &get_data_dir(), json, &mut stdout()).await,
+    };
+    let watch = matches!(event, Event::StartWatch);
+
+    let (tx, rx) = unbounded::<Effect>();
+    update(&core, event, &tx)?;
+
+    // The effects are resolved on other tasks, so block elsewhere while waiting for them.
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        while let Ok(effect) = rx.recv() {
+            if let Effect::Render(_) = effect {
+                let view = core.view();
+                if !view.confirmed {
+                    continue;
+                }
+
+                cli::write_view(&view, json, &mut stdout())?;
+                if !watch {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    })
+    .await?
+}
+
+// One arm per key and TUI event, splitting it up would only scatter them.
+#[allow(clippy::too_many_arguments, clippy::too_many_lines)]
 async fn event_handler(
     core: Core,
     tui: Arc<Mutex<Tui>>,
     mut tui_event_rx: UnboundedReceiver<TuiEvent>,
     effect_tx: Sender<Effect>,
+    save_state: Arc<std::sync::Mutex<SaveState>>,
+    mut config: Arc<Config>,
+    redraw: Arc<Redraw>,
+    toasts: Arc<std::sync::Mutex<Toasts>>,
 ) -> Result<()> {
+    // The save state last drawn, so ticks only redraw when it changed.
+    let mut shown_save_state = SaveState::default();
+    // The save in flight, if any, so that saves never overlap.
+    let mut saving: Option<JoinHandle<()>> = None;
+    // Flips tasks to overdue as time passes, without any user input.
+    let mut overdue = OverdueRefresh::default();
+    // Applies edits to the config file live, if asked to.
+    let mut config_watcher = config
+        .config
+        .watch_config
+        .then(|| ConfigWatcher::new(get_config_dir()));
+
+    // The description being edited, which takes every key until Esc saves it.
+    let mut editor: Option<Editor> = None;
+
     // What I'm seeing is that this might have to have the ability to fire off render events too?
     while let Some(event) = tui_event_rx.recv().await {
         use crossterm::event::KeyCode;
 
         let event = match event {
+            TuiEvent::Key(key_event) if editor.is_some() => {
+                redraw.request();
+
+                if key_event.code == KeyCode::Esc {
+                    editor.take().map(|editor| editor.save())
+                } else {
+                    if let Some(editor) = editor.as_mut() {
+                        editor.handle_key(key_event);
+                    }
+                    None
+                }
+            }
             case::TuiEvent::Key(key_event) => match key_event.code {
                 KeyCode::Char('j') => Some(Event::Increment),
                 KeyCode::Char('k') => Some(Event::Decrement),
                 KeyCode::Char('g') => Some(Event::Get),
+                KeyCode::Char('w') => Some(Event::StartWatch),
+                KeyCode::Char('s') => Some(Event::StopWatch),
+                KeyCode::Char('u') => Some(Event::Undo),
+                KeyCode::Char('r') => Some(Event::Redo),
+                KeyCode::Char('+') if core.view().mode == Mode::Explorer => {
+                    change_priority(&core.view(), 1)
+                }
+                KeyCode::Char('-') if core.view().mode == Mode::Explorer => {
+                    change_priority(&core.view(), -1)
+                }
+                KeyCode::Char('e') if core.view().mode == Mode::Explorer => {
+                    editor = Editor::open_selected(&core.view());
+                    redraw.request();
+
+                    None
+                }
+                KeyCode::Tab => Some(Event::SwitchMode(match core.view().mode {
+                    Mode::Home => Mode::Explorer,
+                    Mode::Explorer => Mode::Home,
+                })),
                 KeyCode::Char('q') => {
                     // just exit
                     return tui.lock().await.exit();
@@ -119,12 +246,53 @@ async fn event_handler(
                 _ => None,
             },
             TuiEvent::Resize(_, _) => {
-                let view = core.view();
+                redraw.request();
+
+                None
+            }
+            TuiEvent::Render => {
+                if redraw.take() {
+                    shown_save_state = current_save_state(&save_state);
+                    draw(
+                        &core,
+                        &tui,
+                        shown_save_state,
+                        &config,
+                        &toasts,
+                        &mut overdue,
+                        editor.as_ref(),
+                    )
+                    .await?;
+                }
+
+                None
+            }
+            TuiEvent::Tick => {
+                let overdue_changed = overdue.tick(chrono::Local::now().naive_local());
+
+                // Saves share the temporary file, so wait for the last one to finish.
+                if saving.as_ref().is_none_or(JoinHandle::is_finished)
+                    && let Some(generation) = save_state
+                        .lock()
+                        .expect("save state lock poisoned")
+                        .start_save()
+                {
+                    saving = Some(save(&core, save_state.clone(), generation));
+                }
+
+                if toasts.lock().expect("toasts lock poisoned").tick() {
+                    redraw.request();
+                }
 
-                tui.lock()
-                    .await
-                    .draw(|f| f.render_widget(TuiViewModel::from(view), f.area()))
-                    .map_err(|e| eyre!(e.to_string()))?;
+                if let Some(reloaded) = config_watcher.as_mut().and_then(ConfigWatcher::poll) {
+                    core::configure(&reloaded.config);
+                    config = Arc::new(reloaded);
+                    redraw.request();
+                }
+
+                if overdue_changed || current_save_state(&save_state) != shown_save_state {
+                    redraw.request();
+                }
 
                 None
             }
@@ -133,25 +301,93 @@ async fn event_handler(
 
         let Some(event) = event else { continue };
 
+        if SaveState::is_mutating(&event) {
+            save_state.lock().expect("save state lock poisoned").edit();
+        }
+
         update(&core, event, &effect_tx)?;
     }
     Ok(())
 }
 
-async fn effect_handler(
-    core: Core,
-    tui: Arc<Mutex<Tui>>,
-    effect_rx: Receiver<Effect>,
-) -> Result<()> {
-    while let Ok(effect) = effect_rx.recv() {
-        if let Effect::Render(_) = effect {
-            let view = core.view();
+/// Moves the priority of the selected row `delta` steps up, if a row is selected.
+fn change_priority(view: &ViewModel, delta: i8) -> Option<Event> {
+    let node = view.row_ids.get(view.selected?)?.clone();
+    Some(Event::ChangePriority { node, delta })
+}
 
-            tui.lock()
-                .await
-                .draw(|f| f.render_widget(TuiViewModel::from(view), f.area()))
-                .map_err(|e| eyre!(e.to_string()))?;
+/// Handles the effects of the core, drawing is left to the frame timer.
+fn effect_handler(
+    effect_rx: &Receiver<Effect>,
+    redraw: &Redraw,
+    toasts: &std::sync::Mutex<Toasts>,
+) {
+    while let Ok(effect) = effect_rx.recv() {
+        match effect {
+            Effect::Render(_) => redraw.request(),
+            Effect::Notify(request) => {
+                toasts
+                    .lock()
+                    .expect("toasts lock poisoned")
+                    .push(request.operation);
+                redraw.request();
+            }
+            _ => {}
         }
     }
-    Ok(())
+}
+
+/// Saves the current `CaseTree` in the background, moving `save_state` on
+/// once it is done, as the save of `generation`.
+fn save(
+    core: &Core,
+    save_state: Arc<std::sync::Mutex<SaveState>>,
+    generation: u64,
+) -> JoinHandle<()> {
+    let path = persistence::tree_file_in(&get_data_dir());
+    let tree = core::core_tree(core);
+
+    tokio::spawn(async move {
+        let result = persistence::save_tree(path, tree).await;
+        let mut save_state = save_state.lock().expect("save state lock poisoned");
+
+        match result {
+            Ok(()) => save_state.finish_save(generation),
+            Err(e) => {
+                // Try again on the next tick.
+                tracing::error!("{e}");
+                save_state.edit();
+            }
+        }
+    })
+}
+
+fn current_save_state(save_state: &std::sync::Mutex<SaveState>) -> SaveState {
+    *save_state.lock().expect("save state lock poisoned")
+}
+
+/// Draws the current view of the core, with the given `SaveState` in the status bar,
+/// the `Editor` if one is open and the `Toasts` on top.
+///
+/// The due dates are handed to `overdue` on the way, as the view has the whole tree.
+async fn draw(
+    core: &Core,
+    tui: &Mutex<Tui>,
+    save_state: SaveState,
+    config: &Config,
+    toasts: &std::sync::Mutex<Toasts>,
+    overdue: &mut OverdueRefresh,
+    editor: Option<&Editor>,
+) -> Result<()> {
+    let view = core.view();
+    overdue.track(&view.tree, chrono::Local::now().naive_local());
+
+    let view = TuiViewModel::from(view)
+        .with_save_state(save_state)
+        .with_empty_message(config.config.empty_message.clone())
+        .with_overdue(overdue.overdue().to_vec())
+        .with_editor(editor.cloned())
+        .with_toasts(toasts.lock().expect("toasts lock poisoned").clone());
+
+    tui.lock().await.draw_view(view)
 }
@@ -0,0 +1,129 @@
+use chrono::{Duration, NaiveDateTime};
+use shared::types::{CaseNode, CaseTree, NodeId};
+
+/// Keeps track of which tasks are overdue, so the view can be redrawn the
+/// moment a task passes its due date without any user input.
+///
+/// The due dates are taken from the tree whenever it is drawn anyway, see
+/// `track`, so that ticks don't need the tree at all. Even so, the overdue
+/// set is only recomputed once per `interval` rather than on every tick.
+#[derive(Debug, Clone)]
+pub struct OverdueRefresh {
+    interval: Duration,
+    last_check: Option<NaiveDateTime>,
+    /// When each unfinished task is due, as of the last `track`.
+    due: Vec<(NodeId, NaiveDateTime)>,
+    overdue: Vec<NodeId>,
+}
+
+impl Default for OverdueRefresh {
+    fn default() -> Self {
+        Self::new(Duration::seconds(5))
+    }
+}
+
+impl OverdueRefresh {
+    /// Creates a new `OverdueRefresh` that recomputes at most once per `interval`.
+    #[must_use]
+    pub const fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_check: None,
+            due: Vec::new(),
+            overdue: Vec::new(),
+        }
+    }
+
+    /// The overdue tasks as of the last recompute.
+    #[must_use]
+    pub fn overdue(&self) -> &[NodeId] {
+        &self.overdue
+    }
+
+    /// Takes the due dates of the unfinished tasks in `tree`, and which of
+    /// them are overdue at `now`, e.g. right before drawing it.
+    pub fn track(&mut self, tree: &CaseTree, now: NaiveDateTime) {
+        self.due = tree
+            .iter()
+            .filter_map(|(id, node)| match node {
+                CaseNode::Task(task) if !task.is_finished() => task.due().map(|due| (id, due)),
+                _ => None,
+            })
+            .collect();
+        self.overdue = tree.overdue(now);
+        self.last_check = Some(now);
+    }
+
+    /// Recomputes the overdue set at `now` from the tracked due dates, unless
+    /// the last recompute was less than `interval` ago.
+    ///
+    /// Returns whether the overdue set changed, i.e. whether to re-render.
+    pub fn tick(&mut self, now: NaiveDateTime) -> bool {
+        if self
+            .last_check
+            .is_some_and(|last_check| now - last_check < self.interval)
+        {
+            return false;
+        }
+        self.last_check = Some(now);
+
+        let overdue: Vec<NodeId> = self
+            .due
+            .iter()
+            .filter(|(_, due)| *due < now)
+            .map(|(id, _)| id.clone())
+            .collect();
+        if overdue == self.overdue {
+            return false;
+        }
+        self.overdue = overdue;
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDate, NaiveDateTime};
+    use shared::types::{CaseNode, CaseTree, DueDateTime, Group, Task};
+
+    use super::OverdueRefresh;
+
+    #[test]
+    fn test_tick_across_due_boundary() {
+        let due = NaiveDate::from_ymd_opt(2026, 1, 10)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        let at = |seconds| -> NaiveDateTime { due + Duration::seconds(seconds) };
+
+        let mut tree = CaseTree::new(CaseNode::Group(Group::new("root")));
+        let root_id = tree.root_id().unwrap().clone();
+        let mut task = Task::new("report");
+        task.set_due(DueDateTime::parse("2026-01-10 09:00").unwrap());
+        let task_id = tree.insert(CaseNode::Task(task), &root_id).unwrap();
+
+        let mut refresh = OverdueRefresh::new(Duration::seconds(5));
+
+        // Not due yet, nothing to redraw.
+        refresh.track(&tree, at(-6));
+        assert!(!refresh.tick(at(-1)));
+        // Past due, but debounced.
+        assert!(!refresh.tick(at(2)));
+        assert!(refresh.overdue().is_empty());
+
+        assert!(refresh.tick(at(4)));
+        assert_eq!(refresh.overdue(), std::slice::from_ref(&task_id));
+
+        // Still overdue, so nothing changed.
+        assert!(!refresh.tick(at(10)));
+
+        // Finishing it takes effect as soon as the tree is drawn again.
+        if let Ok(CaseNode::Task(task)) = tree.get_mut(&task_id) {
+            task.set_finished(true);
+        }
+        refresh.track(&tree, at(11));
+        assert!(refresh.overdue().is_empty());
+        assert!(!refresh.tick(at(20)));
+    }
+}
@@ -0,0 +1,149 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use shared::types::CaseTree;
+
+use crate::TuiError;
+
+/// The name of the file the `CaseTree` is saved to, inside the data directory.
+pub const TREE_FILE: &str = "tree.json";
+
+/// Returns the path the `CaseTree` is saved to, inside `data_dir`.
+#[must_use]
+pub fn tree_file_in(data_dir: &Path) -> PathBuf {
+    data_dir.join(TREE_FILE)
+}
+
+/// Saves `tree` to `path` without stalling the async runtime.
+///
+/// The write is atomic, see `write_atomic`.
+///
+/// # Errors
+///
+/// Fails if the tree can't be serialized or written.
+pub async fn save_tree(path: PathBuf, tree: CaseTree) -> Result<(), TuiError> {
+    tokio::task::spawn_blocking(move || -> io::Result<()> {
+        let bytes = serde_json::to_vec(&tree)?;
+        write_atomic(&path, &bytes)
+    })
+    .await
+    .map_err(|e| TuiError::Save(io::Error::other(e)))?
+    .map_err(TuiError::Save)
+}
+
+/// Reads the JSON of the `CaseTree` saved at `path` without stalling the
+/// async runtime, or `None` if nothing was saved yet.
+///
+/// # Errors
+///
+/// Fails if `path` exists but can't be read.
+pub async fn load_tree(path: PathBuf) -> Result<Option<String>, TuiError> {
+    tokio::task::spawn_blocking(move || match fs::read_to_string(&path) {
+        Ok(json) => Ok(Some(json)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    })
+    .await
+    .map_err(|e| TuiError::Load(io::Error::other(e)))?
+    .map_err(TuiError::Load)
+}
+
+/// Replaces the contents of `path` with `bytes`, so that a crash leaves
+/// either the old or the new contents, never a mix.
+///
+/// The bytes go to a temporary file next to `path` first, which is then
+/// renamed over it.
+///
+/// # Errors
+///
+/// Fails if the temporary file can't be written, or renamed over `path`.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let temp = temp_path(path);
+    write_synced(&temp, bytes)?;
+    fs::rename(&temp, path)
+}
+
+/// The temporary file `write_atomic` writes to, which has to be on the same
+/// filesystem as `path` for the rename to be atomic.
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+fn write_synced(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use shared::types::{CaseNode, CaseTree, Group};
+
+    use super::{load_tree, save_tree, temp_path, tree_file_in, write_atomic, write_synced};
+
+    /// A directory no other test, or test run, writes to.
+    fn test_dir(name: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("case-persistence-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let path = tree_file_in(&test_dir("write"));
+
+        write_atomic(&path, b"first").unwrap();
+        write_atomic(&path, b"second").unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert!(!temp_path(&path).exists());
+    }
+
+    #[test]
+    fn test_interrupted_write() {
+        let path = tree_file_in(&test_dir("interrupted"));
+        write_atomic(&path, b"saved").unwrap();
+
+        // A crash between writing the temporary file and renaming it.
+        write_synced(&temp_path(&path), b"half wri").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"saved");
+
+        // The leftover is simply overwritten by the next save.
+        write_atomic(&path, b"saved again").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"saved again");
+    }
+
+    #[tokio::test]
+    async fn test_save_tree() {
+        let path = tree_file_in(&test_dir("save"));
+        let mut tree = CaseTree::default();
+        tree.root_or_insert_with(|| CaseNode::Group(Group::new("root")));
+
+        save_tree(path.clone(), tree).await.unwrap();
+
+        let saved: CaseTree = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert!(saved.root_id().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_load_tree() {
+        let path = tree_file_in(&test_dir("load"));
+        assert!(load_tree(path.clone()).await.unwrap().is_none());
+
+        write_atomic(&path, b"{}").unwrap();
+
+        assert_eq!(load_tree(path).await.unwrap().as_deref(), Some("{}"));
+    }
+}
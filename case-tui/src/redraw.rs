@@ -0,0 +1,53 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Coalesces requests to redraw the TUI, so that a burst of them between two
+/// frames ends up as a single draw on the next frame.
+///
+/// Anything that changes what's on screen calls `request`, and only the
+/// frame timer draws, whenever `take` says so.
+#[derive(Debug, Default)]
+pub struct Redraw {
+    dirty: AtomicBool,
+}
+
+impl Redraw {
+    /// Asks for a draw on the next frame.
+    pub fn request(&self) {
+        self.dirty.store(true, Ordering::Release);
+    }
+
+    /// Whether to draw on this frame, i.e. whether a draw was requested since
+    /// the last one.
+    pub fn take(&self) -> bool {
+        self.dirty.swap(false, Ordering::AcqRel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Redraw;
+
+    #[test]
+    fn test_burst_is_drawn_once() {
+        let redraw = Redraw::default();
+        let mut draws = 0;
+
+        // Nothing requested, nothing drawn.
+        draws += usize::from(redraw.take());
+        assert_eq!(draws, 0);
+
+        // A storm of renders within one frame interval.
+        for _ in 0..100 {
+            redraw.request();
+        }
+        for _ in 0..3 {
+            draws += usize::from(redraw.take());
+        }
+        assert_eq!(draws, 1);
+
+        // The next request is drawn on the next frame again.
+        redraw.request();
+        draws += usize::from(redraw.take());
+        assert_eq!(draws, 2);
+    }
+}
@@ -0,0 +1,144 @@
+use shared::Event;
+
+/// Whether the user's work has been persisted, shown in the status bar.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SaveState {
+    status: SaveStatus,
+    /// Counts the saves started, so a save that finishes late can't claim
+    /// the edits of a newer one.
+    generation: u64,
+}
+
+/// Where the user's work is at, see `SaveState`.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SaveStatus {
+    /// Everything is on disk.
+    #[default]
+    Saved,
+    /// A persist effect is in flight.
+    Saving,
+    /// There are edits that have not been persisted yet.
+    Unsaved,
+}
+
+impl SaveState {
+    /// Where the user's work is at.
+    #[must_use]
+    pub const fn status(self) -> SaveStatus {
+        self.status
+    }
+
+    /// The text shown in the status bar for this state.
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self.status {
+            SaveStatus::Saved => "saved ✓",
+            SaveStatus::Saving => "saving…",
+            SaveStatus::Unsaved => "unsaved •",
+        }
+    }
+
+    /// Whether processing `event` changes the user's data.
+    #[must_use]
+    pub const fn is_mutating(event: &Event) -> bool {
+        matches!(
+            event,
+            Event::CreateTask(_)
+                | Event::ChangePriority { .. }
+                | Event::SetDescription { .. }
+                | Event::SetDueDate { .. }
+                | Event::Undo
+                | Event::Redo
+        )
+    }
+
+    /// Marks the work as edited, whatever the current state is.
+    pub const fn edit(&mut self) {
+        self.status = SaveStatus::Unsaved;
+    }
+
+    /// Moves to `Saving` if there is anything to save.
+    ///
+    /// Returns the generation of the save to start, to hand back to
+    /// `finish_save`, or `None` if there is nothing to save.
+    pub const fn start_save(&mut self) -> Option<u64> {
+        if matches!(self.status, SaveStatus::Unsaved) {
+            self.status = SaveStatus::Saving;
+            self.generation += 1;
+            Some(self.generation)
+        } else {
+            None
+        }
+    }
+
+    /// Moves to `Saved` once the persist effect of `generation` completed.
+    ///
+    /// An edit made while saving keeps the state `Unsaved`, since it wasn't
+    /// part of the save, and so does a save older than the latest one.
+    pub const fn finish_save(&mut self, generation: u64) {
+        if matches!(self.status, SaveStatus::Saving) && generation == self.generation {
+            self.status = SaveStatus::Saved;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::Event;
+
+    use super::{SaveState, SaveStatus};
+
+    #[test]
+    fn test_edit_save_cycle() {
+        let mut state = SaveState::default();
+        assert_eq!(state.label(), "saved ✓");
+
+        // Nothing to save yet.
+        assert_eq!(state.start_save(), None);
+
+        assert!(SaveState::is_mutating(&Event::CreateTask(
+            "task".to_owned()
+        )));
+        assert!(!SaveState::is_mutating(&Event::Get));
+        assert!(!SaveState::is_mutating(&Event::Increment));
+        state.edit();
+        assert_eq!(state.status(), SaveStatus::Unsaved);
+        assert_eq!(state.label(), "unsaved •");
+
+        let generation = state.start_save().unwrap();
+        assert_eq!(state.status(), SaveStatus::Saving);
+        assert_eq!(state.label(), "saving…");
+
+        state.finish_save(generation);
+        assert_eq!(state.status(), SaveStatus::Saved);
+    }
+
+    #[test]
+    fn test_edit_while_saving() {
+        let mut state = SaveState::default();
+        state.edit();
+
+        let generation = state.start_save().unwrap();
+        state.edit();
+        state.finish_save(generation);
+
+        assert_eq!(state.status(), SaveStatus::Unsaved);
+    }
+
+    #[test]
+    fn test_overlapping_saves() {
+        let mut state = SaveState::default();
+
+        state.edit();
+        let first = state.start_save().unwrap();
+        state.edit();
+        let second = state.start_save().unwrap();
+
+        // The first save doesn't have the second edit.
+        state.finish_save(first);
+        assert_eq!(state.status(), SaveStatus::Saving);
+
+        state.finish_save(second);
+        assert_eq!(state.status(), SaveStatus::Saved);
+    }
+}
@@ -1,26 +1,38 @@
-use futures::{StreamExt, stream};
+use std::{collections::VecDeque, future::Future, time::Duration};
+
+use futures::{StreamExt, TryStream, TryStreamExt, stream};
 
 use reqwest::{Client, Method};
-use shared::{
-    http::{HttpError, Result},
-    sse::{SseRequest, SseResponse},
-};
+use shared::sse::{SseRequest, SseResponse};
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+use crate::TuiError;
+
+/// How long an SSE stream may stay silent before it is considered stalled,
+/// unless configured otherwise.
+pub const INACTIVITY_TIMEOUT: Duration = Duration::from_mins(1);
+
+/// The delay before the first reconnection attempt, unless the server asks
+/// for another one with a `retry:` field.
+pub const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// The longest delay between two reconnection attempts.
+pub const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// How many of the most recent event ids are remembered to filter out
+/// duplicates.
+pub const DEDUP_WINDOW: usize = 64;
 
 pub async fn request(
     SseRequest { url }: &SseRequest,
-) -> Result<impl futures::TryStream<Ok = SseResponse>> {
+) -> Result<impl futures::TryStream<Ok = SseResponse, Error = TuiError>, TuiError> {
     let client = Client::new();
     let method = Method::from_bytes(b"GET").unwrap();
 
-    let request = client
-        .request(method, url)
-        .build()
-        .map_err(|e| HttpError::Url(e.to_string()))?;
+    let request = client.request(method, url).build()?;
 
-    let response = client
-        .execute(request)
-        .await
-        .map_err(|e| HttpError::Io(e.to_string()))?;
+    let response = client.execute(request).await?;
 
     let body = response.bytes_stream();
 
@@ -32,10 +44,419 @@ pub async fn request(
                     let chunk = SseResponse::Chunk(bytes.to_vec());
                     Ok(Some((chunk, body)))
                 }
-                Err(e) => Err(HttpError::Json(format!(
-                    "failed to read from http response; err = {e:?}"
-                ))),
+                Err(e) => Err(TuiError::from(e)),
             },
         )
     })))
 }
+
+/// Waits for the next item of `stream`, for at most `timeout`.
+///
+/// A stream that stays silent for longer than `timeout` is considered stalled,
+/// and results in `TuiError::Timeout` so the consumer can reconnect.
+pub async fn next_with_timeout<S>(
+    stream: &mut S,
+    timeout: Duration,
+) -> Result<Option<S::Ok>, TuiError>
+where
+    S: TryStream<Error = TuiError> + Unpin,
+{
+    tokio::time::timeout(timeout, stream.try_next())
+        .await
+        .map_err(|_| TuiError::Timeout)?
+}
+
+/// Exponential backoff between reconnection attempts, capped at `max`.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    current: Duration,
+}
+
+impl Backoff {
+    #[must_use]
+    pub const fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            current: initial,
+        }
+    }
+
+    /// Returns the delay to wait before the next attempt, doubling the one
+    /// after it.
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.current;
+        self.current = (self.current * 2).min(self.max);
+        delay
+    }
+
+    /// Starts over from the initial delay, after a successful connection.
+    pub const fn reset(&mut self) {
+        self.current = self.initial;
+    }
+
+    /// Uses the retry interval sent by the server as the initial delay.
+    pub fn set_initial(&mut self, initial: Duration) {
+        self.initial = initial.min(self.max);
+        self.reset();
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(RECONNECT_DELAY, MAX_RECONNECT_DELAY)
+    }
+}
+
+/// Filters out SSE events with an `id:` seen recently, e.g. replayed by the
+/// server after a reconnect.
+///
+/// The last `window` ids are remembered, least recently seen first out.
+/// Events without an id are always passed on.
+///
+/// Events are put back together across chunks, so an event split between
+/// two chunks is passed on once complete.
+#[derive(Debug, Clone)]
+pub struct Dedup {
+    window: usize,
+    seen: VecDeque<String>,
+    /// The start of an event whose end hasn't arrived yet.
+    pending: Vec<u8>,
+}
+
+impl Dedup {
+    #[must_use]
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            seen: VecDeque::with_capacity(window),
+            pending: vec![],
+        }
+    }
+
+    /// Returns the complete events in `chunk` that aren't duplicates, as a
+    /// chunk of their own, or `None` if there are none.
+    pub fn filter(&mut self, chunk: &[u8]) -> Option<Vec<u8>> {
+        self.pending.extend_from_slice(chunk);
+
+        let mut events = vec![];
+        while let Some(len) = event_len(&self.pending) {
+            let event: Vec<u8> = self.pending.drain(..len).collect();
+            if event_id(&event).is_none_or(|id| !self.is_duplicate(id)) {
+                events.extend(event);
+            }
+        }
+
+        (!events.is_empty()).then_some(events)
+    }
+
+    /// Forgets the partial event of a lost connection, the ids seen are kept
+    /// to filter what the next one replays.
+    pub fn reconnected(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Whether `id` was seen within the window, remembering it either way.
+    fn is_duplicate(&mut self, id: String) -> bool {
+        let seen = self.seen.iter().position(|seen| *seen == id);
+        if let Some(index) = seen {
+            self.seen.remove(index);
+        } else if self.seen.len() == self.window {
+            self.seen.pop_front();
+        }
+
+        if self.window > 0 {
+            self.seen.push_back(id);
+        }
+        seen.is_some()
+    }
+}
+
+impl Default for Dedup {
+    fn default() -> Self {
+        Self::new(DEDUP_WINDOW)
+    }
+}
+
+/// Returns the length of the first complete event in `buf`, up to and
+/// including the blank line ending it.
+fn event_len(buf: &[u8]) -> Option<usize> {
+    let mut line_start = 0;
+    for (index, byte) in buf.iter().enumerate() {
+        if *byte != b'\n' {
+            continue;
+        }
+
+        if matches!(&buf[line_start..index], [] | [b'\r']) {
+            return Some(index + 1);
+        }
+        line_start = index + 1;
+    }
+
+    None
+}
+
+/// Returns the value of the `id:` field of `event`, if any.
+fn event_id(event: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(event)
+        .lines()
+        .filter_map(|line| line.strip_prefix("id:"))
+        .next_back()
+        .map(|id| id.strip_prefix(' ').unwrap_or(id).to_owned())
+}
+
+/// Returns the reconnection time of a `retry:` field in `chunk`, if any.
+fn retry_interval(chunk: &[u8]) -> Option<Duration> {
+    String::from_utf8_lossy(chunk)
+        .lines()
+        .filter_map(|line| line.strip_prefix("retry:"))
+        .find_map(|millis| millis.trim().parse().ok())
+        .map(Duration::from_millis)
+}
+
+/// Keeps an SSE subscription alive, passing every response to `on_response`.
+///
+/// Whenever the stream ends, fails or stays silent for `inactivity_timeout`,
+/// `connect` is called again after waiting according to `backoff`. Only
+/// cancelling `token` stops watching, so the core never sees the stream end.
+/// Events replayed by the new connection are filtered out by `dedup`.
+///
+/// # Errors
+///
+/// Fails if `on_response` does.
+pub async fn watch<C, Fut, S, F>(
+    mut connect: C,
+    token: &CancellationToken,
+    inactivity_timeout: Duration,
+    mut backoff: Backoff,
+    mut dedup: Dedup,
+    mut on_response: F,
+) -> color_eyre::Result<()>
+where
+    C: FnMut() -> Fut,
+    Fut: Future<Output = Result<S, TuiError>>,
+    S: TryStream<Ok = SseResponse, Error = TuiError> + Unpin,
+    F: FnMut(SseResponse) -> color_eyre::Result<()>,
+{
+    loop {
+        let connection = tokio::select! {
+            () = token.cancelled() => return Ok(()),
+            connection = connect() => connection,
+        };
+
+        match connection {
+            Ok(mut stream) => loop {
+                let next = tokio::select! {
+                    () = token.cancelled() => return Ok(()),
+                    next = next_with_timeout(&mut stream, inactivity_timeout) => next,
+                };
+
+                match next {
+                    Ok(Some(response)) => {
+                        if let SseResponse::Chunk(chunk) = &response
+                            && let Some(retry) = retry_interval(chunk)
+                        {
+                            backoff.set_initial(retry);
+                        }
+                        backoff.reset();
+
+                        let response = match response {
+                            SseResponse::Chunk(chunk) => match dedup.filter(&chunk) {
+                                Some(events) => SseResponse::Chunk(events),
+                                None => continue,
+                            },
+                            response => response,
+                        };
+                        on_response(response)?;
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        warn!("sse stream failed: {e}");
+                        break;
+                    }
+                }
+            },
+            Err(e) => warn!("failed to connect to the sse stream: {e}"),
+        }
+        dedup.reconnected();
+
+        tokio::select! {
+            () = token.cancelled() => return Ok(()),
+            () = tokio::time::sleep(backoff.next_delay()) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::{StreamExt, stream};
+    use shared::sse::SseResponse;
+    use tokio_util::sync::CancellationToken;
+
+    use super::{Backoff, Dedup, INACTIVITY_TIMEOUT, next_with_timeout, retry_interval, watch};
+    use crate::TuiError;
+
+    #[tokio::test]
+    async fn test_stalled_stream_times_out() {
+        let mut stalled = stream::pending::<Result<SseResponse, TuiError>>();
+
+        let next = next_with_timeout(&mut stalled, Duration::from_millis(10)).await;
+
+        assert!(matches!(next, Err(TuiError::Timeout)));
+    }
+
+    #[tokio::test]
+    async fn test_live_stream() {
+        let mut live = stream::iter([Ok::<_, TuiError>(SseResponse::Done)]);
+
+        let next = next_with_timeout(&mut live, Duration::from_millis(10)).await;
+
+        assert!(matches!(next, Ok(Some(SseResponse::Done))));
+        assert!(matches!(
+            next_with_timeout(&mut live, Duration::from_millis(10)).await,
+            Ok(None)
+        ));
+    }
+
+    #[test]
+    fn test_backoff() {
+        let mut backoff = Backoff::new(Duration::from_millis(100), Duration::from_millis(300));
+
+        assert_eq!(backoff.next_delay(), Duration::from_millis(100));
+        assert_eq!(backoff.next_delay(), Duration::from_millis(200));
+        assert_eq!(backoff.next_delay(), Duration::from_millis(300));
+        assert_eq!(backoff.next_delay(), Duration::from_millis(300));
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_millis(100));
+
+        backoff.set_initial(Duration::from_secs(5));
+        assert_eq!(backoff.next_delay(), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn test_retry_interval() {
+        assert_eq!(
+            retry_interval(b"retry: 2500\ndata: {}\n\n"),
+            Some(Duration::from_millis(2500))
+        );
+        assert_eq!(retry_interval(b"data: {}\n\n"), None);
+    }
+
+    #[tokio::test]
+    async fn test_watch_reconnects() {
+        let token = CancellationToken::new();
+        let mut connections = 0;
+        let mut responses = vec![];
+
+        watch(
+            || {
+                connections += 1;
+                let connection = connections;
+                let token = token.clone();
+
+                async move {
+                    if connection == 1 {
+                        // The server restarts after a single event.
+                        Ok(
+                            stream::iter(vec![Ok(SseResponse::Chunk(b"data: 1\n\n".to_vec()))])
+                                .boxed(),
+                        )
+                    } else {
+                        // The user stops watching once we're back.
+                        token.cancel();
+                        Ok(stream::pending().boxed())
+                    }
+                }
+            },
+            &token,
+            INACTIVITY_TIMEOUT,
+            Backoff::new(Duration::from_millis(1), Duration::from_millis(1)),
+            Dedup::default(),
+            |response| {
+                responses.push(response);
+                Ok(())
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(connections, 2);
+        assert_eq!(responses, vec![SseResponse::Chunk(b"data: 1\n\n".to_vec())]);
+    }
+
+    #[tokio::test]
+    async fn test_watch_drops_replayed_events() {
+        let token = CancellationToken::new();
+        let mut connections = 0;
+        let mut responses = vec![];
+
+        watch(
+            || {
+                connections += 1;
+                let connection = connections;
+                let token = token.clone();
+
+                async move {
+                    let chunks: Vec<&[u8]> = match connection {
+                        1 => vec![b"id: 1\ndata: a\n\n"],
+                        // The server replays the last event on reconnect.
+                        2 => vec![b"id: 1\ndata: a\n\nid: 2\n", b"data: b\n\n"],
+                        _ => {
+                            token.cancel();
+                            return Ok(stream::pending().boxed());
+                        }
+                    };
+
+                    Ok(stream::iter(
+                        chunks
+                            .into_iter()
+                            .map(|chunk| Ok(SseResponse::Chunk(chunk.to_vec()))),
+                    )
+                    .boxed())
+                }
+            },
+            &token,
+            INACTIVITY_TIMEOUT,
+            Backoff::new(Duration::from_millis(1), Duration::from_millis(1)),
+            Dedup::default(),
+            |response| {
+                responses.push(response);
+                Ok(())
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            responses,
+            vec![
+                SseResponse::Chunk(b"id: 1\ndata: a\n\n".to_vec()),
+                SseResponse::Chunk(b"id: 2\ndata: b\n\n".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dedup_window() {
+        let mut dedup = Dedup::new(2);
+        let event = |id: &str| format!("id: {id}\ndata: {{}}\n\n").into_bytes();
+
+        assert!(dedup.filter(&event("1")).is_some());
+        assert!(dedup.filter(&event("2")).is_some());
+        assert!(dedup.filter(&event("1")).is_none());
+
+        // 2 was seen least recently, so 3 pushes it out of the window.
+        assert!(dedup.filter(&event("3")).is_some());
+        assert!(dedup.filter(&event("2")).is_some());
+        assert!(dedup.filter(&event("3")).is_none());
+
+        // Events without an id always pass.
+        assert!(dedup.filter(b"data: {}\n\n").is_some());
+        assert!(dedup.filter(b"data: {}\n\n").is_some());
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    io::stdout,
+    panic,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use color_eyre::Result;
+use crossterm::{
+    cursor,
+    event::{DisableBracketedPaste, DisableMouseCapture},
+    terminal::LeaveAlternateScreen,
+};
+use tracing::error;
+
+/// Whether the terminal is in the raw, alternate screen mode of the TUI.
+///
+/// Both `Tui::exit` and the panic hook restore the terminal through
+/// `restore_with`, so that between them it happens exactly once per
+/// `Tui::enter`, whichever comes first.
+#[derive(Debug, Default)]
+pub struct TerminalState {
+    raw: AtomicBool,
+}
+
+/// The state of the actual terminal.
+pub static TERMINAL: TerminalState = TerminalState::new();
+
+impl TerminalState {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            raw: AtomicBool::new(false),
+        }
+    }
+
+    /// Marks the terminal as entered, i.e. in need of restoring.
+    pub fn enter(&self) {
+        self.raw.store(true, Ordering::Release);
+    }
+
+    /// Runs `restore` if the terminal is entered, returning whether it did.
+    ///
+    /// # Errors
+    /// Errors if `restore` does, the terminal counts as restored regardless
+    /// since there's no point in trying twice.
+    pub fn restore_with(&self, restore: impl FnOnce() -> Result<()>) -> Result<bool> {
+        if !self.raw.swap(false, Ordering::AcqRel) {
+            return Ok(false);
+        }
+
+        restore().map(|()| true)
+    }
+}
+
+/// Restores the terminal if the TUI left it in raw mode, without knowing
+/// how it was entered, so everything the TUI may have enabled is disabled.
+///
+/// # Errors
+/// Errors if writing to the terminal fails.
+pub fn restore() -> Result<bool> {
+    TERMINAL.restore_with(|| {
+        crossterm::execute!(
+            stdout(),
+            DisableBracketedPaste,
+            DisableMouseCapture,
+            LeaveAlternateScreen,
+            cursor::Show
+        )?;
+        crossterm::terminal::disable_raw_mode()?;
+        Ok(())
+    })
+}
+
+/// Restores the terminal before any panic is reported, so that the report
+/// is readable and the shell usable, wherever the panic happened.
+///
+/// Chains onto the current panic hook, so call this after
+/// `color_eyre::install`.
+pub fn install_panic_hook() {
+    chain_panic_hook(|| {
+        if let Err(e) = restore() {
+            error!("failed to restore the terminal: {e}");
+        }
+    });
+}
+
+/// Sets a panic hook calling `before` and then the current hook.
+fn chain_panic_hook(before: impl Fn() + Send + Sync + 'static) {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        before();
+        hook(info);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::{TerminalState, chain_panic_hook};
+
+    #[tokio::test]
+    async fn test_panic_in_event_handler_restores_once() {
+        static STATE: TerminalState = TerminalState::new();
+        static RESTORES: AtomicUsize = AtomicUsize::new(0);
+        let restore = || {
+            RESTORES.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        };
+
+        STATE.enter();
+        chain_panic_hook(move || {
+            STATE.restore_with(restore).unwrap();
+        });
+
+        let event_handler = tokio::spawn(async {
+            panic!("simulated panic in the event handler");
+        });
+        assert!(event_handler.await.unwrap_err().is_panic());
+
+        assert!(!STATE.raw.load(Ordering::SeqCst));
+        assert_eq!(RESTORES.load(Ordering::SeqCst), 1);
+
+        // `Tui::exit` running afterwards leaves the terminal alone.
+        assert!(!STATE.restore_with(restore).unwrap());
+        assert_eq!(RESTORES.load(Ordering::SeqCst), 1);
+    }
+}
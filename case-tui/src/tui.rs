@@ -16,7 +16,7 @@ use crossterm::{
     terminal::{EnterAlternateScreen, LeaveAlternateScreen},
 };
 use futures::{FutureExt, StreamExt};
-use ratatui::backend::CrosstermBackend as Backend;
+use ratatui::{Terminal, backend::CrosstermBackend as Backend};
 use serde::{Deserialize, Serialize};
 use tokio::{
     sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
@@ -26,6 +26,8 @@ use tokio::{
 use tokio_util::sync::CancellationToken;
 use tracing::error;
 
+use crate::{TuiError, TuiViewModel, term::TERMINAL};
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Event {
     Init,
@@ -33,6 +35,8 @@ pub enum Event {
     Error,
     Closed,
     Tick,
+    /// Sent `frame_rate` times per second, the only time to draw.
+    Render,
     FocusGained,
     FocusLost,
     Paste(String),
@@ -42,7 +46,7 @@ pub enum Event {
 }
 
 pub struct Tui {
-    pub terminal: ratatui::Terminal<Backend<Stdout>>,
+    pub terminal: Terminal<Backend<Stdout>>,
     pub task: JoinHandle<()>,
     pub cancellation_token: CancellationToken,
     pub event_rx: Option<UnboundedReceiver<Event>>,
@@ -58,7 +62,7 @@ impl Tui {
     pub fn new() -> Result<Self> {
         let (event_tx, event_rx) = mpsc::unbounded_channel();
         Ok(Self {
-            terminal: ratatui::Terminal::new(Backend::new(stdout()))?,
+            terminal: Terminal::new(Backend::new(stdout()))?,
             task: tokio::spawn(async {}),
             cancellation_token: CancellationToken::new(),
             event_rx: Some(event_rx),
@@ -101,6 +105,7 @@ impl Tui {
             self.event_tx.clone(),
             self.cancellation_token.clone(),
             self.tick_rate,
+            self.frame_rate,
         );
         self.task = tokio::spawn(async {
             event_loop.await;
@@ -111,9 +116,11 @@ impl Tui {
         event_tx: UnboundedSender<Event>,
         cancellation_token: CancellationToken,
         tick_rate: f64,
+        frame_rate: f64,
     ) {
         let mut event_stream = EventStream::new();
         let mut tick_interval = interval(Duration::from_secs_f64(1.0 / tick_rate));
+        let mut render_interval = interval(Duration::from_secs_f64(1.0 / frame_rate));
 
         // If this fails, then it's likely a bug in the calling code.
         event_tx
@@ -125,6 +132,7 @@ impl Tui {
                     break;
                 }
                 _ = tick_interval.tick() => Event::Tick,
+                _ = render_interval.tick() => Event::Render,
                 crossterm_event = event_stream.next().fuse() => match crossterm_event {
                     Some(Ok(event)) => match event {
                         CrosstermEvent::Key(key) if key.kind == KeyEventKind::Press => Event::Key(key),
@@ -169,6 +177,7 @@ impl Tui {
     #[allow(clippy::missing_errors_doc)]
     pub fn enter(&mut self) -> Result<()> {
         crossterm::terminal::enable_raw_mode()?;
+        TERMINAL.enter();
         crossterm::execute!(stdout(), EnterAlternateScreen, cursor::Hide)?;
         if self.mouse {
             crossterm::execute!(stdout(), EnableMouseCapture)?;
@@ -183,7 +192,7 @@ impl Tui {
     #[allow(clippy::missing_errors_doc)]
     pub fn exit(&mut self) -> Result<()> {
         self.stop()?;
-        if crossterm::terminal::is_raw_mode_enabled()? {
+        TERMINAL.restore_with(|| {
             self.flush()?;
             if self.paste {
                 crossterm::execute!(stdout(), DisableBracketedPaste)?;
@@ -193,7 +202,8 @@ impl Tui {
             }
             crossterm::execute!(stdout(), LeaveAlternateScreen, cursor::Show)?;
             crossterm::terminal::disable_raw_mode()?;
-        }
+            Ok(())
+        })?;
         Ok(())
     }
 
@@ -227,10 +237,29 @@ impl Tui {
     pub const fn take_event_rx(&mut self) -> Option<UnboundedReceiver<Event>> {
         self.event_rx.take()
     }
+
+    /// Draws a frame of `view`, the widgets shown depending on its `Mode`.
+    ///
+    /// # Errors
+    /// Errors with `TuiError::Render` if writing to the terminal fails.
+    pub fn draw_view(&mut self, view: impl Into<TuiViewModel>) -> Result<()> {
+        draw_view(&mut self.terminal, view).map_err(TuiError::Render)?;
+        Ok(())
+    }
+}
+
+/// Draws a frame of `view` on `terminal`, whatever its backend, see `Tui::draw_view`.
+fn draw_view<B: ratatui::backend::Backend>(
+    terminal: &mut Terminal<B>,
+    view: impl Into<TuiViewModel>,
+) -> Result<(), B::Error> {
+    let view = view.into();
+    terminal.draw(|f| f.render_widget(view, f.area()))?;
+    Ok(())
 }
 
 impl Deref for Tui {
-    type Target = ratatui::Terminal<Backend<Stdout>>;
+    type Target = Terminal<Backend<Stdout>>;
 
     fn deref(&self) -> &Self::Target {
         &self.terminal
@@ -245,6 +274,51 @@ impl DerefMut for Tui {
 
 impl Drop for Tui {
     fn drop(&mut self) {
-        self.exit().unwrap();
+        // Panicking here while unwinding would abort, so only log.
+        if let Err(e) = self.exit() {
+            error!("failed to restore the terminal: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{Terminal, backend::TestBackend};
+    use shared::{
+        Mode, ViewModel,
+        notify::{Level, NotifyOperation},
+        types::{CaseNode, CaseTree, Group},
+    };
+
+    use super::draw_view;
+    use crate::{Toasts, TuiViewModel};
+
+    #[test]
+    fn test_draw_view() {
+        let mut terminal = Terminal::new(TestBackend::new(60, 10)).unwrap();
+        let mut toasts = Toasts::default();
+        toasts.push(NotifyOperation {
+            level: Level::Info,
+            message: "Task created".to_owned(),
+        });
+        let view = ViewModel {
+            tree: CaseTree::new(CaseNode::Group(Group::new("root"))),
+            rows: vec!["▾ root".to_owned()],
+            selected: Some(0),
+            mode: Mode::Explorer,
+            ..ViewModel::default()
+        };
+
+        draw_view(&mut terminal, TuiViewModel::from(view).with_toasts(toasts)).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let rendered = buffer
+            .content()
+            .iter()
+            .map(ratatui::buffer::Cell::symbol)
+            .collect::<String>();
+        assert!(rendered.contains("CASE"));
+        assert!(rendered.contains("▾ root"));
+        assert!(rendered.contains("Task created"));
     }
 }
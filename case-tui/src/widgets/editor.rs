@@ -0,0 +1,346 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Paragraph},
+};
+use shared::{
+    Event, ViewModel,
+    types::{CaseNode, NodeId},
+};
+
+/// A multi-line text editor for the description of a task.
+///
+/// Lines are never wrapped, long ones scroll horizontally to keep the cursor
+/// in view instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Editor {
+    node: NodeId,
+    lines: Vec<String>,
+    /// The line the cursor is on.
+    row: usize,
+    /// The cursor's position in its line, in chars.
+    col: usize,
+}
+
+impl Editor {
+    /// Opens an editor on the `text` of `node`, with the cursor at the end.
+    #[must_use]
+    pub fn open(node: NodeId, text: &str) -> Self {
+        let lines: Vec<String> = text.split('\n').map(str::to_owned).collect();
+        let row = lines.len() - 1;
+        let col = lines[row].chars().count();
+
+        Self {
+            node,
+            lines,
+            row,
+            col,
+        }
+    }
+
+    /// Opens an editor on the selected task of `view`, if a task is selected.
+    #[must_use]
+    pub fn open_selected(view: &ViewModel) -> Option<Self> {
+        let id = view.row_ids.get(view.selected?)?;
+
+        match view.tree.get(id) {
+            Ok(CaseNode::Task(task)) => Some(Self::open(id.clone(), task.description())),
+            _ => None,
+        }
+    }
+
+    /// Returns the edited text, with lines joined by `\n`.
+    #[must_use]
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// Returns the cursor position as `(row, col)`, in chars.
+    #[must_use]
+    pub const fn cursor(&self) -> (usize, usize) {
+        (self.row, self.col)
+    }
+
+    /// Returns the event writing the edited text back to the task.
+    #[must_use]
+    pub fn save(&self) -> Event {
+        Event::SetDescription {
+            node: self.node.clone(),
+            description: self.text(),
+        }
+    }
+
+    /// Applies a key press, returning whether the editor used it.
+    ///
+    /// Saving and closing are up to the caller, e.g. on `Esc`.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char(c) => self.insert_char(c),
+            KeyCode::Enter => self.insert_newline(),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Left => self.move_left(),
+            KeyCode::Right => self.move_right(),
+            KeyCode::Up => self.move_up(),
+            KeyCode::Down => self.move_down(),
+            KeyCode::Home => self.col = 0,
+            KeyCode::End => self.col = self.line_len(),
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// Inserts `c` at the cursor.
+    pub fn insert_char(&mut self, c: char) {
+        let index = self.byte_index();
+        self.lines[self.row].insert(index, c);
+        self.col += 1;
+    }
+
+    /// Splits the line at the cursor, moving to the start of the new line.
+    pub fn insert_newline(&mut self) {
+        let index = self.byte_index();
+        let rest = self.lines[self.row].split_off(index);
+
+        self.row += 1;
+        self.col = 0;
+        self.lines.insert(self.row, rest);
+    }
+
+    /// Deletes the char before the cursor, joining with the previous line
+    /// at the start of a line.
+    pub fn backspace(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+            let index = self.byte_index();
+            self.lines[self.row].remove(index);
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.line_len();
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    /// Moves the cursor one char left, wrapping to the end of the previous line.
+    pub fn move_left(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.col = self.line_len();
+        }
+    }
+
+    /// Moves the cursor one char right, wrapping to the start of the next line.
+    pub fn move_right(&mut self) {
+        if self.col < self.line_len() {
+            self.col += 1;
+        } else if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = 0;
+        }
+    }
+
+    /// Moves the cursor one line up, keeping its column where possible.
+    pub fn move_up(&mut self) {
+        if self.row > 0 {
+            self.row -= 1;
+            self.col = self.col.min(self.line_len());
+        }
+    }
+
+    /// Moves the cursor one line down, keeping its column where possible.
+    pub fn move_down(&mut self) {
+        if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = self.col.min(self.line_len());
+        }
+    }
+
+    fn line_len(&self) -> usize {
+        self.lines[self.row].chars().count()
+    }
+
+    /// The byte index of the cursor in its line.
+    fn byte_index(&self) -> usize {
+        let line = &self.lines[self.row];
+        line.char_indices()
+            .nth(self.col)
+            .map_or(line.len(), |(index, _)| index)
+    }
+}
+
+impl Widget for &Editor {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::bordered().title_top(Line::from("Description").centered());
+        let inner = block.inner(area);
+
+        // Scroll just enough to keep the cursor inside the editor.
+        let scroll_y = self
+            .row
+            .saturating_sub(usize::from(inner.height.saturating_sub(1)));
+        let scroll_x = self
+            .col
+            .saturating_sub(usize::from(inner.width.saturating_sub(1)));
+        let to_u16 = |n: usize| u16::try_from(n).unwrap_or(u16::MAX);
+
+        Paragraph::new(
+            self.lines
+                .iter()
+                .map(String::as_str)
+                .map(Line::from)
+                .collect::<Vec<_>>(),
+        )
+        .block(block)
+        .scroll((to_u16(scroll_y), to_u16(scroll_x)))
+        .render(area, buf);
+
+        let cursor = Position::new(
+            inner.x.saturating_add(to_u16(self.col - scroll_x)),
+            inner.y.saturating_add(to_u16(self.row - scroll_y)),
+        );
+        if inner.contains(cursor)
+            && let Some(cell) = buf.cell_mut(cursor)
+        {
+            cell.set_style(Style::new().reversed());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use ratatui::{Terminal, backend::TestBackend};
+    use shared::{
+        Event, Mode, ViewModel,
+        types::{CaseNode, CaseTree, Group, NodeId, Task},
+    };
+
+    use super::Editor;
+
+    fn task_id() -> NodeId {
+        CaseTree::new(CaseNode::Task(Task::new("task")))
+            .root_id()
+            .unwrap()
+            .clone()
+    }
+
+    fn press(editor: &mut Editor, code: KeyCode) {
+        assert!(editor.handle_key(KeyEvent::new(code, KeyModifiers::empty())));
+    }
+
+    fn type_str(editor: &mut Editor, s: &str) {
+        for c in s.chars() {
+            press(editor, KeyCode::Char(c));
+        }
+    }
+
+    fn render(editor: &Editor, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal
+            .draw(|f| f.render_widget(editor, f.area()))
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(usize::from(buffer.area.width))
+            .map(|row| row.iter().map(ratatui::buffer::Cell::symbol).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_multi_line_entry() {
+        let mut editor = Editor::open(task_id(), "");
+
+        type_str(&mut editor, "first");
+        press(&mut editor, KeyCode::Enter);
+        type_str(&mut editor, "second");
+        press(&mut editor, KeyCode::Enter);
+        type_str(&mut editor, "third");
+        assert_eq!(editor.text(), "first\nsecond\nthird");
+        assert_eq!(editor.cursor(), (2, 5));
+
+        // Splitting a line in the middle.
+        press(&mut editor, KeyCode::Up);
+        press(&mut editor, KeyCode::Left);
+        press(&mut editor, KeyCode::Enter);
+        assert_eq!(editor.text(), "first\nseco\nnd\nthird");
+
+        // And joining it back with backspace.
+        press(&mut editor, KeyCode::Backspace);
+        assert_eq!(editor.text(), "first\nsecond\nthird");
+        assert_eq!(editor.cursor(), (1, 4));
+
+        // Right wraps to the next line, backspace deletes across it.
+        press(&mut editor, KeyCode::End);
+        press(&mut editor, KeyCode::Right);
+        press(&mut editor, KeyCode::Backspace);
+        press(&mut editor, KeyCode::Backspace);
+        assert_eq!(editor.text(), "first\nseconthird");
+
+        assert_eq!(
+            editor.save(),
+            Event::SetDescription {
+                node: task_id(),
+                description: "first\nseconthird".to_owned(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_open_existing() {
+        let mut editor = Editor::open(task_id(), "naïve\ncafé");
+        assert_eq!(editor.cursor(), (1, 4));
+
+        press(&mut editor, KeyCode::Up);
+        press(&mut editor, KeyCode::Backspace);
+        type_str(&mut editor, "ï!");
+        assert_eq!(editor.text(), "naïï!e\ncafé");
+    }
+
+    #[test]
+    fn test_horizontal_scroll() {
+        let mut editor = Editor::open(task_id(), "short");
+        press(&mut editor, KeyCode::Enter);
+        type_str(&mut editor, "0123456789abcdefghij");
+
+        // 8 columns inside the border, so only the end of the long line shows.
+        let rows = render(&editor, 10, 4);
+        assert_eq!(rows[2], "│defghij │");
+
+        press(&mut editor, KeyCode::Home);
+        let rows = render(&editor, 10, 4);
+        assert_eq!(rows[1], "│short   │");
+        assert_eq!(rows[2], "│01234567│");
+    }
+
+    #[test]
+    fn test_open_selected() {
+        let mut tree = CaseTree::new(CaseNode::Group(Group::new("root")));
+        let root_id = tree.root_id().unwrap().clone();
+        let mut task = Task::new("report");
+        task.set_description("draft");
+        let task_id = tree.insert(CaseNode::Task(task), &root_id).unwrap();
+
+        let mut view = ViewModel {
+            tree,
+            rows: vec!["▾ root".to_owned(), "  report".to_owned()],
+            row_ids: vec![root_id, task_id.clone()],
+            mode: Mode::Explorer,
+            ..ViewModel::default()
+        };
+        assert_eq!(Editor::open_selected(&view), None);
+
+        // Groups have no description to edit.
+        view.selected = Some(0);
+        assert_eq!(Editor::open_selected(&view), None);
+
+        view.selected = Some(1);
+        assert_eq!(
+            Editor::open_selected(&view),
+            Some(Editor::open(task_id, "draft"))
+        );
+    }
+}
@@ -1,29 +1,294 @@
 use ratatui::{
     prelude::*,
-    widgets::{Block, Paragraph, Wrap},
+    widgets::{Block, List, ListItem, ListState, Paragraph, Wrap},
 };
-use shared::ViewModel;
+use shared::{Mode, ViewModel, types::NodeId};
+
+use crate::SaveState;
+
+mod editor;
+pub use editor::Editor;
+
+mod priority;
+pub use priority::priority_color;
+
+mod toast;
+pub use toast::{TOAST_TTL, Toast, Toasts};
+
+/// Shown in place of the tree while it has no nodes, unless configured otherwise.
+pub const DEFAULT_EMPTY_MESSAGE: &str = "No tasks yet — press n to add one";
 
 impl From<ViewModel> for TuiViewModel {
     fn from(value: ViewModel) -> Self {
-        Self(value)
+        Self {
+            view_model: value,
+            save_state: SaveState::default(),
+            empty_message: DEFAULT_EMPTY_MESSAGE.to_owned(),
+            toasts: Toasts::default(),
+            overdue: Vec::new(),
+            editor: None,
+        }
     }
 }
 
-pub struct TuiViewModel(shared::ViewModel);
+pub struct TuiViewModel {
+    view_model: shared::ViewModel,
+    save_state: SaveState,
+    empty_message: String,
+    toasts: Toasts,
+    overdue: Vec<NodeId>,
+    editor: Option<Editor>,
+}
+
+impl TuiViewModel {
+    /// Sets the `SaveState` shown in the status bar.
+    #[must_use]
+    pub const fn with_save_state(mut self, save_state: SaveState) -> Self {
+        self.save_state = save_state;
+        self
+    }
+
+    /// Sets the message shown while the tree is empty.
+    #[must_use]
+    pub fn with_empty_message(mut self, empty_message: impl Into<String>) -> Self {
+        self.empty_message = empty_message.into();
+        self
+    }
+
+    /// Sets the tasks shown as overdue.
+    #[must_use]
+    pub fn with_overdue(mut self, overdue: Vec<NodeId>) -> Self {
+        self.overdue = overdue;
+        self
+    }
+
+    /// Sets the `Editor` shown below the tree, while a description is being edited.
+    #[must_use]
+    pub fn with_editor(mut self, editor: Option<Editor>) -> Self {
+        self.editor = editor;
+        self
+    }
+
+    /// Sets the `Toasts` drawn on top of everything else.
+    #[must_use]
+    pub fn with_toasts(mut self, toasts: Toasts) -> Self {
+        self.toasts = toasts;
+        self
+    }
+}
 
 impl Widget for TuiViewModel {
     fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer)
     where
         Self: Sized,
     {
-        let view_model = self.0;
-
-        Paragraph::new(Text::from(view_model.text))
-            .block(Block::bordered().title_top(Line::from("CASE").centered()))
-            .style(Style::new().white().on_black())
-            .alignment(Alignment::Center)
-            .wrap(Wrap { trim: true })
-            .render(area, buf);
+        let view_model = self.view_model;
+        let style = Style::new().white().on_black();
+        let mut block = Block::bordered()
+            .title_top(Line::from("CASE").centered())
+            .title_bottom(Line::from(self.save_state.label()).right_aligned());
+        if let Some(error) = view_model.error {
+            block = block.title_bottom(Line::from(error).left_aligned().red());
+        }
+        let inner = block.inner(area);
+        block.style(style).render(area, buf);
+
+        match view_model.mode {
+            Mode::Home => Paragraph::new(Text::from(view_model.text))
+                .style(style)
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true })
+                .render(inner, buf),
+            Mode::Explorer if view_model.tree.root_id().is_none() => {
+                let [_, message, _] = Layout::vertical([
+                    Constraint::Fill(1),
+                    Constraint::Length(1),
+                    Constraint::Fill(1),
+                ])
+                .areas(inner);
+
+                Paragraph::new(self.empty_message)
+                    .style(style.dark_gray())
+                    .alignment(Alignment::Center)
+                    .render(message, buf);
+            }
+            Mode::Explorer => {
+                let (inner, editor_area) = if self.editor.is_some() {
+                    let [list, editor] =
+                        Layout::vertical([Constraint::Fill(1), Constraint::Percentage(40)])
+                            .areas(inner);
+                    (list, Some(editor))
+                } else {
+                    (inner, None)
+                };
+
+                let mut state = ListState::default().with_selected(view_model.selected);
+
+                let row_ids = view_model.row_ids;
+                let rows = view_model.rows.into_iter().enumerate().map(|(index, row)| {
+                    let item = ListItem::new(row);
+                    if row_ids
+                        .get(index)
+                        .is_some_and(|id| self.overdue.contains(id))
+                    {
+                        item.red()
+                    } else {
+                        item
+                    }
+                });
+
+                StatefulWidget::render(
+                    List::new(rows)
+                        .style(style)
+                        .highlight_style(style.reversed()),
+                    inner,
+                    buf,
+                    &mut state,
+                );
+
+                if let (Some(editor), Some(editor_area)) = (&self.editor, editor_area) {
+                    editor.render(editor_area, buf);
+                }
+            }
+        }
+
+        Widget::render(&self.toasts, area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{Terminal, backend::TestBackend, style::Color};
+    use shared::{
+        Mode, ViewModel,
+        types::{CaseNode, CaseTree, Group},
+    };
+
+    use super::{DEFAULT_EMPTY_MESSAGE, Editor, TuiViewModel};
+
+    fn render(view_model: ViewModel) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(60, 10)).unwrap();
+        terminal
+            .draw(|f| f.render_widget(TuiViewModel::from(view_model), f.area()))
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(usize::from(buffer.area.width))
+            .map(|row| row.iter().map(ratatui::buffer::Cell::symbol).collect())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_empty_state() {
+        let empty = render(ViewModel {
+            mode: Mode::Explorer,
+            ..ViewModel::default()
+        });
+        assert!(empty.contains(DEFAULT_EMPTY_MESSAGE));
+
+        let view_model = ViewModel {
+            tree: CaseTree::new(CaseNode::Group(Group::new("root"))),
+            mode: Mode::Explorer,
+            ..ViewModel::default()
+        };
+        assert!(!render(view_model).contains(DEFAULT_EMPTY_MESSAGE));
+    }
+
+    #[test]
+    fn test_mode_widgets() {
+        let view_model = ViewModel {
+            text: "42 (pending)".to_owned(),
+            tree: CaseTree::new(CaseNode::Group(Group::new("root"))),
+            rows: vec!["▾ root".to_owned()],
+            selected: Some(0),
+            ..ViewModel::default()
+        };
+
+        let home = render(view_model.clone());
+        assert!(home.contains("42 (pending)"));
+        assert!(!home.contains("▾ root"));
+
+        let explorer = render(ViewModel {
+            mode: Mode::Explorer,
+            ..view_model
+        });
+        assert!(explorer.contains("▾ root"));
+        assert!(!explorer.contains("42 (pending)"));
+        assert_ne!(home, explorer);
+    }
+
+    #[test]
+    fn test_edit_error() {
+        assert!(!render(ViewModel::default()).contains("invalid"));
+
+        let view_model = ViewModel {
+            error: Some("invalid due date \"soon\"".to_owned()),
+            ..ViewModel::default()
+        };
+        assert!(render(view_model).contains("invalid due date \"soon\""));
+    }
+
+    #[test]
+    fn test_overdue_row() {
+        let tree = CaseTree::new(CaseNode::Group(Group::new("root")));
+        let root_id = tree.root_id().unwrap().clone();
+        let view_model = ViewModel {
+            tree,
+            rows: vec!["late".to_owned()],
+            row_ids: vec![root_id.clone()],
+            mode: Mode::Explorer,
+            ..ViewModel::default()
+        };
+
+        let mut terminal = Terminal::new(TestBackend::new(60, 10)).unwrap();
+        terminal
+            .draw(|f| {
+                f.render_widget(
+                    TuiViewModel::from(view_model).with_overdue(vec![root_id]),
+                    f.area(),
+                );
+            })
+            .unwrap();
+
+        // The first row sits right inside the border.
+        assert_eq!(terminal.backend().buffer()[(1, 1)].symbol(), "l");
+        assert_eq!(terminal.backend().buffer()[(1, 1)].fg, Color::Red);
+    }
+
+    #[test]
+    fn test_editor_below_tree() {
+        let tree = CaseTree::new(CaseNode::Group(Group::new("root")));
+        let root_id = tree.root_id().unwrap().clone();
+        let view_model = ViewModel {
+            tree,
+            rows: vec!["▾ root".to_owned()],
+            row_ids: vec![root_id.clone()],
+            mode: Mode::Explorer,
+            ..ViewModel::default()
+        };
+
+        let mut terminal = Terminal::new(TestBackend::new(60, 10)).unwrap();
+        terminal
+            .draw(|f| {
+                f.render_widget(
+                    TuiViewModel::from(view_model)
+                        .with_editor(Some(Editor::open(root_id, "draft"))),
+                    f.area(),
+                );
+            })
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let rendered = buffer
+            .content()
+            .iter()
+            .map(ratatui::buffer::Cell::symbol)
+            .collect::<String>();
+        assert!(rendered.contains("▾ root"));
+        assert!(rendered.contains("Description"));
+        assert!(rendered.contains("draft"));
     }
 }
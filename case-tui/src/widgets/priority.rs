@@ -0,0 +1,33 @@
+use ratatui::style::Color;
+use shared::types::Priority;
+
+/// Returns the color to draw a task of the given `Priority` with.
+#[must_use]
+pub const fn priority_color(priority: &Priority) -> Color {
+    let (r, g, b) = priority.rgb();
+    Color::Rgb(r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::Color;
+    use shared::types::Priority;
+
+    use super::priority_color;
+
+    #[test]
+    fn test_priority_color() {
+        assert_eq!(priority_color(&Priority::Asap), Color::Rgb(255, 0, 0));
+        assert_eq!(priority_color(&Priority::Far), Color::Rgb(0, 0, 255));
+
+        for priority in [
+            Priority::High,
+            Priority::Medium,
+            Priority::Low,
+            Priority::None,
+        ] {
+            let (r, g, b) = priority.rgb();
+            assert_eq!(priority_color(&priority), Color::Rgb(r, g, b));
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::VecDeque;
+
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Clear, Paragraph},
+};
+use shared::notify::{Level, NotifyOperation};
+
+/// How many ticks a toast stays up, 3 seconds at the default tick rate.
+pub const TOAST_TTL: u32 = 12;
+
+/// The most toasts shown at once, older ones are dropped first.
+const MAX_TOASTS: usize = 3;
+
+/// A transient message from the core, see `Toasts`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Toast {
+    pub level: Level,
+    pub message: String,
+    /// Ticks left before the toast is dismissed.
+    ticks_left: u32,
+}
+
+/// The toasts currently up, shown stacked in the bottom right corner until
+/// they expire.
+#[derive(Debug, Clone, Default)]
+pub struct Toasts {
+    toasts: VecDeque<Toast>,
+}
+
+impl Toasts {
+    /// Shows the message of a `Effect::Notify` for `TOAST_TTL` ticks.
+    pub fn push(&mut self, NotifyOperation { level, message }: NotifyOperation) {
+        if self.toasts.len() == MAX_TOASTS {
+            self.toasts.pop_front();
+        }
+
+        self.toasts.push_back(Toast {
+            level,
+            message,
+            ticks_left: TOAST_TTL,
+        });
+    }
+
+    /// Ages every toast by a tick, returning whether any expired.
+    pub fn tick(&mut self) -> bool {
+        let before = self.toasts.len();
+        for toast in &mut self.toasts {
+            toast.ticks_left = toast.ticks_left.saturating_sub(1);
+        }
+        self.toasts.retain(|toast| toast.ticks_left > 0);
+
+        self.toasts.len() != before
+    }
+
+    /// Returns the toasts up, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &Toast> {
+        self.toasts.iter()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.toasts.is_empty()
+    }
+}
+
+impl Widget for &Toasts {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut bottom = area.bottom();
+
+        // Newest at the bottom, stacking up from there.
+        for toast in self.toasts.iter().rev() {
+            let width = u16::try_from(toast.message.chars().count())
+                .unwrap_or(u16::MAX)
+                .saturating_add(2)
+                .min(area.width);
+            if bottom < area.top() + 3 || width < 3 {
+                break;
+            }
+
+            bottom -= 3;
+            let toast_area = Rect::new(area.right() - width, bottom, width, 3);
+            let color = match toast.level {
+                Level::Info => Color::Green,
+                Level::Warning => Color::Yellow,
+                Level::Error => Color::Red,
+            };
+
+            Clear.render(toast_area, buf);
+            Paragraph::new(toast.message.as_str())
+                .block(Block::bordered().border_style(Style::new().fg(color)))
+                .render(toast_area, buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossbeam_channel::unbounded;
+    use ratatui::{Terminal, backend::TestBackend};
+    use shared::{Effect, Event, notify::Level};
+
+    use super::{TOAST_TTL, Toasts};
+    use crate::core::{new, update};
+
+    fn notified(toasts: &mut Toasts, event: Event) {
+        let core = new();
+        let (tx, rx) = unbounded();
+        update(&core, event, &tx).unwrap();
+
+        for effect in rx.try_iter() {
+            if let Effect::Notify(request) = effect {
+                toasts.push(request.operation);
+            }
+        }
+    }
+
+    #[test]
+    fn test_notify_enqueues_toast() {
+        let mut toasts = Toasts::default();
+
+        notified(&mut toasts, Event::CreateTask("write tests".to_owned()));
+
+        let toast = toasts.iter().next().unwrap();
+        assert_eq!(toast.level, Level::Info);
+        assert_eq!(toast.message, "Task created");
+
+        let mut terminal = Terminal::new(TestBackend::new(30, 5)).unwrap();
+        terminal
+            .draw(|f| f.render_widget(&toasts, f.area()))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let row: String = (0..30).map(|x| buffer[(x, 3)].symbol()).collect();
+        assert!(row.ends_with("│Task created│"));
+    }
+
+    #[test]
+    fn test_toast_expires() {
+        let mut toasts = Toasts::default();
+        notified(&mut toasts, Event::CreateTask("write tests".to_owned()));
+
+        for _ in 1..TOAST_TTL {
+            assert!(!toasts.tick());
+        }
+        assert!(!toasts.is_empty());
+
+        assert!(toasts.tick());
+        assert!(toasts.is_empty());
+    }
+}
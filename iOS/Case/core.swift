@@ -47,8 +47,14 @@ class Core: ObservableObject {
         case let .serverSentEvents(req):
             Task {
                 for await result in await requestSse(req) {
-                    let response = try result.get()
-                    
+                    let response: SseResponse
+                    switch result {
+                    case let .success(chunk):
+                        response = chunk
+                    case let .failure(error):
+                        response = .error("\(error)")
+                    }
+
                     let effects = [UInt8](
                         core.resolve(
                             request.id,
@@ -62,6 +68,9 @@ class Core: ObservableObject {
                     }
                 }
             }
+        case let .notify(notification):
+            // There's no toast UI yet, so the message only goes to the log.
+            print("[\(notification.level)] \(notification.message)")
         }
     }
 }
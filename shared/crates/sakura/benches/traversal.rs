@@ -0,0 +1,82 @@
+//! Compares the pre-order traversals against the `VecDeque` based one they
+//! replaced, on a tree about the size of a big CASE workspace.
+
+use std::{collections::VecDeque, hint::black_box};
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use sakura::{
+    InsertBehavior::{AsRoot, UnderNode},
+    Node, NodeId, Tree,
+};
+
+const NODES: usize = 50_000;
+const CHILDREN_PER_NODE: usize = 8;
+
+/// A tree of `NODES` nodes, each with up to `CHILDREN_PER_NODE` children.
+fn wide_tree() -> (Tree<usize>, NodeId) {
+    let mut tree = Tree::new();
+    let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+
+    let mut parents = VecDeque::from([root_id.clone()]);
+    let mut len = 1;
+    while len < NODES {
+        let parent = parents.pop_front().unwrap();
+        for _ in 0..CHILDREN_PER_NODE.min(NODES - len) {
+            parents.push_back(tree.insert(Node::new(len), UnderNode(&parent)).unwrap());
+            len += 1;
+        }
+    }
+
+    (tree, root_id)
+}
+
+/// The traversal before it stacked references into the `Tree`, queueing a
+/// clone of every `NodeId` ahead of visiting it.
+fn queue_pre_order<'a>(
+    tree: &'a Tree<usize>,
+    start: &NodeId,
+) -> impl Iterator<Item = &'a Node<usize>> {
+    let mut queue = VecDeque::with_capacity(tree.capacity());
+    queue.push_front(start.clone());
+
+    std::iter::from_fn(move || {
+        let node = tree.get(&queue.pop_front()?).ok()?;
+        for child_id in node.children().iter().rev() {
+            queue.push_front(child_id.clone());
+        }
+        Some(node)
+    })
+}
+
+fn pre_order(c: &mut Criterion) {
+    let (tree, root_id) = wide_tree();
+    let mut group = c.benchmark_group("pre_order_50k");
+
+    group.bench_function("queue", |b| {
+        b.iter(|| {
+            queue_pre_order(&tree, black_box(&root_id))
+                .map(|node| *node.data())
+                .sum::<usize>()
+        });
+    });
+    group.bench_function("traverse_pre_order", |b| {
+        b.iter(|| {
+            tree.traverse_pre_order(black_box(&root_id))
+                .unwrap()
+                .map(|node| *node.data())
+                .sum::<usize>()
+        });
+    });
+    group.bench_function("traverse_pre_order_ids", |b| {
+        b.iter(|| {
+            tree.traverse_pre_order_ids(black_box(&root_id))
+                .unwrap()
+                .count()
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, pre_order);
+criterion_main!(benches);
@@ -1,6 +1,7 @@
 use super::NodeId;
 
 /// Describes the possible behaviors of the `Tree::insert` method.
+#[non_exhaustive]
 pub enum InsertBehavior<'a> {
     /// Insert the `Node` as the root of the tree.
     ///
@@ -42,6 +43,7 @@ pub enum InsertBehavior<'a> {
     UnderNode(&'a NodeId),
 }
 
+#[non_exhaustive]
 pub enum RemoveBehavior {
     /// The entire subtree of the `Node` being removed will be
     /// dropped from the tree, effectively meaning that all children
@@ -127,6 +129,7 @@ pub enum RemoveBehavior {
     OrphanChildren,
 }
 
+#[non_exhaustive]
 pub enum MoveBehavior<'a> {
     /// Sets the `Node` as the new root `Node`, while having all their children
     /// travel with them.
@@ -185,4 +188,32 @@ pub enum MoveBehavior<'a> {
     /// ```
     ///
     ToParent(&'a NodeId),
+
+    /// Moves a `Node` in the `Tree` to a new parent, like `ToParent`, but
+    /// places it at the given index among the new parent's children.
+    ///
+    /// The index is the final position of the `Node`, and is clamped to the
+    /// number of children, so an out of range index places it last. This also
+    /// works for reordering a `Node` under its current parent.
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    /// use sakura::MoveBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    ///
+    /// let root_id = tree.insert(Node::new(1), AsRoot).unwrap();
+    /// let first_child_id = tree.insert(Node::new(2),  UnderNode(&root_id)).unwrap();
+    /// let second_child_id = tree.insert(Node::new(3), UnderNode(&root_id)).unwrap();
+    ///
+    /// tree.move_node(&second_child_id, ToParentAtIndex(&root_id, 0)).unwrap();
+    ///
+    /// assert_eq!(
+    ///     tree.get(&root_id).unwrap().children(),
+    ///     &vec![second_child_id, first_child_id]
+    /// );
+    /// ```
+    ///
+    ToParentAtIndex(&'a NodeId, usize),
 }
@@ -0,0 +1,36 @@
+use crate::NodeId;
+
+/// Describes a single structural or data change between two `Tree`s.
+///
+/// Produced by `Tree::diff`, where `Node`s are matched by their `NodeId`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeChange {
+    /// A `Node` exists in the new `Tree` but not in the old one.
+    Added {
+        /// The `NodeId` of the added `Node`.
+        id: NodeId,
+        /// The parent of the added `Node` in the new `Tree`.
+        parent: Option<NodeId>,
+    },
+
+    /// A `Node` exists in the old `Tree` but not in the new one.
+    Removed {
+        /// The `NodeId` of the removed `Node`.
+        id: NodeId,
+        /// The parent the removed `Node` had in the old `Tree`.
+        parent: Option<NodeId>,
+    },
+
+    /// A `Node` exists in both `Tree`s, but under a different parent.
+    Moved {
+        /// The `NodeId` of the moved `Node`.
+        id: NodeId,
+        /// The parent in the old `Tree`.
+        from: Option<NodeId>,
+        /// The parent in the new `Tree`.
+        to: Option<NodeId>,
+    },
+
+    /// A `Node` exists in both `Tree`s, but its data is different.
+    DataChanged(NodeId),
+}
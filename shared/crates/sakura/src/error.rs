@@ -2,10 +2,44 @@ use std::{error::Error, fmt::Display};
 
 /// Enum for all possible `NodeId` errors that could happen.
 #[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum NodeIdError {
     /// Occurs when a `NodeId` is used on a `Tree` after the corresponding
     /// `Node` has been removed.
     NodeIdNoLongerValid,
+
+    /// Occurs when moving a `Node` under one of its own descendants (or
+    /// itself) was requested.
+    WouldCreateCycle,
+
+    /// Occurs when a `NodeId` points past the end of the `Tree`, so it can't
+    /// have come from it, e.g. when it was deserialized from a corrupt
+    /// document.
+    OutOfBounds,
+
+    /// Occurs when a `Node` was expected to be a child of another `Node`,
+    /// but isn't.
+    NotAChild,
+
+    /// Occurs when a sibling was requested for a `Node` without a parent,
+    /// e.g. the root.
+    NoSiblings,
+
+    /// Occurs when following the parents of a `Node` leads back to a `Node`
+    /// that was already visited, which only happens in a corrupt `Tree`.
+    ParentCycle,
+
+    /// Occurs when the contents of an empty `Tree` were to be inserted into
+    /// another one.
+    EmptyTree,
+
+    /// Occurs when a free `NodeId` of a `Tree` is out of bounds, listed twice
+    /// or still in use, which only happens in a corrupt `Tree`.
+    InvalidFreeId,
+
+    /// Occurs when a `Node` of a `Tree` can't be reached from its root, which
+    /// only happens in a corrupt `Tree`.
+    Unreachable,
 }
 
 impl NodeIdError {
@@ -14,6 +48,16 @@ impl NodeIdError {
             Self::NodeIdNoLongerValid => {
                 "The given NodeId is no longer valid. The Node in question has been removed."
             }
+            Self::WouldCreateCycle => {
+                "The Node can not be moved under itself or one of its descendants."
+            }
+            Self::OutOfBounds => "The given NodeId is out of bounds of the Tree.",
+            Self::NotAChild => "The Node is not a child of the given parent.",
+            Self::NoSiblings => "The Node has no parent, so it can not have siblings.",
+            Self::ParentCycle => "The parents of the Node loop back onto themselves.",
+            Self::EmptyTree => "The Tree to insert has no Nodes.",
+            Self::InvalidFreeId => "A free NodeId is out of bounds, listed twice or in use.",
+            Self::Unreachable => "The Node can not be reached from the root of the Tree.",
         }
     }
 }
@@ -0,0 +1,140 @@
+use serde_json::Value;
+
+use crate::{Node, Tree, TreeBuilder};
+
+impl Tree<String> {
+    /// Builds a `Tree` out of an arbitrary JSON document, e.g. an imported
+    /// outline.
+    ///
+    /// The mapping rules are:
+    /// * The root is an unnamed `Node` (an empty `String`) holding the document.
+    /// * An object becomes one `Node` per key, holding the key, with the value
+    ///   of that key mapped below it.
+    /// * An array becomes one `Node` per element, in order. Nested objects and
+    ///   arrays are held by a `Node` with their index as data.
+    /// * A scalar becomes a leaf holding its text: strings without quotes,
+    ///   everything else (including `null`) as written in JSON.
+    ///
+    /// A document that is just a scalar results in a single root leaf.
+    ///
+    /// # Panics
+    ///
+    /// Can panic if the freshly built root is missing, but this would be a
+    /// bug in `Sakura`
+    ///
+    /// ```
+    /// use sakura::Tree;
+    /// use serde_json::json;
+    ///
+    /// let tree = Tree::from_json_value(&json!({ "work": ["email", "review"] }));
+    ///
+    /// let mut s = String::new();
+    /// tree.write_formatted_ascii(&mut s).unwrap();
+    /// assert_eq!(&s, "\
+    /// \"\"
+    /// `-- \"work\"
+    ///     |-- \"email\"
+    ///     `-- \"review\"
+    /// ");
+    /// ```
+    #[must_use]
+    pub fn from_json_value(value: &Value) -> Self {
+        if !(value.is_object() || value.is_array()) {
+            return TreeBuilder::new()
+                .with_root(Node::new(scalar_text(value)))
+                .build();
+        }
+
+        let mut tree = TreeBuilder::new()
+            .with_root(Node::new(String::new()))
+            .build();
+        let root_id = tree
+            .root_node_id()
+            .cloned()
+            .expect("Tree::from_json_value: root was just set");
+
+        let mut stack = vec![(value, root_id)];
+
+        while let Some((value, parent_id)) = stack.pop() {
+            match value {
+                Value::Object(map) => {
+                    for (key, value) in map {
+                        let id = tree.insert_with_parent(Node::new(key.clone()), &parent_id);
+                        stack.push((value, id));
+                    }
+                }
+                Value::Array(elements) => {
+                    for (index, element) in elements.iter().enumerate() {
+                        if element.is_object() || element.is_array() {
+                            let id =
+                                tree.insert_with_parent(Node::new(index.to_string()), &parent_id);
+                            stack.push((element, id));
+                        } else {
+                            tree.insert_with_parent(Node::new(scalar_text(element)), &parent_id);
+                        }
+                    }
+                }
+                scalar => {
+                    tree.insert_with_parent(Node::new(scalar_text(scalar)), &parent_id);
+                }
+            }
+        }
+
+        tree
+    }
+}
+
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod json_tests {
+    use serde_json::json;
+
+    use crate::Tree;
+
+    fn data(tree: &Tree<String>) -> Vec<&str> {
+        tree.traverse_pre_order(tree.root_node_id().unwrap())
+            .unwrap()
+            .map(|node| node.data().as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_nested_object() {
+        let tree = Tree::from_json_value(&json!({
+            "work": { "email": "today", "done": false },
+            "home": null,
+        }));
+
+        // `serde_json` keeps object keys sorted, so siblings are too.
+        assert_eq!(
+            data(&tree),
+            vec![
+                "", "home", "null", "work", "done", "false", "email", "today"
+            ]
+        );
+        assert_eq!(tree.height(), 4);
+    }
+
+    #[test]
+    fn test_nested_array() {
+        let tree = Tree::from_json_value(&json!(["a", ["b", ["c"]], { "d": 1 }]));
+
+        assert_eq!(
+            data(&tree),
+            vec!["", "a", "1", "b", "1", "c", "2", "d", "1"]
+        );
+    }
+
+    #[test]
+    fn test_scalar() {
+        let tree = Tree::from_json_value(&json!("just text"));
+
+        assert_eq!(data(&tree), vec!["just text"]);
+    }
+}
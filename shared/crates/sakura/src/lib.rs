@@ -9,8 +9,13 @@ use autosurgeon::{Hydrate, Reconcile};
 use serde::{Deserialize, Serialize};
 
 mod behaviors;
+mod diff;
 mod error;
 mod iterators;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "nested")]
+mod nested;
 mod node;
 mod tree;
 
@@ -18,6 +23,7 @@ pub use node::Node;
 
 pub use tree::Tree;
 pub use tree::TreeBuilder;
+pub use tree::TreeSnapshot;
 
 pub use behaviors::InsertBehavior;
 pub use behaviors::MoveBehavior;
@@ -29,6 +35,11 @@ pub use iterators::ChildrenIds;
 pub use iterators::PreOrderTraversal;
 pub use iterators::PreOrderTraversalIds;
 
+pub use diff::TreeChange;
+
+#[cfg(feature = "nested")]
+pub use nested::{NestedNode, NestedTree};
+
 pub use error::NodeIdError;
 
 /// A Node Id
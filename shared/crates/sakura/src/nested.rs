@@ -0,0 +1,186 @@
+use std::slice::Iter;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Node, NodeId, Tree, TreeBuilder};
+
+/// A `Tree` in a nested shape, for readable output like logs and snapshot
+/// tests.
+///
+/// `Tree` itself serializes its storage as is, which suits `AutoMerge` but is
+/// hard to read as JSON. Converting to a `NestedTree` first serializes every
+/// `Node` as `{ "data": .., "children": [..] }` instead, with `children` left
+/// out for leaves, and an empty `Tree` as `null`:
+///
+/// ```
+/// use sakura::*;
+/// use sakura::InsertBehavior::*;
+///
+/// let mut tree: Tree<&str> = Tree::new();
+/// let root_id = tree.insert(Node::new("home"), AsRoot).unwrap();
+/// tree.insert(Node::new("work"), UnderNode(&root_id)).unwrap();
+///
+/// let json = serde_json::to_string(&NestedTree::from(&tree)).unwrap();
+/// assert_eq!(json, r#"{"data":"home","children":[{"data":"work"}]}"#);
+/// ```
+///
+/// Only the root and its descendants are kept, `Node`s without a parent
+/// other than the root are left out. `NodeId`s aren't kept either, so
+/// converting back into a `Tree` assigns fresh ones.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct NestedTree<T>(pub Option<NestedNode<T>>);
+
+/// A `Node` of a `NestedTree`, holding its children directly.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NestedNode<T> {
+    /// The data of the `Node`.
+    pub data: T,
+    /// The children of the `Node`, in order.
+    #[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Self>,
+}
+
+impl<T> NestedNode<T> {
+    const fn leaf(data: T) -> Self {
+        Self {
+            data,
+            children: Vec::new(),
+        }
+    }
+}
+
+impl<T: Clone> From<&Tree<T>> for NestedTree<T> {
+    fn from(tree: &Tree<T>) -> Self {
+        let Some(root_id) = tree.root_node_id() else {
+            return Self(None);
+        };
+        let root = tree
+            .get(root_id)
+            .expect("NestedTree::from: the root is a valid node id");
+
+        // Each level holds the `NestedNode` being built and the children it
+        // is still missing.
+        let mut stack: Vec<(NestedNode<T>, Iter<'_, NodeId>)> =
+            vec![(NestedNode::leaf(root.data.clone()), root.children.iter())];
+
+        loop {
+            let (_, children) = stack
+                .last_mut()
+                .expect("NestedTree::from: the root is only popped last");
+
+            if let Some(child_id) = children.next() {
+                let child = tree
+                    .get(child_id)
+                    .expect("NestedTree::from: children are valid node ids");
+                stack.push((NestedNode::leaf(child.data.clone()), child.children.iter()));
+                continue;
+            }
+
+            let (done, _) = stack
+                .pop()
+                .expect("NestedTree::from: the stack is not empty");
+            match stack.last_mut() {
+                Some((parent, _)) => parent.children.push(done),
+                None => return Self(Some(done)),
+            }
+        }
+    }
+}
+
+impl<T> From<NestedTree<T>> for Tree<T> {
+    fn from(nested: NestedTree<T>) -> Self {
+        let Some(root) = nested.0 else {
+            return Self::new();
+        };
+
+        let mut tree = TreeBuilder::new().with_root(Node::new(root.data)).build();
+        let root_id = tree
+            .root_node_id()
+            .cloned()
+            .expect("Tree::from: root was just set");
+
+        let mut stack: Vec<(NestedNode<T>, NodeId)> = root
+            .children
+            .into_iter()
+            .rev()
+            .map(|child| (child, root_id.clone()))
+            .collect();
+
+        while let Some((node, parent_id)) = stack.pop() {
+            let id = tree.insert_with_parent(Node::new(node.data), &parent_id);
+            stack.extend(
+                node.children
+                    .into_iter()
+                    .rev()
+                    .map(|child| (child, id.clone())),
+            );
+        }
+
+        tree
+    }
+}
+
+#[cfg(test)]
+mod nested_tests {
+    use serde_json::json;
+
+    use crate::{InsertBehavior::*, NestedTree, Node, RemoveBehavior::*, Tree};
+
+    fn fixture() -> Tree<i32> {
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+        tree.insert(Node::new(3), UnderNode(&node_1_id)).unwrap();
+
+        tree
+    }
+
+    #[test]
+    fn test_readable_json() {
+        let value = serde_json::to_value(NestedTree::from(&fixture())).unwrap();
+
+        assert_eq!(
+            value,
+            json!({
+                "data": 0,
+                "children": [
+                    { "data": 1, "children": [{ "data": 3 }] },
+                    { "data": 2 },
+                ],
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(NestedTree::from(&Tree::<i32>::new())).unwrap(),
+            json!(null)
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let tree = fixture();
+
+        let json = serde_json::to_string(&NestedTree::from(&tree)).unwrap();
+        let nested: NestedTree<i32> = serde_json::from_str(&json).unwrap();
+        let back: Tree<i32> = nested.into();
+
+        assert!(back.structurally_eq(&tree));
+
+        let empty: Tree<i32> = NestedTree(None).into();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_fresh_ids() {
+        let mut tree = fixture();
+        let root_id = tree.root_node_id().cloned().unwrap();
+        let first = tree.first_child(&root_id).unwrap().unwrap();
+        tree.remove_node(first, DropChildren).unwrap();
+
+        // The freed slots aren't carried over.
+        let back = Tree::from(NestedTree::from(&tree));
+        assert!(back.structurally_eq(&tree));
+        assert_eq!(back.slot_count(), 2);
+    }
+}
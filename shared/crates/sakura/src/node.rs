@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::NodeId;
 
-#[derive(Debug, Serialize, Deserialize, Reconcile, Hydrate, Ord, Eq, PartialOrd)]
+#[derive(Debug, Clone, Serialize, Deserialize, Reconcile, Hydrate, Ord, Eq, PartialOrd)]
 pub struct Node<T> {
     pub(crate) data: T,
     pub(crate) parent: Option<NodeId>,
@@ -86,6 +86,39 @@ impl<T> Node<T> {
         data
     }
 
+    /// Consumes this `Node`, returning its data.
+    ///
+    /// ```
+    /// use sakura::Node;
+    ///
+    /// let node: Node<String> = Node::new("home".to_owned());
+    /// let data: String = node.into_data();
+    /// # assert_eq!(data, "home");
+    /// ```
+    pub fn into_data(self) -> T {
+        self.data
+    }
+
+    /// Consumes this `Node`, returning its data, parent and children.
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    ///
+    /// let (data, parent, children) = tree.get(&root_id).unwrap().clone().into_parts();
+    ///
+    /// # assert_eq!(data, 0);
+    /// # assert_eq!(parent, None);
+    /// # assert_eq!(children, vec![child_id]);
+    /// ```
+    pub fn into_parts(self) -> (T, Option<NodeId>, Vec<NodeId>) {
+        (self.data, self.parent, self.children)
+    }
+
     /// Returns the parent of this `Node`, if it has one.
     ///
     /// ```
@@ -110,6 +143,103 @@ impl<T> Node<T> {
         &self.children
     }
 
+    /// Reverses the order of this `Node`s children, in place.
+    ///
+    /// Only the order changes, so this is safe to use on a `Node` inside a
+    /// `Tree` as well as on a detached one.
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let first_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let second_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    ///
+    /// tree.get_mut(&root_id).unwrap().reverse_children();
+    ///
+    /// # assert_eq!(tree.get(&root_id).unwrap().children(), &vec![second_id, first_id]);
+    /// ```
+    pub fn reverse_children(&mut self) {
+        self.children.reverse();
+    }
+
+    /// Sorts this `Node`s children by their `NodeId`s, in place, using compare.
+    ///
+    /// Unlike `Tree::sort_children_by`, this doesn't need a `Tree`, which makes
+    /// it usable for preparing a detached `Node` before grafting it.
+    ///
+    /// This sort is stable and O(n log n) worst case.
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let first_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let second_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    ///
+    /// tree.get_mut(&root_id).unwrap().sort_children_by(|a, b| b.cmp(a));
+    ///
+    /// # assert_eq!(tree.get(&root_id).unwrap().children(), &vec![second_id, first_id]);
+    /// ```
+    pub fn sort_children_by<F>(&mut self, compare: F)
+    where
+        F: FnMut(&NodeId, &NodeId) -> std::cmp::Ordering,
+    {
+        self.children.sort_by(compare);
+    }
+
+    /// Consumes this `Node`, transforming its data with `f`.
+    ///
+    /// The parent and children `NodeId`s are carried over unchanged.
+    ///
+    /// ```
+    /// use sakura::Node;
+    ///
+    /// let node: Node<i32> = Node::new(10);
+    /// let mapped: Node<String> = node.map(|data| data.to_string());
+    /// # assert_eq!(mapped.data(), "10");
+    /// ```
+    pub fn map<U, F>(self, f: F) -> Node<U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        Node {
+            data: f(self.data),
+            parent: self.parent,
+            children: self.children,
+        }
+    }
+
+    /// Consumes this `Node`, transforming its data with the fallible `f`.
+    ///
+    /// The parent and children `NodeId`s are carried over unchanged.
+    ///
+    /// # Errors
+    /// Returns the error of `f`, if it failed.
+    ///
+    /// ```
+    /// use sakura::Node;
+    ///
+    /// let node: Node<&str> = Node::new("10");
+    /// let mapped = node.try_map(str::parse::<i32>).unwrap();
+    /// # assert_eq!(mapped.data(), &10);
+    /// # assert!(Node::new("ten").try_map(str::parse::<i32>).is_err());
+    /// ```
+    pub fn try_map<U, E, F>(self, f: F) -> Result<Node<U>, E>
+    where
+        F: FnOnce(T) -> Result<U, E>,
+    {
+        Ok(Node {
+            data: f(self.data)?,
+            parent: self.parent,
+            children: self.children,
+        })
+    }
+
     pub(crate) const fn children_mut(&mut self) -> &mut Vec<NodeId> {
         &mut self.children
     }
@@ -201,4 +331,107 @@ mod node_tests {
         assert_ne!(node1, node3);
         assert_ne!(node2, node3);
     }
+
+    #[test]
+    fn test_reverse_children() {
+        let mut node = Node::new(0);
+
+        node.add_child(NodeId { index: 1 });
+        node.add_child(NodeId { index: 2 });
+        node.add_child(NodeId { index: 3 });
+
+        node.reverse_children();
+
+        assert_eq!(
+            node.children,
+            vec![
+                NodeId { index: 3 },
+                NodeId { index: 2 },
+                NodeId { index: 1 }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sort_children_by() {
+        let mut node = Node::new(0);
+
+        node.add_child(NodeId { index: 2 });
+        node.add_child(NodeId { index: 3 });
+        node.add_child(NodeId { index: 1 });
+
+        node.sort_children_by(Ord::cmp);
+        assert_eq!(
+            node.children,
+            vec![
+                NodeId { index: 1 },
+                NodeId { index: 2 },
+                NodeId { index: 3 }
+            ]
+        );
+
+        node.sort_children_by(|a, b| b.cmp(a));
+        assert_eq!(
+            node.children,
+            vec![
+                NodeId { index: 3 },
+                NodeId { index: 2 },
+                NodeId { index: 1 }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_map() {
+        let mut node = Node::new(1);
+        node.set_parent(Some(NodeId { index: 0 }));
+        node.add_child(NodeId { index: 2 });
+        node.add_child(NodeId { index: 3 });
+
+        let mapped = node.map(|data| format!("node {data}"));
+
+        assert_eq!(mapped.data(), "node 1");
+        assert_eq!(mapped.parent(), Some(&NodeId { index: 0 }));
+        assert_eq!(
+            mapped.children(),
+            &vec![NodeId { index: 2 }, NodeId { index: 3 }]
+        );
+    }
+
+    #[test]
+    fn test_try_map() {
+        let mut node = Node::new("1");
+        node.set_parent(Some(NodeId { index: 0 }));
+        node.add_child(NodeId { index: 2 });
+
+        let mapped = node.clone().try_map(str::parse::<i32>).unwrap();
+
+        assert_eq!(mapped.data(), &1);
+        assert_eq!(mapped.parent(), Some(&NodeId { index: 0 }));
+        assert_eq!(mapped.children(), &vec![NodeId { index: 2 }]);
+
+        assert!(node.try_map(|_| Err::<i32, _>("invalid")).is_err());
+    }
+
+    #[test]
+    fn test_into_data() {
+        let mut node = Node::new(vec![1, 2]);
+        node.add_child(NodeId::new(1));
+
+        assert_eq!(node.into_data(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_into_parts() {
+        let mut node = Node::new(10);
+        node.set_parent(Some(NodeId::new(0)));
+        node.add_child(NodeId::new(2));
+        node.add_child(NodeId::new(3));
+
+        let (data, parent, children) = node.into_parts();
+
+        assert_eq!(data, 10);
+        assert_eq!(parent, Some(NodeId::new(0)));
+        assert_eq!(children, vec![NodeId::new(2), NodeId::new(3)]);
+    }
 }
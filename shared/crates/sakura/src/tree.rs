@@ -1,11 +1,17 @@
-use std::cmp::Ordering;
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, TryReserveError},
+};
 
-use autosurgeon::{Hydrate, Reconcile};
+use autosurgeon::{
+    Hydrate, HydrateError, ReadDoc, Reconcile, Reconciler, hydrate_prop,
+    reconcile::{MapReconciler, NoKey},
+};
 use serde::{Deserialize, Serialize};
 
 use crate::{
     Ancestors, Children, ChildrenIds, InsertBehavior, MoveBehavior, Node, NodeId,
-    PreOrderTraversal, PreOrderTraversalIds, RemoveBehavior, error::NodeIdError,
+    PreOrderTraversal, PreOrderTraversalIds, RemoveBehavior, TreeChange, error::NodeIdError,
     iterators::AncestorsIds,
 };
 
@@ -107,7 +113,7 @@ impl<T> TreeBuilder<T> {
     /// ```
     /// use sakura::TreeBuilder;
     ///
-    /// let _tree_builder: TreeBuilder<i32> = TreeBuilder::new().with_node_capacity(1);
+    /// let _tree_builder: TreeBuilder<i32> = TreeBuilder::new().with_swap_capacity(1);
     ///
     /// ```
     #[must_use]
@@ -117,6 +123,29 @@ impl<T> TreeBuilder<T> {
         self
     }
 
+    /// Sets both the `node_capacity` and the `swap_capacity` for `TreeBuilder`.
+    ///
+    /// See `with_node_capacity` and `with_swap_capacity` for what each one is for.
+    ///
+    /// ```
+    /// use sakura::TreeBuilder;
+    /// use sakura::Tree;
+    ///
+    /// let tree: Tree<i32> = TreeBuilder::new().with_capacities(10, 3).build();
+    /// # assert_eq!(tree.capacity(), 10);
+    /// ```
+    #[must_use]
+    #[allow(clippy::use_self)]
+    pub const fn with_capacities(
+        mut self,
+        node_capacity: usize,
+        swap_capacity: usize,
+    ) -> TreeBuilder<T> {
+        self.node_capacity = node_capacity;
+        self.swap_capacity = swap_capacity;
+        self
+    }
+
     ///
     /// Build a `Tree` based upon the current settings in the `TreeBuilder`.
     ///
@@ -132,10 +161,19 @@ impl<T> TreeBuilder<T> {
     ///         .build();
     /// ```
     pub fn build(mut self) -> Tree<T> {
+        // The root always needs a slot, so reserve it up front rather than
+        // letting the push below pick a capacity of its own.
+        let node_capacity = if self.root.is_some() {
+            self.node_capacity.max(1)
+        } else {
+            self.node_capacity
+        };
+
         let mut tree = Tree {
             root: None,
-            nodes: Vec::with_capacity(self.node_capacity),
+            nodes: Vec::with_capacity(node_capacity),
             free_ids: Vec::with_capacity(self.swap_capacity),
+            height_cache: HeightCache::default(),
         };
 
         if self.root.is_some() {
@@ -156,11 +194,57 @@ impl<T> TreeBuilder<T> {
 /// Any function that takes a `NodeId` can `panic`, but this should
 /// only happen with improper `NodeId` management within `Sakura`, and
 /// should have nothing to do with library user's code.
-#[derive(Debug, Serialize, Deserialize, Reconcile, Hydrate)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tree<T> {
     root: Option<NodeId>,
     pub(crate) nodes: Vec<Option<Node<T>>>,
     free_ids: Vec<NodeId>,
+    #[serde(skip)]
+    height_cache: HeightCache,
+}
+
+// Written by hand to leave `height_cache` out of documents, which the derive
+// has no attribute for.
+impl<T: Reconcile> Reconcile for Tree<T> {
+    type Key<'a> = NoKey;
+
+    fn reconcile<R: Reconciler>(&self, mut reconciler: R) -> Result<(), R::Error> {
+        let mut map = reconciler.map()?;
+        map.put("root", &self.root)?;
+        map.put("nodes", &self.nodes)?;
+        map.put("free_ids", &self.free_ids)?;
+
+        // Documents written by older versions stored the cache as well.
+        if map.entry("height_cache").is_some() {
+            map.delete("height_cache")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: Hydrate> Hydrate for Tree<T> {
+    fn hydrate_map<D: ReadDoc>(doc: &D, obj: &automerge::ObjId) -> Result<Self, HydrateError> {
+        Ok(Self {
+            root: hydrate_prop(doc, obj, "root")?,
+            nodes: hydrate_prop(doc, obj, "nodes")?,
+            free_ids: hydrate_prop(doc, obj, "free_ids")?,
+            height_cache: HeightCache::default(),
+        })
+    }
+}
+
+/// The height last computed by `Tree::cached_height`, if the structure of the
+/// `Tree` didn't change since.
+///
+/// It is only a cache, so it is neither serialized nor stored in documents.
+#[derive(Debug, Clone, Copy, Default)]
+struct HeightCache(Option<usize>);
+
+/// The whole state of a `Tree` at some point, see `Tree::snapshot`.
+#[derive(Debug, Clone)]
+pub struct TreeSnapshot<T> {
+    tree: Tree<T>,
 }
 
 impl<T> Default for Tree<T> {
@@ -205,6 +289,53 @@ where
     }
 }
 
+/// Builds a flat, single-level `Tree`: the first item becomes the root, and
+/// every following item becomes a direct child of the root, in order.
+///
+/// NOTE: This is not a general shape constructor, an empty iterator simply
+/// results in an empty `Tree`.
+///
+/// ```
+/// use sakura::*;
+///
+/// let tree: Tree<i32> = [0, 1, 2].into_iter().collect();
+/// let root_id = tree.root_node_id().unwrap();
+///
+/// # assert_eq!(tree.get(root_id).unwrap().data(), &0);
+/// # assert_eq!(tree.children(root_id).unwrap().map(Node::data).collect::<Vec<_>>(), vec![&1, &2]);
+/// ```
+impl<T> FromIterator<T> for Tree<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut iter = iter.into_iter();
+
+        let Some(root) = iter.next() else {
+            return Self::new();
+        };
+
+        let mut tree = TreeBuilder::new()
+            .with_root(Node::new(root))
+            .with_node_capacity(iter.size_hint().0 + 1)
+            .build();
+        let root_id = tree
+            .root
+            .clone()
+            .expect("Tree::from_iter: root was just set");
+
+        for data in iter {
+            tree.insert_with_parent(Node::new(data), &root_id);
+        }
+
+        tree
+    }
+}
+
+/// An owned, order-independent form of a subtree, used by `Tree::eq_unordered`.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Canonical<T> {
+    data: T,
+    children: Vec<Self>,
+}
+
 impl<T> Tree<T> {
     /// Creates a new `Tree` with default settings (no root `Node` and no space pre-allocation)
     ///
@@ -219,6 +350,53 @@ impl<T> Tree<T> {
         TreeBuilder::new().build()
     }
 
+    /// Creates a new empty `Tree` with space for `node_capacity` `Node`s.
+    ///
+    /// A shortcut for `TreeBuilder::new().with_node_capacity(node_capacity).build()`,
+    /// see `TreeBuilder` for the other settings.
+    ///
+    /// ```
+    /// use sakura::Tree;
+    ///
+    /// let tree: Tree<i32> = Tree::with_capacity(10);
+    ///
+    /// # assert_eq!(tree.capacity(), 10);
+    /// ```
+    #[must_use]
+    pub fn with_capacity(node_capacity: usize) -> Self {
+        TreeBuilder::new().with_node_capacity(node_capacity).build()
+    }
+
+    /// Takes a `TreeSnapshot` of the `Tree`, to go back to it with
+    /// `Tree::restore`, e.g. when a speculative edit is cancelled.
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    ///
+    /// let snapshot = tree.snapshot();
+    /// tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// tree.restore(snapshot);
+    ///
+    /// # assert_eq!(tree.len(), 1);
+    /// ```
+    #[must_use]
+    pub fn snapshot(&self) -> TreeSnapshot<T>
+    where
+        T: Clone,
+    {
+        TreeSnapshot { tree: self.clone() }
+    }
+
+    /// Puts the `Tree` back the way it was when `snapshot` was taken, free
+    /// slots included, so every `NodeId` from back then is valid again.
+    pub fn restore(&mut self, snapshot: TreeSnapshot<T>) {
+        *self = snapshot.tree;
+    }
+
     ///
     /// Returns the number of elements the tree can hold without reallocating.
     ///
@@ -227,6 +405,197 @@ impl<T> Tree<T> {
         self.nodes.capacity()
     }
 
+    /// Returns the number of `Node`s in the `Tree`.
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// # assert_eq!(tree.len(), 0);
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    ///
+    /// # assert_eq!(tree.len(), 2);
+    /// ```
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.slot_count() - self.free_count()
+    }
+
+    /// Returns whether the `Tree` has no `Node`s.
+    ///
+    /// ```
+    /// use sakura::Tree;
+    ///
+    /// let tree: Tree<i32> = Tree::new();
+    /// # assert!(tree.is_empty());
+    /// ```
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the `NodeId`s of every `Node` in the `Tree`,
+    /// in index order.
+    ///
+    /// Unlike the traversals this doesn't follow the structure of the `Tree`,
+    /// so it also reaches `Node`s orphaned by `RemoveBehavior::OrphanChildren`.
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    /// use sakura::RemoveBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let grandchild_id = tree.insert(Node::new(2), UnderNode(&child_id)).unwrap();
+    /// tree.remove_node(child_id, OrphanChildren).unwrap();
+    ///
+    /// let ids: Vec<NodeId> = tree.node_ids().collect();
+    ///
+    /// # assert_eq!(ids, vec![root_id, grandchild_id]);
+    /// ```
+    pub fn node_ids(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.is_some())
+            .map(|(index, _)| NodeId::new(index))
+    }
+
+    /// Returns the number of slots the `Tree` uses for its `Node`s, including
+    /// the holes left behind by removed `Node`s.
+    ///
+    /// Together with `free_count`, this helps with tuning `TreeBuilder`
+    /// capacities, or deciding when to rebuild a long-lived `Tree`.
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    /// use sakura::RemoveBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// tree.remove_node(child_id, DropChildren).unwrap();
+    ///
+    /// # assert_eq!(tree.slot_count(), 2);
+    /// # assert_eq!(tree.len(), 1);
+    /// ```
+    #[must_use]
+    pub const fn slot_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns the number of holes left behind by removed `Node`s, which
+    /// will be reused by the next insertions.
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    /// use sakura::RemoveBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// tree.remove_node(child_id, DropChildren).unwrap();
+    ///
+    /// # assert_eq!(tree.free_count(), 1);
+    /// ```
+    #[must_use]
+    pub const fn free_count(&self) -> usize {
+        self.free_ids.len()
+    }
+
+    /// Returns a rough estimate of the memory used by the `Tree`, in bytes.
+    ///
+    /// This sums up the allocated capacities of the `Tree`'s storage and of
+    /// every `Node`s children, but can't see memory owned by the data itself
+    /// (e.g. the contents of a `String`), so it is a lower bound.
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let empty = tree.estimated_size_bytes();
+    /// tree.insert(Node::new(0), AsRoot).unwrap();
+    ///
+    /// # assert!(tree.estimated_size_bytes() > empty);
+    /// ```
+    #[must_use]
+    pub fn estimated_size_bytes(&self) -> usize {
+        use std::mem::size_of;
+
+        let children: usize = self
+            .nodes
+            .iter()
+            .flatten()
+            .map(|node| node.children.capacity())
+            .sum();
+
+        size_of::<Self>()
+            + self.nodes.capacity() * size_of::<Option<Node<T>>>()
+            + (self.free_ids.capacity() + children) * size_of::<NodeId>()
+    }
+
+    /// Shrinks the capacity of the `Tree`'s storage, and of every `Node`s
+    /// children, as much as possible.
+    ///
+    /// NOTE: The holes left behind by removed `Node`s are kept, so that no
+    /// `NodeId` changes, use `rebuild_indices_preorder` to drop them as well.
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = TreeBuilder::new().with_node_capacity(10).build();
+    /// tree.insert(Node::new(0), AsRoot).unwrap();
+    ///
+    /// tree.shrink_to_fit();
+    ///
+    /// # assert_eq!(tree.capacity(), 1);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.nodes.shrink_to_fit();
+        self.free_ids.shrink_to_fit();
+
+        for node in self.nodes.iter_mut().flatten() {
+            node.children.shrink_to_fit();
+        }
+    }
+
+    /// Tries to reserve room for at least `additional` more `Node`s, and for
+    /// removing as many later on, without reallocating.
+    ///
+    /// Unlike growing the `Tree` by inserting, this doesn't abort the process
+    /// when the allocation fails, e.g. before a large import on a memory
+    /// constrained target.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the capacity would overflow, or the allocator reports a
+    /// failure. The contents of the `Tree` are left untouched in that case,
+    /// although the room for new `Node`s may already have been reserved.
+    ///
+    /// ```
+    /// use sakura::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    ///
+    /// tree.try_reserve(100).unwrap();
+    /// # assert!(tree.capacity() >= 100);
+    ///
+    /// assert!(tree.try_reserve(usize::MAX).is_err());
+    /// # assert!(tree.is_empty());
+    /// ```
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.nodes.try_reserve(additional)?;
+        self.free_ids.try_reserve(additional)
+    }
+
     /// Returns a `Some` value containing the `NodeId` of the root `Node` if
     /// it exists. Otherwise, a `None` is returned.
     ///
@@ -245,6 +614,60 @@ impl<T> Tree<T> {
         self.root.as_ref()
     }
 
+    /// Returns the `NodeId` of the root `Node`, first creating a root from
+    /// `f()` if the `Tree` is empty.
+    ///
+    /// `f` is only called when a root has to be created.
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.root_or_insert_with(|| 0);
+    /// tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    ///
+    /// # assert_eq!(tree.root_or_insert_with(|| 2), root_id);
+    /// # assert_eq!(tree.get(&root_id).unwrap().data(), &0);
+    /// ```
+    pub fn root_or_insert_with<F>(&mut self, f: F) -> NodeId
+    where
+        F: FnOnce() -> T,
+    {
+        match &self.root {
+            Some(root_id) => root_id.clone(),
+            None => self.set_root(Node::new(f())),
+        }
+    }
+
+    /// Makes `node` the new root `Node`, leaving the old root and its subtree
+    /// in the `Tree` as an orphan instead of moving it under the new root, like
+    /// `InsertBehavior::AsRoot` does.
+    ///
+    /// Returns the `NodeId` of the new root, and that of the old root if
+    /// there was one, so the caller can decide what to do with it.
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let old_root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    ///
+    /// let (new_root_id, orphan_id) = tree.set_root_orphaning_old(Node::new(1));
+    ///
+    /// # assert_eq!(tree.root_node_id(), Some(&new_root_id));
+    /// # assert_eq!(orphan_id, Some(old_root_id.clone()));
+    /// # assert_eq!(tree.parent(&old_root_id).unwrap(), None);
+    /// # assert!(tree.children(&new_root_id).unwrap().next().is_none());
+    /// ```
+    pub fn set_root_orphaning_old(&mut self, node: Node<T>) -> (NodeId, Option<NodeId>) {
+        let new_root_id = self.insert_new_node(node);
+        let old_root_id = self.root.replace(new_root_id.clone());
+
+        (new_root_id, old_root_id)
+    }
+
     /// Returns the maximum height of the `Tree`.
     ///
     /// ```
@@ -267,6 +690,39 @@ impl<T> Tree<T> {
             .map_or_else(|| 0, |id| self.height_of_node(id))
     }
 
+    /// Same as `height`, but remembers the result until the structure of the
+    /// `Tree` changes, which makes repeated calls O(1).
+    ///
+    /// Inserting, removing or moving `Node`s invalidates the cached height,
+    /// it is then recomputed by the next call. Changing data or the order of
+    /// siblings keeps it. Use `height` where only a shared borrow is available.
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(1), AsRoot).unwrap();
+    /// # assert_eq!(1, tree.cached_height());
+    ///
+    /// tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    /// # assert_eq!(2, tree.cached_height());
+    /// ```
+    pub fn cached_height(&mut self) -> usize {
+        if let Some(height) = self.height_cache.0 {
+            return height;
+        }
+
+        let height = self.height();
+        self.height_cache.0 = Some(height);
+        height
+    }
+
+    /// Drops the cached height, for any change to the structure of the `Tree`.
+    const fn invalidate_height(&mut self) {
+        self.height_cache.0 = None;
+    }
+
     fn height_of_node(&self, node: &NodeId) -> usize {
         let mut h = 0;
         for n in self.children_ids(node).unwrap() {
@@ -354,26 +810,149 @@ impl<T> Tree<T> {
             .ok_or(NodeIdError::NodeIdNoLongerValid)
     }
 
-    /// Inserts a `Node` into the `Tree`, via the provided `InsertBehavior`
+    /// Gets a reference to a `Node` from the `Tree`, if there is one.
     ///
-    /// # Errors
+    /// Unlike `get`, this never panics and returns `None` both for `NodeId`s
+    /// that were removed from the `Tree` and for ones that were never part of
+    /// it.
     ///
-    /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
     ///
-    /// # Panics
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(5), AsRoot).unwrap();
     ///
-    /// Can panic if the `NodeId` does not exist in the `Tree`, but this would
-    /// be a bug in `Sakura`
+    /// # assert_eq!(tree.try_get(&root_id).map(Node::data), Some(&5));
+    /// ```
+    #[must_use]
+    pub fn try_get(&self, node_id: &NodeId) -> Option<&Node<T>> {
+        self.nodes.get(node_id.index as usize)?.as_ref()
+    }
+
+    /// Gets a mutable reference to a `Node` from the `Tree`, if there is one.
+    ///
+    /// Unlike `get_mut`, this never panics and returns `None` both for
+    /// `NodeId`s that were removed from the `Tree` and for ones that were
+    /// never part of it.
     ///
     /// ```
     /// use sakura::*;
     /// use sakura::InsertBehavior::*;
     ///
-    /// let root_node = Node::new(1);
-    /// let child_node = Node::new(2);
-    ///
     /// let mut tree: Tree<i32> = Tree::new();
-    /// let root_id = tree.insert(root_node, AsRoot).unwrap();
+    /// let root_id = tree.insert(Node::new(5), AsRoot).unwrap();
+    ///
+    /// if let Some(root) = tree.try_get_mut(&root_id) {
+    ///     *root.data_mut() = 6;
+    /// }
+    ///
+    /// # assert_eq!(tree.get(&root_id).unwrap().data(), &6);
+    /// ```
+    pub fn try_get_mut(&mut self, node_id: &NodeId) -> Option<&mut Node<T>> {
+        self.nodes.get_mut(node_id.index as usize)?.as_mut()
+    }
+
+    /// Whether the `NodeId` currently refers to a `Node` in the `Tree`.
+    ///
+    /// Returns `false` for `NodeId`s that were removed from the `Tree` and for
+    /// ones that were never part of it, without panicking.
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(5), AsRoot).unwrap();
+    ///
+    /// # assert!(tree.contains(&root_id));
+    /// ```
+    #[must_use]
+    pub fn contains(&self, node_id: &NodeId) -> bool {
+        self.try_get(node_id).is_some()
+    }
+
+    /// Checks that the `Tree` is consistent, for one that didn't come from
+    /// `Sakura` itself, e.g. a deserialized document.
+    ///
+    /// That is, every free `NodeId` is an unused slot listed once, every
+    /// child points back at the `Node` listing it as its parent, no parents
+    /// loop and every `Node` can be reached from the root.
+    ///
+    /// # Errors
+    ///
+    /// Errors with `NodeIdError::InvalidFreeId`, `NodeIdError::NotAChild`,
+    /// `NodeIdError::ParentCycle` or `NodeIdError::Unreachable` respectively,
+    /// or like `get` if a `Node` points at an id the `Tree` doesn't have.
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    ///
+    /// assert_eq!(tree.validate(), Ok(()));
+    /// ```
+    pub fn validate(&self) -> Result<(), NodeIdError> {
+        let mut free = vec![false; self.nodes.len()];
+        for free_id in &self.free_ids {
+            match free.get_mut(free_id.index as usize) {
+                Some(seen) if !*seen && self.nodes[free_id.index as usize].is_none() => {
+                    *seen = true;
+                }
+                _ => return Err(NodeIdError::InvalidFreeId),
+            }
+        }
+
+        for node_id in self.node_ids() {
+            let node = self.get(&node_id)?;
+            for (index, child_id) in node.children().iter().enumerate() {
+                if self.get(child_id)?.parent() != Some(&node_id)
+                    || node.children()[..index].contains(child_id)
+                {
+                    return Err(NodeIdError::NotAChild);
+                }
+            }
+
+            self.for_each_ancestor(&node_id, |_| {})?;
+        }
+
+        let reachable = match &self.root {
+            Some(root_id) => {
+                self.get(root_id)?;
+                self.traverse_pre_order_ids(root_id)?.count()
+            }
+            None => 0,
+        };
+        if reachable != self.node_ids().count() {
+            return Err(NodeIdError::Unreachable);
+        }
+
+        Ok(())
+    }
+
+    /// Inserts a `Node` into the `Tree`, via the provided `InsertBehavior`
+    ///
+    /// # Errors
+    ///
+    /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    ///
+    /// # Panics
+    ///
+    /// Can panic if the `NodeId` does not exist in the `Tree`, but this would
+    /// be a bug in `Sakura`
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let root_node = Node::new(1);
+    /// let child_node = Node::new(2);
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(root_node, AsRoot).unwrap();
     ///
     /// tree.insert(child_node, UnderNode(&root_id)).unwrap();
     /// ```
@@ -437,6 +1016,36 @@ impl<T> Tree<T> {
         }
     }
 
+    /// Removes a `Node` from the `Tree` via the provided `RemoveBehavior`,
+    /// returning only its data.
+    ///
+    /// # Errors
+    ///
+    /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    /// use sakura::RemoveBehavior::*;
+    ///
+    /// let mut tree: Tree<String> = Tree::new();
+    /// let root_id = tree.insert(Node::new("home".to_owned()), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new("work".to_owned()), UnderNode(&root_id)).unwrap();
+    ///
+    /// let work: String = tree.take_data(child_id, DropChildren).unwrap();
+    ///
+    /// # assert_eq!(work, "work");
+    /// # assert_eq!(tree.len(), 1);
+    /// ```
+    pub fn take_data(
+        &mut self,
+        node_id: NodeId,
+        behavior: RemoveBehavior,
+    ) -> Result<T, NodeIdError> {
+        self.get(&node_id)?;
+        self.remove_node(node_id, behavior).map(Node::into_data)
+    }
+
     /// Remove a `Node` from the `Tree`, while transferring all of its children
     /// to its parent.
     fn remove_node_lift_children(&mut self, node_id: NodeId) -> Node<T> {
@@ -480,11 +1089,112 @@ impl<T> Tree<T> {
         self.remove_node_internal(node_id)
     }
 
+    /// Removes every `Node` matching `predicate` and returns their data, in
+    /// pre-order of the root subtree followed by any orphaned subtrees.
+    ///
+    /// The children of a removed `Node` are kept in the `Tree` under the
+    /// closest surviving ancestor, taking the place of the removed `Node`
+    /// among its siblings. The root is never drained, as its children would
+    /// have nowhere to go. `predicate` sees the `Tree` as it was before
+    /// anything was removed.
+    ///
+    /// # Panics
+    ///
+    /// Can panic if a `NodeId` does not exist in the `Tree`, but this would
+    /// be a bug in `Sakura`
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let odd_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let even_id = tree.insert(Node::new(2), UnderNode(&odd_id)).unwrap();
+    ///
+    /// let odd = tree.drain_where(|node| node.data() % 2 == 1);
+    ///
+    /// # assert_eq!(odd, vec![1]);
+    /// # assert_eq!(tree.get(&root_id).unwrap().children(), &vec![even_id]);
+    /// ```
+    pub fn drain_where<F>(&mut self, mut predicate: F) -> Vec<T>
+    where
+        F: FnMut(&Node<T>) -> bool,
+    {
+        let orphans = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.as_ref().is_some_and(|node| node.parent.is_none()))
+            .map(|(index, _)| NodeId::new(index))
+            .filter(|id| Some(id) != self.root.as_ref());
+
+        let mut matching = vec![];
+        for start in self.root.iter().cloned().chain(orphans) {
+            for node_id in self
+                .traverse_pre_order_ids(&start)
+                .expect("Tree::drain_where: start is a valid node id")
+            {
+                let node = self
+                    .get(&node_id)
+                    .expect("Tree::drain_where: traversed ids are valid");
+                if Some(&node_id) != self.root.as_ref() && predicate(node) {
+                    matching.push(node_id);
+                }
+            }
+        }
+
+        matching
+            .into_iter()
+            .map(|node_id| self.remove_node_splice_children(node_id).data)
+            .collect()
+    }
+
+    /// Remove a `Node` from the `Tree`, putting its children in its place
+    /// among the children of its parent, or orphaning them if it has none.
+    fn remove_node_splice_children(&mut self, node_id: NodeId) -> Node<T> {
+        let Some(parent_id) = self
+            .get(&node_id)
+            .expect("Tree::remove_node_splice_children: Expecting node_id to be valid.")
+            .parent()
+            .cloned()
+        else {
+            return self.remove_node_orphan_children(node_id);
+        };
+
+        self.invalidate_height();
+
+        let children = self
+            .get_mut(&node_id)
+            .expect("Tree::remove_node_splice_children: Expecting node_id to be valid.")
+            .take_children();
+        for child_id in &children {
+            self.get_mut(child_id)
+                .expect("Tree::remove_node_splice_children: Expecting children to be valid.")
+                .set_parent(Some(parent_id.clone()));
+        }
+
+        let siblings = self
+            .get_mut(&parent_id)
+            .expect("Tree::remove_node_splice_children: Expecting parent_id to be valid.")
+            .children_mut();
+        let index = siblings
+            .iter()
+            .position(|child_id| *child_id == node_id)
+            .expect(
+                "Tree::remove_node_splice_children: Expecting node_id to be a child of its parent.",
+            );
+        siblings.splice(index..=index, children);
+
+        self.remove_node_internal(node_id)
+    }
+
     /// Moves a `Node` in the `Tree`, via the provided `MoveBehavior`
     ///
     /// # Errors
     ///
     /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    /// A corrupt `Tree` whose parents loop errors with `NodeIdError::ParentCycle`.
     ///
     /// # Panics
     ///
@@ -500,20 +1210,109 @@ impl<T> Tree<T> {
         self.is_valid_node_id(node_id)?;
 
         match behavior {
-            MoveBehavior::ToRoot => {
-                self.move_node_to_root(node_id);
+            MoveBehavior::ToRoot => self.move_node_to_root(node_id),
+            MoveBehavior::ToParent(parent_id) => self.move_node_to_parent(node_id, parent_id),
+            MoveBehavior::ToParentAtIndex(parent_id, index) => {
+                self.get(parent_id)?;
+                self.move_node_to_parent(node_id, parent_id)?;
+                self.move_node_to_index(node_id, parent_id, index);
                 Ok(())
             }
-            MoveBehavior::ToParent(parent_id) => {
-                self.move_node_to_parent(node_id, parent_id);
-                Ok(())
+        }
+    }
+
+    /// Moves several `Node`s under `new_parent`, each one's subtree moving
+    /// along with it.
+    ///
+    /// The moved `Node`s are appended to the children of `new_parent` in the
+    /// order they were given. A `Node` whose ancestor is also being moved
+    /// is skipped, since it already travels with that ancestor.
+    ///
+    /// Nothing is moved if any of the `NodeId`s are invalid, or if any of the
+    /// `Node`s is `new_parent` or one of its ancestors.
+    ///
+    /// # Errors
+    ///
+    /// Can error if any of the given `NodeId`s is not valid (i.e. it was removed
+    /// from the `Tree`), or with `NodeIdError::WouldCreateCycle` if a `Node`
+    /// would be moved under itself.
+    ///
+    /// # Panics
+    ///
+    /// Can panic if the `NodeId` does not exist in the `Tree`, but this would
+    /// be a bug in `Sakura`
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let target_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let a_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    /// let b_id = tree.insert(Node::new(3), UnderNode(&root_id)).unwrap();
+    ///
+    /// tree.move_many(&[b_id.clone(), a_id.clone()], &target_id).unwrap();
+    ///
+    /// # assert_eq!(tree.get(&target_id).unwrap().children(), &vec![b_id, a_id]);
+    /// assert_eq!(
+    ///     tree.move_many(&[root_id], &target_id),
+    ///     Err(NodeIdError::WouldCreateCycle)
+    /// );
+    /// ```
+    pub fn move_many(&mut self, nodes: &[NodeId], new_parent: &NodeId) -> Result<(), NodeIdError> {
+        use std::collections::HashSet;
+
+        self.get(new_parent)?;
+
+        let selected: HashSet<&NodeId> = nodes.iter().collect();
+        for node_id in &selected {
+            self.get(node_id)?;
+        }
+
+        if selected.contains(new_parent)
+            || self
+                .ancestor_ids(new_parent)?
+                .any(|ancestor_id| selected.contains(ancestor_id))
+        {
+            return Err(NodeIdError::WouldCreateCycle);
+        }
+
+        let mut moved = HashSet::new();
+        let to_move: Vec<NodeId> = nodes
+            .iter()
+            .filter(|node_id| {
+                !self
+                    .ancestor_ids(node_id)
+                    .expect("Tree::move_many: node ids were validated")
+                    .any(|ancestor_id| selected.contains(ancestor_id))
+            })
+            .filter(|node_id| moved.insert(*node_id))
+            .cloned()
+            .collect();
+
+        for node_id in &to_move {
+            if let Some(old_parent) = self
+                .get(node_id)
+                .expect("Tree::move_many: node ids were validated")
+                .parent()
+                .cloned()
+            {
+                self.detach_from_parent(&old_parent, node_id);
             }
+            self.set_as_parent_and_child(new_parent, node_id);
         }
+
+        Ok(())
     }
 
-    fn move_node_to_parent(&mut self, node_id: &NodeId, parent_id: &NodeId) {
+    fn move_node_to_parent(
+        &mut self,
+        node_id: &NodeId,
+        parent_id: &NodeId,
+    ) -> Result<(), NodeIdError> {
         if let Some(subtree_root_id) = self
-            .find_subtree_root_between_ids(parent_id, node_id)
+            .find_subtree_root_between_ids(parent_id, node_id)?
             .cloned()
         {
             // node_id is above parent_id, this is a move "down" the tree
@@ -567,6 +1366,127 @@ impl<T> Tree<T> {
             }
         }
         self.set_as_parent_and_child(parent_id, node_id);
+
+        Ok(())
+    }
+
+    /// Moves `node_id`, which must be the last child of `parent_id`, to `index`
+    /// among its siblings, clamped to the number of children.
+    fn move_node_to_index(&mut self, node_id: &NodeId, parent_id: &NodeId, index: usize) {
+        let children = self
+            .get_mut(parent_id)
+            .expect("Tree::move_node_to_index: Expecting valid parent_id")
+            .children_mut();
+
+        let moved = children
+            .pop()
+            .expect("Tree::move_node_to_index: Expecting node to be the last child");
+        debug_assert_eq!(&moved, node_id);
+
+        children.insert(std::cmp::min(index, children.len()), moved);
+    }
+
+    /// Splices a new `Node` onto the edge between `parent` and `child`: the
+    /// new `Node` takes the place of `child` among the children of `parent`,
+    /// and `child` (with its subtree) becomes its only child.
+    ///
+    /// Returns the `NodeId` of the new `Node`.
+    ///
+    /// # Errors
+    ///
+    /// Can error if either of the given `NodeId`s is not valid (i.e. it was
+    /// removed from the `Tree`), or with `NodeIdError::NotAChild` if `child`
+    /// is not a child of `parent`, leaving the `Tree` untouched.
+    ///
+    /// # Panics
+    ///
+    /// Can panic if the `NodeId` does not exist in the `Tree`, but this would
+    /// be a bug in `Sakura`
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<&str> = Tree::new();
+    /// let root_id = tree.insert(Node::new("root"), AsRoot).unwrap();
+    /// let task_id = tree.insert(Node::new("task"), UnderNode(&root_id)).unwrap();
+    ///
+    /// let group_id = tree
+    ///     .insert_between(&root_id, &task_id, Node::new("group"))
+    ///     .unwrap();
+    ///
+    /// # assert_eq!(tree.parent(&group_id).unwrap(), Some(root_id));
+    /// # assert_eq!(tree.parent(&task_id).unwrap(), Some(group_id));
+    /// ```
+    pub fn insert_between(
+        &mut self,
+        parent: &NodeId,
+        child: &NodeId,
+        node: Node<T>,
+    ) -> Result<NodeId, NodeIdError> {
+        self.get(child)?;
+        let index = self
+            .get(parent)?
+            .children
+            .iter()
+            .position(|child_id| child_id == child)
+            .ok_or(NodeIdError::NotAChild)?;
+
+        let new_id = self.insert_new_node(node);
+        self.get_mut(parent)
+            .expect("Tree::insert_between: parent was checked to be valid")
+            .children_mut()[index] = new_id.clone();
+        self.set_parent(&new_id, Some(parent.clone()));
+        self.set_as_parent_and_child(&new_id, child);
+
+        Ok(new_id)
+    }
+
+    /// Swaps the data of two `Node`s, leaving the structure of the `Tree`
+    /// untouched: both `Node`s keep their parents and children.
+    ///
+    /// # Errors
+    ///
+    /// Can error if either of the given `NodeId`s is not valid (i.e. it was removed from the `Tree`.)
+    ///
+    /// # Panics
+    ///
+    /// Can panic if the `NodeId` does not exist in the `Tree`, but this would
+    /// be a bug in `Sakura`
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    ///
+    /// tree.swap_data(&root_id, &child_id).unwrap();
+    ///
+    /// # assert_eq!(tree.get(&root_id).unwrap().data(), &1);
+    /// # assert_eq!(tree.get(&child_id).unwrap().data(), &0);
+    /// # assert_eq!(tree.root_node_id(), Some(&root_id));
+    /// ```
+    pub fn swap_data(&mut self, a: &NodeId, b: &NodeId) -> Result<(), NodeIdError> {
+        self.get(a)?;
+        self.get(b)?;
+
+        if a == b {
+            return Ok(());
+        }
+
+        let [Some(node_a), Some(node_b)] = self
+            .nodes
+            .get_disjoint_mut([a.index as usize, b.index as usize])
+            .expect("Tree::swap_data: indices are valid and distinct")
+        else {
+            unreachable!("Tree::swap_data: both nodes were validated");
+        };
+
+        std::mem::swap(&mut node_a.data, &mut node_b.data);
+
+        Ok(())
     }
 
     /// Sorts the children of a `Node`, in-place, using compare to compare
@@ -687,36 +1607,63 @@ impl<T> Tree<T> {
         Ok(())
     }
 
-    /// Returns an `Ancestors` iterator
+    /// Reverses the order of the children of every `Node` in the subtree
+    /// starting at `start`, including `start` itself.
+    ///
+    /// This is cheaper than sorting again when only the opposite of the
+    /// current order is wanted.
     ///
     /// # Errors
     ///
     /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
     ///
-    /// # Panics
-    ///
-    /// Can panic if the `NodeId` does not exist in the `Tree`, but this would
-    /// be a bug in `Sakura`
-    ///
     /// ```
     /// use sakura::*;
     /// use sakura::InsertBehavior::*;
     ///
     /// let mut tree: Tree<i32> = Tree::new();
     /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
-    /// let node_1 = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let first_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let second_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
     ///
-    /// let mut ancestors = tree.ancestors(&node_1).unwrap();
+    /// tree.reverse_children_recursive(&root_id).unwrap();
     ///
-    /// # assert_eq!(ancestors.next().unwrap().data(), &0);
-    /// # assert!(ancestors.next().is_none());
+    /// # assert_eq!(tree.get(&root_id).unwrap().children(), &vec![second_id, first_id]);
     /// ```
-    pub fn ancestors(&self, node_id: &NodeId) -> Result<Ancestors<'_, T>, NodeIdError> {
-        self.is_valid_node_id(node_id)?;
-        Ok(Ancestors::new(self, node_id.clone()))
+    pub fn reverse_children_recursive(&mut self, start: &NodeId) -> Result<(), NodeIdError> {
+        self.walk_mut(start, Node::reverse_children)
     }
 
-    /// Returns an `AncestorIds` iterator
+    /// Returns an `Ancestors` iterator
+    ///
+    /// # Errors
+    ///
+    /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    ///
+    /// # Panics
+    ///
+    /// Can panic if the `NodeId` does not exist in the `Tree`, but this would
+    /// be a bug in `Sakura`
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let node_1 = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    ///
+    /// let mut ancestors = tree.ancestors(&node_1).unwrap();
+    ///
+    /// # assert_eq!(ancestors.next().unwrap().data(), &0);
+    /// # assert!(ancestors.next().is_none());
+    /// ```
+    pub fn ancestors(&self, node_id: &NodeId) -> Result<Ancestors<'_, T>, NodeIdError> {
+        self.is_valid_node_id(node_id)?;
+        Ok(Ancestors::new(self, node_id.clone()))
+    }
+
+    /// Returns an `AncestorIds` iterator
     ///
     ///
     /// # Errors
@@ -749,6 +1696,270 @@ impl<T> Tree<T> {
         Ok(AncestorsIds::new(self, node_id.clone()))
     }
 
+    /// Returns references to the data of every `Node` from the root down to
+    /// the given `Node`, both included, e.g. for breadcrumbs.
+    ///
+    /// For a `Node` in a subtree without a parent, the path starts at the top
+    /// of that subtree instead.
+    ///
+    /// # Errors
+    ///
+    /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<&str> = Tree::new();
+    /// let root_id = tree.insert(Node::new("home"), AsRoot).unwrap();
+    /// let work_id = tree.insert(Node::new("work"), UnderNode(&root_id)).unwrap();
+    /// let task_id = tree.insert(Node::new("report"), UnderNode(&work_id)).unwrap();
+    ///
+    /// assert_eq!(
+    ///     tree.get_path_data(&task_id).unwrap(),
+    ///     [&"home", &"work", &"report"]
+    /// );
+    /// ```
+    pub fn get_path_data(&self, node_id: &NodeId) -> Result<Vec<&T>, NodeIdError> {
+        let node = self.get(node_id)?;
+
+        let mut path: Vec<&T> = std::iter::once(node)
+            .chain(self.ancestors(node_id)?)
+            .map(Node::data)
+            .collect();
+        path.reverse();
+
+        Ok(path)
+    }
+
+    /// Calls `f` on every ancestor of the given `Node`, nearest ancestor
+    /// first and the root last. The `Node` itself is not visited.
+    ///
+    /// Visits the same `Node`s as `ancestors`, without building an iterator.
+    ///
+    /// # Errors
+    ///
+    /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    /// A corrupt `Tree` errors with `NodeIdError::NodeIdNoLongerValid` for a
+    /// parent that was removed, or `NodeIdError::ParentCycle` if the parents
+    /// loop, after visiting at most as many `Node`s as there are.
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let grandchild_id = tree.insert(Node::new(2), UnderNode(&child_id)).unwrap();
+    ///
+    /// let mut breadcrumbs = vec![];
+    /// tree.for_each_ancestor(&grandchild_id, |node| breadcrumbs.push(*node.data())).unwrap();
+    ///
+    /// # assert_eq!(breadcrumbs, vec![1, 0]);
+    /// ```
+    pub fn for_each_ancestor<F>(&self, start: &NodeId, mut f: F) -> Result<(), NodeIdError>
+    where
+        F: FnMut(&Node<T>),
+    {
+        let mut parent = self.get(start)?.parent();
+
+        // A `Node` has fewer ancestors than there are `Node`s, unless they loop.
+        for _ in 0..self.nodes.len() {
+            let Some(parent_id) = parent else {
+                return Ok(());
+            };
+            let node = self.get(parent_id)?;
+            f(node);
+            parent = node.parent();
+        }
+
+        Err(NodeIdError::ParentCycle)
+    }
+
+    /// Returns whether `ancestor` is a strict ancestor of `descendant`, i.e.
+    /// whether `descendant` is somewhere in the subtree below `ancestor`.
+    ///
+    /// A `Node` is not its own ancestor.
+    ///
+    /// # Errors
+    ///
+    /// Can error if either `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let grandchild_id = tree.insert(Node::new(2), UnderNode(&child_id)).unwrap();
+    ///
+    /// assert!(tree.is_ancestor_of(&root_id, &grandchild_id).unwrap());
+    /// # assert!(!tree.is_ancestor_of(&grandchild_id, &root_id).unwrap());
+    /// # assert!(!tree.is_ancestor_of(&child_id, &child_id).unwrap());
+    /// ```
+    pub fn is_ancestor_of(
+        &self,
+        ancestor: &NodeId,
+        descendant: &NodeId,
+    ) -> Result<bool, NodeIdError> {
+        self.get(ancestor)?;
+        self.get(descendant)?;
+
+        Ok(self
+            .ancestor_ids(descendant)?
+            .any(|ancestor_id| ancestor_id == ancestor))
+    }
+
+    /// Returns the deepest `Node` that has both `a` and `b` in its subtree,
+    /// or `None` if they are not connected.
+    ///
+    /// A `Node` is in its own subtree, so the lowest common ancestor of a
+    /// `Node` and one of its descendants is that `Node` itself.
+    ///
+    /// # Errors
+    ///
+    /// Can error if either `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let grandchild_id = tree.insert(Node::new(2), UnderNode(&child_id)).unwrap();
+    /// let other_id = tree.insert(Node::new(3), UnderNode(&root_id)).unwrap();
+    ///
+    /// assert_eq!(tree.lowest_common_ancestor(&grandchild_id, &other_id).unwrap(), Some(root_id));
+    /// # assert_eq!(tree.lowest_common_ancestor(&child_id, &grandchild_id).unwrap(), Some(child_id));
+    /// ```
+    pub fn lowest_common_ancestor(
+        &self,
+        a: &NodeId,
+        b: &NodeId,
+    ) -> Result<Option<NodeId>, NodeIdError> {
+        Ok(self
+            .lca_positions(a, b)?
+            .map(|(a_chain, a_index, _, _)| a_chain[a_index].clone()))
+    }
+
+    /// Returns the `Node`s on the way from `a` up to their lowest common
+    /// ancestor and down to `b`, both ends included, or `None` if they are
+    /// not connected.
+    ///
+    /// # Errors
+    ///
+    /// Can error if either `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let grandchild_id = tree.insert(Node::new(2), UnderNode(&child_id)).unwrap();
+    /// let other_id = tree.insert(Node::new(3), UnderNode(&root_id)).unwrap();
+    ///
+    /// let path = tree.path_between(&grandchild_id, &other_id).unwrap().unwrap();
+    ///
+    /// # assert_eq!(path, vec![grandchild_id, child_id, root_id, other_id]);
+    /// ```
+    pub fn path_between(&self, a: &NodeId, b: &NodeId) -> Result<Option<Vec<NodeId>>, NodeIdError> {
+        Ok(self
+            .lca_positions(a, b)?
+            .map(|(mut a_chain, a_index, b_chain, b_index)| {
+                a_chain.truncate(a_index + 1);
+                a_chain.extend(b_chain[..b_index].iter().rev().cloned());
+                a_chain
+            }))
+    }
+
+    /// Returns the longest path from the top of the `Tree` that the paths to
+    /// all of the given `Node`s start with, e.g. a breadcrumb shared by all of
+    /// them.
+    ///
+    /// The path to a single `Node` ends with the `Node` itself, and no `Node`s
+    /// share an empty path.
+    ///
+    /// # Errors
+    ///
+    /// Can error if any `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let a_id = tree.insert(Node::new(2), UnderNode(&child_id)).unwrap();
+    /// let b_id = tree.insert(Node::new(3), UnderNode(&child_id)).unwrap();
+    ///
+    /// let prefix = tree.common_path_prefix(&[a_id, b_id]).unwrap();
+    ///
+    /// # assert_eq!(prefix, vec![root_id, child_id]);
+    /// ```
+    pub fn common_path_prefix(&self, ids: &[NodeId]) -> Result<Vec<NodeId>, NodeIdError> {
+        let path = |node_id: &NodeId| -> Result<Vec<NodeId>, NodeIdError> {
+            self.get(node_id)?;
+
+            let mut path: Vec<NodeId> = self.ancestor_ids(node_id)?.cloned().collect();
+            path.reverse();
+            path.push(node_id.clone());
+            Ok(path)
+        };
+
+        let Some((first, rest)) = ids.split_first() else {
+            return Ok(vec![]);
+        };
+
+        let mut prefix = path(first)?;
+        for node_id in rest {
+            let other = path(node_id)?;
+            let shared = prefix
+                .iter()
+                .zip(&other)
+                .take_while(|(a, b)| a == b)
+                .count();
+            prefix.truncate(shared);
+        }
+
+        Ok(prefix)
+    }
+
+    /// Finds the lowest common ancestor of `a` and `b` in the chains from
+    /// each of them up to their root, returning both chains along with the
+    /// position of the ancestor in each.
+    #[allow(clippy::type_complexity)]
+    fn lca_positions(
+        &self,
+        a: &NodeId,
+        b: &NodeId,
+    ) -> Result<Option<(Vec<NodeId>, usize, Vec<NodeId>, usize)>, NodeIdError> {
+        self.get(a)?;
+        self.get(b)?;
+
+        let chain = |node_id: &NodeId| -> Result<Vec<NodeId>, NodeIdError> {
+            Ok(std::iter::once(node_id.clone())
+                .chain(self.ancestor_ids(node_id)?.cloned())
+                .collect())
+        };
+        let a_chain = chain(a)?;
+        let b_chain = chain(b)?;
+
+        let position = a_chain.iter().enumerate().find_map(|(a_index, id)| {
+            b_chain
+                .iter()
+                .position(|other| other == id)
+                .map(|b_index| (a_index, b_index))
+        });
+
+        Ok(position.map(|(a_index, b_index)| (a_chain, a_index, b_chain, b_index)))
+    }
+
     /// Returns an `Children` iterator for a given `NodeId`
     ///
     /// # Errors
@@ -807,16 +2018,40 @@ impl<T> Tree<T> {
         Ok(ChildrenIds::new(self, node_id))
     }
 
-    /// Returns a `PreOrderTraversal` iterator
+    /// Returns an iterator over the data of the children of a `Node`, in
+    /// order.
     ///
     /// # Errors
     ///
     /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
     ///
-    /// # Panics
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
     ///
-    /// Can panic if the `NodeId` does not exist in the `Tree`, but this would
-    /// be a bug in `Sakura`
+    /// let mut tree: Tree<&str> = Tree::new();
+    /// let root_id = tree.insert(Node::new("home"), AsRoot).unwrap();
+    /// tree.insert(Node::new("work"), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new("chores"), UnderNode(&root_id)).unwrap();
+    ///
+    /// let names: Vec<&&str> = tree.children_data(&root_id).unwrap().collect();
+    ///
+    /// # assert_eq!(names, [&"work", &"chores"]);
+    /// ```
+    pub fn children_data(
+        &self,
+        node_id: &NodeId,
+    ) -> Result<impl Iterator<Item = &T> + '_, NodeIdError> {
+        self.get(node_id)?;
+        Ok(Children::new(self, node_id).map(Node::data))
+    }
+
+    /// Returns the `NodeId` of the parent of a `Node`, or `None` if it is the
+    /// root (or an orphan).
+    ///
+    /// # Errors
+    ///
+    /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
     ///
     /// ```
     /// use sakura::*;
@@ -824,25 +2059,69 @@ impl<T> Tree<T> {
     ///
     /// let mut tree: Tree<i32> = Tree::new();
     /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
-    /// tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let node_1 = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
     ///
-    /// let mut nodes = tree.traverse_pre_order(&root_id).unwrap();
+    /// # assert_eq!(tree.parent(&node_1).unwrap(), Some(root_id.clone()));
+    /// # assert_eq!(tree.parent(&root_id).unwrap(), None);
+    /// ```
+    pub fn parent(&self, node_id: &NodeId) -> Result<Option<NodeId>, NodeIdError> {
+        Ok(self.get(node_id)?.parent.clone())
+    }
+
+    /// Returns whether a `Node` has no children.
+    ///
+    /// # Errors
+    ///
+    /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
     ///
-    /// # assert_eq!(nodes.next().unwrap().data(), &0);
-    /// # assert_eq!(nodes.next().unwrap().data(), &1);
-    /// # assert!(nodes.next().is_none());
     /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
     ///
-    pub fn traverse_pre_order(
-        &self,
-        node_id: &NodeId,
-    ) -> Result<PreOrderTraversal<'_, T>, NodeIdError> {
-        self.is_valid_node_id(node_id)?;
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let leaf_id = tree.insert(Node::new(2), UnderNode(&child_id)).unwrap();
+    ///
+    /// assert!(tree.is_leaf(&leaf_id).unwrap());
+    /// # assert!(!tree.is_leaf(&child_id).unwrap());
+    /// # assert!(!tree.is_leaf(&root_id).unwrap());
+    /// ```
+    pub fn is_leaf(&self, node_id: &NodeId) -> Result<bool, NodeIdError> {
+        Ok(self.get(node_id)?.children.is_empty())
+    }
 
-        Ok(PreOrderTraversal::new(self, node_id.clone()))
+    /// Returns whether a `Node` is the root of the `Tree`.
+    ///
+    /// Orphans have no parent either, but aren't the root.
+    ///
+    /// # Errors
+    ///
+    /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let leaf_id = tree.insert(Node::new(2), UnderNode(&child_id)).unwrap();
+    ///
+    /// assert!(tree.is_root(&root_id).unwrap());
+    /// # assert!(!tree.is_root(&child_id).unwrap());
+    /// # assert!(!tree.is_root(&leaf_id).unwrap());
+    /// ```
+    pub fn is_root(&self, node_id: &NodeId) -> Result<bool, NodeIdError> {
+        self.get(node_id)?;
+        Ok(self.root.as_ref() == Some(node_id))
     }
 
-    /// Returns a `PreOrderTraversalIds` iterator
+    /// Returns the number of leaves in the subtree of a `Node`, itself
+    /// included if it is a leaf.
+    ///
+    /// Visits every `Node` of the subtree once, so it costs O(subtree size),
+    /// without allocating more than a stack of `NodeId`s.
     ///
     /// # Errors
     ///
@@ -850,8 +2129,8 @@ impl<T> Tree<T> {
     ///
     /// # Panics
     ///
-    /// Can panic if the `NodeId` does not exist in the `Tree`, but this would
-    /// be a bug in `Sakura`
+    /// Can panic if a child `NodeId` does not exist in the `Tree`, but this
+    /// would be a bug in `Sakura`
     ///
     /// ```
     /// use sakura::*;
@@ -859,874 +2138,3693 @@ impl<T> Tree<T> {
     ///
     /// let mut tree: Tree<i32> = Tree::new();
     /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
-    /// tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new(2), UnderNode(&child_id)).unwrap();
+    /// tree.insert(Node::new(3), UnderNode(&child_id)).unwrap();
+    /// tree.insert(Node::new(4), UnderNode(&root_id)).unwrap();
     ///
-    /// let mut nodes = tree.traverse_pre_order_ids(&root_id).unwrap();
+    /// assert_eq!(tree.leaf_count(&root_id).unwrap(), 3);
+    /// # assert_eq!(tree.leaf_count(&child_id).unwrap(), 2);
     ///
-    /// assert_eq!(tree.get(&nodes.next().unwrap()).unwrap().data(), &0);
-    /// assert_eq!(tree.get(&nodes.next().unwrap()).unwrap().data(), &1);
-    /// assert!(nodes.next().is_none());
-    /// ```
+    /// let mut single: Tree<i32> = Tree::new();
+    /// let single_id = single.insert(Node::new(0), AsRoot).unwrap();
     ///
-    pub fn traverse_pre_order_ids(
-        &self,
-        node_id: &NodeId,
-    ) -> Result<PreOrderTraversalIds<'_, T>, NodeIdError> {
-        self.is_valid_node_id(node_id)?;
-
-        Ok(PreOrderTraversalIds::new(self, node_id.clone()))
-    }
-
-    fn move_node_to_root(&mut self, node_id: &NodeId) {
-        let old_root = self.root.clone();
-
-        if let Some(parent_id) = self
-            .get(node_id)
-            .expect("Tree::move_node_to_root Expected a valid NodeId")
-            .parent
-            .clone()
-        {
-            self.detach_from_parent(&parent_id, node_id);
-        }
-
-        self.clear_parent(node_id);
+    /// assert_eq!(single.leaf_count(&single_id).unwrap(), 1);
+    /// ```
+    pub fn leaf_count(&self, node_id: &NodeId) -> Result<usize, NodeIdError> {
+        let mut count = 0;
+        let mut stack = vec![self.get(node_id)?];
 
-        self.root = Some(node_id.clone());
+        while let Some(node) = stack.pop() {
+            if node.children.is_empty() {
+                count += 1;
+            }
 
-        if let Some(old_root) = old_root {
-            self.move_node_to_parent(&old_root, node_id);
+            stack.extend(node.children.iter().map(|child_id| {
+                self.get(child_id)
+                    .expect("Tree::leaf_count: children are valid node ids")
+            }));
         }
-    }
 
-    fn insert_with_parent(&mut self, child: Node<T>, parent_id: &NodeId) -> NodeId {
-        let new_child_id = self.insert_new_node(child);
-        self.set_as_parent_and_child(parent_id, &new_child_id);
-        new_child_id
+        Ok(count)
     }
 
-    fn set_root(&mut self, new_root: Node<T>) -> NodeId {
-        let new_root_id = self.insert_new_node(new_root);
-
-        if let Some(current_root_node_id) = self.root.clone() {
-            self.set_as_parent_and_child(&new_root_id, &current_root_node_id);
+    /// Returns the `NodeId` of the first child of a `Node`, or `None` if it
+    /// is a leaf.
+    ///
+    /// # Errors
+    ///
+    /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let node_1 = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    ///
+    /// # assert_eq!(tree.first_child(&root_id).unwrap(), Some(node_1.clone()));
+    /// # assert_eq!(tree.first_child(&node_1).unwrap(), None);
+    /// ```
+    pub fn first_child(&self, node_id: &NodeId) -> Result<Option<NodeId>, NodeIdError> {
+        Ok(self.get(node_id)?.children.first().cloned())
+    }
+
+    /// Returns the `NodeId` of the last child of a `Node`, or `None` if it
+    /// is a leaf.
+    ///
+    /// # Errors
+    ///
+    /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let node_2 = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    ///
+    /// # assert_eq!(tree.last_child(&root_id).unwrap(), Some(node_2.clone()));
+    /// # assert_eq!(tree.last_child(&node_2).unwrap(), None);
+    /// ```
+    pub fn last_child(&self, node_id: &NodeId) -> Result<Option<NodeId>, NodeIdError> {
+        Ok(self.get(node_id)?.children.last().cloned())
+    }
+
+    /// Returns the index of a `Node` among its siblings along with how many
+    /// siblings there are (itself included), e.g. `(2, 7)` for "3 of 7".
+    ///
+    /// Returns `None` for a `Node` without a parent, like the root.
+    ///
+    /// # Errors
+    ///
+    /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    ///
+    /// # Panics
+    ///
+    /// Can panic if the `Node` is missing from its parent's children, but this
+    /// would be a bug in `Sakura`
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let node_2 = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    ///
+    /// # assert_eq!(tree.position_in_parent(&node_2).unwrap(), Some((1, 2)));
+    /// # assert_eq!(tree.position_in_parent(&root_id).unwrap(), None);
+    /// ```
+    pub fn position_in_parent(
+        &self,
+        node_id: &NodeId,
+    ) -> Result<Option<(usize, usize)>, NodeIdError> {
+        let Some(parent_id) = self.get(node_id)?.parent.as_ref() else {
+            return Ok(None);
+        };
+
+        let siblings = &self.get(parent_id)?.children;
+        let index = siblings
+            .iter()
+            .position(|id| id == node_id)
+            .expect("Tree::position_in_parent: node is missing from its parent's children");
+
+        Ok(Some((index, siblings.len())))
+    }
+
+    /// Returns a `PreOrderTraversal` iterator
+    ///
+    /// # Errors
+    ///
+    /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    ///
+    /// # Panics
+    ///
+    /// Can panic if the `NodeId` does not exist in the `Tree`, but this would
+    /// be a bug in `Sakura`
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    ///
+    /// let mut nodes = tree.traverse_pre_order(&root_id).unwrap();
+    ///
+    /// # assert_eq!(nodes.next().unwrap().data(), &0);
+    /// # assert_eq!(nodes.next().unwrap().data(), &1);
+    /// # assert!(nodes.next().is_none());
+    /// ```
+    ///
+    pub fn traverse_pre_order(
+        &self,
+        node_id: &NodeId,
+    ) -> Result<PreOrderTraversal<'_, T>, NodeIdError> {
+        self.is_valid_node_id(node_id)?;
+
+        Ok(PreOrderTraversal::new(self, node_id.clone()))
+    }
+
+    /// Returns a `PreOrderTraversalIds` iterator
+    ///
+    /// # Errors
+    ///
+    /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    ///
+    /// # Panics
+    ///
+    /// Can panic if the `NodeId` does not exist in the `Tree`, but this would
+    /// be a bug in `Sakura`
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    ///
+    /// let mut nodes = tree.traverse_pre_order_ids(&root_id).unwrap();
+    ///
+    /// assert_eq!(tree.get(&nodes.next().unwrap()).unwrap().data(), &0);
+    /// assert_eq!(tree.get(&nodes.next().unwrap()).unwrap().data(), &1);
+    /// assert!(nodes.next().is_none());
+    /// ```
+    ///
+    pub fn traverse_pre_order_ids(
+        &self,
+        node_id: &NodeId,
+    ) -> Result<PreOrderTraversalIds<'_, T>, NodeIdError> {
+        self.is_valid_node_id(node_id)?;
+
+        Ok(PreOrderTraversalIds::new(self, node_id.clone()))
+    }
+
+    /// Returns an iterator over every path from the root down to a leaf, one
+    /// `Vec<NodeId>` per leaf, starting with the root and ending with the leaf.
+    ///
+    /// Leaves are visited in Pre-Order Traversal order. An empty `Tree` yields
+    /// no paths, and a `Tree` with only a root yields a single path of length 1.
+    ///
+    /// # Panics
+    ///
+    /// Can panic if a `NodeId` does not exist in the `Tree`, but this would
+    /// be a bug in `Sakura`
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// # assert_eq!(tree.root_to_leaf_paths().count(), 0);
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// # assert_eq!(tree.root_to_leaf_paths().collect::<Vec<_>>(), vec![vec![root_id.clone()]]);
+    /// let node_1 = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let node_2 = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    ///
+    /// let paths: Vec<Vec<NodeId>> = tree.root_to_leaf_paths().collect();
+    ///
+    /// # assert_eq!(paths, vec![vec![root_id.clone(), node_1], vec![root_id, node_2]]);
+    /// ```
+    pub fn root_to_leaf_paths(&self) -> impl Iterator<Item = Vec<NodeId>> + '_ {
+        self.root
+            .iter()
+            .flat_map(|root| {
+                self.traverse_pre_order_ids(root)
+                    .expect("Tree::root_to_leaf_paths: the root is a valid node id")
+            })
+            .filter(|node_id| {
+                self.get(node_id)
+                    .expect("Tree::root_to_leaf_paths: traversal only yields valid node ids")
+                    .children
+                    .is_empty()
+            })
+            .map(|leaf| {
+                let mut path: Vec<NodeId> = self
+                    .ancestor_ids(&leaf)
+                    .expect("Tree::root_to_leaf_paths: traversal only yields valid node ids")
+                    .cloned()
+                    .collect();
+                path.reverse();
+                path.push(leaf);
+                path
+            })
+    }
+
+    /// Calls `f` on every `Node` of the subtree starting at `node_id`, in
+    /// Pre-Order Traversal order.
+    ///
+    /// Both visit the same `Node`s by borrowing them, this one just needs no
+    /// iterator struct, which suits visiting every `Node` anyway (e.g.
+    /// streaming a large `Tree` into a sink). Use `traverse_pre_order` when
+    /// you need to stop early or compose with adapters.
+    ///
+    /// # Errors
+    ///
+    /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    ///
+    /// # Panics
+    ///
+    /// Can panic if the `NodeId` does not exist in the `Tree`, but this would
+    /// be a bug in `Sakura`
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    ///
+    /// let mut sum = 0;
+    /// tree.for_each_pre_order(&root_id, |node| sum += node.data()).unwrap();
+    ///
+    /// # assert_eq!(sum, 1);
+    /// ```
+    pub fn for_each_pre_order<F>(&self, node_id: &NodeId, mut f: F) -> Result<(), NodeIdError>
+    where
+        F: FnMut(&Node<T>),
+    {
+        self.get(node_id)?;
+
+        let mut stack = vec![node_id];
+
+        while let Some(node_id) = stack.pop() {
+            let node = self
+                .get(node_id)
+                .expect("Tree::for_each_pre_order: expecting children to be valid node_ids");
+
+            f(node);
+
+            stack.extend(node.children().iter().rev());
+        }
+
+        Ok(())
+    }
+
+    /// Counts the `Node`s of the subtree starting at `start`, itself
+    /// included, that match `predicate`, in a single pass.
+    ///
+    /// # Errors
+    ///
+    /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    ///
+    /// # Panics
+    ///
+    /// Can panic if the `NodeId` does not exist in the `Tree`, but this would
+    /// be a bug in `Sakura`
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new(4), UnderNode(&root_id)).unwrap();
+    ///
+    /// let is_even = |node: &Node<i32>| node.data() % 2 == 0;
+    ///
+    /// assert_eq!(tree.count_where(&root_id, is_even).unwrap(), 3);
+    /// assert_eq!(tree.count_where(&child_id, is_even).unwrap(), 0);
+    /// ```
+    pub fn count_where<F>(&self, start: &NodeId, predicate: F) -> Result<usize, NodeIdError>
+    where
+        F: Fn(&Node<T>) -> bool,
+    {
+        let mut count = 0;
+        self.for_each_pre_order(start, |node| count += usize::from(predicate(node)))?;
+
+        Ok(count)
+    }
+
+    /// Calls `f` with a mutable reference to every `Node` of the subtree
+    /// starting at `start`, in Pre-Order Traversal order.
+    ///
+    /// This is the mutable counterpart of `for_each_pre_order`, useful for
+    /// bulk edits of `Node` data. Children are visited in their order after
+    /// `f` was called on their parent, so reordering them in `f` is reflected
+    /// in the walk.
+    ///
+    /// # Errors
+    ///
+    /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    ///
+    /// # Panics
+    ///
+    /// Can panic if the `NodeId` does not exist in the `Tree`, but this would
+    /// be a bug in `Sakura`
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    ///
+    /// tree.walk_mut(&root_id, |node| *node.data_mut() *= 10).unwrap();
+    ///
+    /// # assert_eq!(tree.get(&child_id).unwrap().data(), &10);
+    /// ```
+    pub fn walk_mut<F>(&mut self, start: &NodeId, mut f: F) -> Result<(), NodeIdError>
+    where
+        F: FnMut(&mut Node<T>),
+    {
+        self.get(start)?;
+
+        let mut stack = vec![start.clone()];
+
+        while let Some(node_id) = stack.pop() {
+            let node = self
+                .get_mut(&node_id)
+                .expect("Tree::walk_mut: expecting children to be valid node_ids");
+
+            f(node);
+
+            stack.extend(node.children().iter().rev().cloned());
+        }
+
+        Ok(())
+    }
+
+    /// Returns the `NodeId`s of every `Node` in the subtree starting at
+    /// `start` (including `start` itself) that satisfies the predicate, in
+    /// Pre-Order Traversal order.
+    ///
+    /// NOTE: This allocates a `Vec` for the matches, prefer
+    /// `traverse_pre_order_ids` with a `filter` if you only need to iterate.
+    ///
+    /// # Errors
+    ///
+    /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    ///
+    /// # Panics
+    ///
+    /// Can panic if the `NodeId` does not exist in the `Tree`, but this would
+    /// be a bug in `Sakura`
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let big_id = tree.insert(Node::new(10), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new(3), UnderNode(&big_id)).unwrap();
+    /// let bigger_id = tree.insert(Node::new(20), UnderNode(&root_id)).unwrap();
+    ///
+    /// let matches = tree.find_all_ids(&root_id, |n| *n.data() > 5).unwrap();
+    ///
+    /// # assert_eq!(matches, vec![big_id, bigger_id]);
+    /// ```
+    pub fn find_all_ids<F>(&self, start: &NodeId, predicate: F) -> Result<Vec<NodeId>, NodeIdError>
+    where
+        F: Fn(&Node<T>) -> bool,
+    {
+        self.get(start)?;
+
+        Ok(self
+            .traverse_pre_order_ids(start)?
+            .filter(|node_id| {
+                predicate(
+                    self.get(node_id)
+                        .expect("Tree::find_all_ids: traversal only yields valid node ids"),
+                )
+            })
+            .collect())
+    }
+
+    /// Returns clones of the data of every `Node` in the subtree starting at
+    /// `start`, including `start` itself, in pre-order.
+    ///
+    /// # Errors
+    ///
+    /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new(2), UnderNode(&child_id)).unwrap();
+    /// tree.insert(Node::new(3), UnderNode(&child_id)).unwrap();
+    /// tree.insert(Node::new(4), UnderNode(&root_id)).unwrap();
+    ///
+    /// let data = tree.collect_subtree_data(&child_id).unwrap();
+    ///
+    /// assert_eq!(data, vec![1, 2, 3]);
+    /// ```
+    pub fn collect_subtree_data(&self, start: &NodeId) -> Result<Vec<T>, NodeIdError>
+    where
+        T: Clone,
+    {
+        self.get(start)?;
+
+        Ok(self
+            .traverse_pre_order(start)?
+            .map(|node| node.data().clone())
+            .collect())
+    }
+
+    /// Walks up from a `Node` and returns the `NodeId` of the first ancestor
+    /// that satisfies the predicate, nearest ancestor first.
+    ///
+    /// The `Node` itself is not tested. Stops as soon as a match is found,
+    /// without collecting the ancestors.
+    ///
+    /// # Errors
+    ///
+    /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    ///
+    /// # Panics
+    ///
+    /// Can panic if the `NodeId` does not exist in the `Tree`, but this would
+    /// be a bug in `Sakura`
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let grandchild_id = tree.insert(Node::new(2), UnderNode(&child_id)).unwrap();
+    ///
+    /// let even = tree.ancestor_matching(&grandchild_id, |n| n.data() % 2 == 0).unwrap();
+    ///
+    /// # assert_eq!(even, Some(root_id));
+    /// ```
+    pub fn ancestor_matching<F>(
+        &self,
+        node_id: &NodeId,
+        predicate: F,
+    ) -> Result<Option<NodeId>, NodeIdError>
+    where
+        F: Fn(&Node<T>) -> bool,
+    {
+        self.get(node_id)?;
+
+        Ok(self
+            .ancestor_ids(node_id)?
+            .find(|ancestor_id| {
+                predicate(
+                    self.get(ancestor_id)
+                        .expect("Tree::ancestor_matching: expecting ancestors to be valid"),
+                )
+            })
+            .cloned())
+    }
+
+    /// Replaces the subtree below a `Node` with the contents of another `Tree`,
+    /// while keeping the `NodeId` of the `Node` stable.
+    ///
+    /// All existing descendants of the `Node` are dropped. The data of `new`'s
+    /// root replaces the data of the `Node`, and `new`'s root children (with
+    /// their subtrees) become the children of the `Node`. If `new` is empty,
+    /// the `Node` keeps its data and simply loses its descendants.
+    ///
+    /// # Errors
+    ///
+    /// Can error if the given `NodeId` is not valid (i.e. it was removed from the `Tree`.)
+    ///
+    /// # Panics
+    ///
+    /// Can panic if the `NodeId` does not exist in the `Tree`, but this would
+    /// be a bug in `Sakura`
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new(2), UnderNode(&child_id)).unwrap();
+    ///
+    /// let mut new: Tree<i32> = Tree::new();
+    /// let new_root_id = new.insert(Node::new(10), AsRoot).unwrap();
+    /// new.insert(Node::new(11), UnderNode(&new_root_id)).unwrap();
+    ///
+    /// tree.replace_subtree(&child_id, new).unwrap();
+    ///
+    /// # assert_eq!(tree.get(&child_id).unwrap().data(), &10);
+    /// # let children: Vec<_> = tree.children(&child_id).unwrap().map(|n| *n.data()).collect();
+    /// # assert_eq!(children, vec![11]);
+    /// ```
+    pub fn replace_subtree(&mut self, node_id: &NodeId, new: Self) -> Result<(), NodeIdError> {
+        let children = self.get_mut(node_id)?.take_children();
+
+        for child_id in children {
+            self.remove_node_drop_children(child_id);
+        }
+
+        let Self {
+            root, mut nodes, ..
+        } = new;
+
+        if let Some(root) = root {
+            let new_root = nodes
+                .get_mut(root.index as usize)
+                .and_then(Option::take)
+                .expect("Tree::replace_subtree: expecting the root of new to be valid");
+
+            self.get_mut(node_id)
+                .expect("Tree::replace_subtree: expecting node_id to be valid")
+                .replace_data(new_root.data);
+
+            self.graft(&mut nodes, new_root.children, node_id);
+        }
+
+        Ok(())
+    }
+
+    /// Inserts the contents of another `Tree` as a sibling of `anchor`, right
+    /// after it if `after` is set, and right before it otherwise.
+    ///
+    /// Returns the `NodeId` of `other`'s root in this `Tree`.
+    ///
+    /// # Errors
+    ///
+    /// Can error if the given `NodeId` is not valid (i.e. it was removed from
+    /// the `Tree`), with `NodeIdError::NoSiblings` if `anchor` has no
+    /// parent, e.g. because it is the root, or with `NodeIdError::EmptyTree`
+    /// if `other` is empty. Nothing is inserted in either case.
+    ///
+    /// # Panics
+    ///
+    /// Can panic if the `NodeId` does not exist in the `Tree`, but this would
+    /// be a bug in `Sakura`
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    ///
+    /// let mut other: Tree<i32> = Tree::new();
+    /// let other_root_id = other.insert(Node::new(10), AsRoot).unwrap();
+    /// other.insert(Node::new(11), UnderNode(&other_root_id)).unwrap();
+    ///
+    /// let new_id = tree.insert_subtree_as_sibling(&child_id, other, false).unwrap();
+    ///
+    /// # assert_eq!(tree.get(&root_id).unwrap().children(), &vec![new_id.clone(), child_id.clone()]);
+    /// # let children: Vec<_> = tree.children(&new_id).unwrap().map(|n| *n.data()).collect();
+    /// # assert_eq!(children, vec![11]);
+    /// assert_eq!(
+    ///     tree.insert_subtree_as_sibling(&root_id, Tree::new(), true),
+    ///     Err(NodeIdError::NoSiblings)
+    /// );
+    /// # assert_eq!(
+    /// #     tree.insert_subtree_as_sibling(&child_id, Tree::new(), true),
+    /// #     Err(NodeIdError::EmptyTree)
+    /// # );
+    /// ```
+    pub fn insert_subtree_as_sibling(
+        &mut self,
+        anchor: &NodeId,
+        other: Self,
+        after: bool,
+    ) -> Result<NodeId, NodeIdError> {
+        let parent_id = self
+            .get(anchor)?
+            .parent
+            .clone()
+            .ok_or(NodeIdError::NoSiblings)?;
+
+        let Self {
+            root, mut nodes, ..
+        } = other;
+
+        let root = root.ok_or(NodeIdError::EmptyTree)?;
+
+        let index = self
+            .get(&parent_id)
+            .expect("Tree::insert_subtree_as_sibling: expecting the parent of anchor to be valid")
+            .children
+            .iter()
+            .position(|child_id| child_id == anchor)
+            .expect(
+                "Tree::insert_subtree_as_sibling: expecting anchor to be a child of its parent",
+            )
+            + usize::from(after);
+
+        let new_root = nodes
+            .get_mut(root.index as usize)
+            .and_then(Option::take)
+            .expect("Tree::insert_subtree_as_sibling: expecting the root of other to be valid");
+
+        let new_id = self.insert_with_parent(Node::new(new_root.data), &parent_id);
+        self.move_node_to_index(&new_id, &parent_id, index);
+        self.graft(&mut nodes, new_root.children, &new_id);
+
+        Ok(new_id)
+    }
+
+    /// Merges the `Node`s of `other` into this `Tree`, matching them by
+    /// `NodeId`.
+    ///
+    /// For a `NodeId` present in both, `on_conflict` is handed the data in
+    /// this `Tree` to update along with the data from `other`. A `NodeId` only
+    /// present in `other` is added under the same `NodeId`, as the last child
+    /// of its parent in `other`.
+    ///
+    /// This assumes both `Tree`s share their `NodeId`s, e.g. `other` is an
+    /// edited copy of this `Tree`: nothing checks that matching `NodeId`s
+    /// actually refer to the same thing. The structure of this `Tree` wins
+    /// for `Node`s present in both, so `Node`s moved or removed in `other`
+    /// stay where they are. An added `Node` without a parent becomes the root
+    /// if this `Tree` has none and it is the root of `other`, otherwise it is
+    /// left without a parent.
+    ///
+    /// # Panics
+    ///
+    /// Can panic if a `Node` of `other` is not where its `NodeId` points, but
+    /// this would be a bug in `Sakura`
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(1), AsRoot).unwrap();
+    ///
+    /// let mut other = tree.clone();
+    /// let child_id = other.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    /// *other.get_mut(&root_id).unwrap().data_mut() = 10;
+    ///
+    /// tree.merge_from(other, |ours, theirs| *ours += theirs);
+    ///
+    /// # assert_eq!(tree.get(&root_id).unwrap().data(), &11);
+    /// # assert_eq!(tree.parent(&child_id).unwrap(), Some(root_id));
+    /// ```
+    pub fn merge_from<F>(&mut self, other: Self, mut on_conflict: F)
+    where
+        F: FnMut(&mut T, T),
+    {
+        let orphans = other
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.as_ref().is_some_and(|node| node.parent.is_none()))
+            .map(|(index, _)| NodeId::new(index))
+            .filter(|id| Some(id) != other.root.as_ref());
+
+        // Parents come before their children, so added `Node`s can be linked.
+        let order: Vec<NodeId> = other
+            .root
+            .iter()
+            .cloned()
+            .chain(orphans)
+            .flat_map(|start| {
+                other
+                    .traverse_pre_order_ids(&start)
+                    .expect("Tree::merge_from: start is a valid node id")
+            })
+            .collect();
+
+        let Self {
+            root: other_root,
+            nodes: mut other_nodes,
+            ..
+        } = other;
+
+        for node_id in order {
+            let node = other_nodes
+                .get_mut(node_id.index as usize)
+                .and_then(Option::take)
+                .expect("Tree::merge_from: traversed ids are valid");
+
+            if let Some(existing) = self.try_get_mut(&node_id) {
+                on_conflict(&mut existing.data, node.data);
+                continue;
+            }
+
+            self.insert_node_at(&node_id, Node::new(node.data));
+
+            if let Some(parent_id) = node.parent {
+                self.set_as_parent_and_child(&parent_id, &node_id);
+            } else if self.root.is_none() && other_root.as_ref() == Some(&node_id) {
+                self.root = Some(node_id);
+            }
+        }
+    }
+
+    /// Clones the subtree starting at a `Node` of this `Tree` into `dest`, as
+    /// the last child of `dest_parent`, returning the `NodeId` of the copy of
+    /// `start` in `dest`.
+    ///
+    /// The order of children is kept, and this `Tree` is left untouched.
+    ///
+    /// # Errors
+    ///
+    /// Can error if `start` is not valid in this `Tree`, or `dest_parent` is
+    /// not valid in `dest` (i.e. they were removed.)
+    ///
+    /// # Panics
+    ///
+    /// Can panic if a descendant of `start` does not exist in the `Tree`, but
+    /// this would be a bug in `Sakura`
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut a: Tree<i32> = Tree::new();
+    /// let a_root = a.insert(Node::new(0), AsRoot).unwrap();
+    /// let a_1 = a.insert(Node::new(1), UnderNode(&a_root)).unwrap();
+    /// a.insert(Node::new(2), UnderNode(&a_1)).unwrap();
+    ///
+    /// let mut b: Tree<i32> = Tree::new();
+    /// let b_root = b.insert(Node::new(10), AsRoot).unwrap();
+    ///
+    /// let copy = a.clone_subtree_into(&a_1, &mut b, &b_root).unwrap();
+    ///
+    /// # assert_eq!(b.get(&copy).unwrap().data(), &1);
+    /// # assert_eq!(b.len(), 3);
+    /// # assert_eq!(a.len(), 3);
+    /// ```
+    pub fn clone_subtree_into(
+        &self,
+        start: &NodeId,
+        dest: &mut Self,
+        dest_parent: &NodeId,
+    ) -> Result<NodeId, NodeIdError>
+    where
+        T: Clone,
+    {
+        let start_node = self.get(start)?;
+        dest.get(dest_parent)?;
+
+        let new_id = dest.insert_with_parent(Node::new(start_node.data.clone()), dest_parent);
+
+        let mut stack: Vec<(&NodeId, NodeId)> = start_node
+            .children
+            .iter()
+            .rev()
+            .map(|child_id| (child_id, new_id.clone()))
+            .collect();
+
+        while let Some((source_id, parent_id)) = stack.pop() {
+            let node = self
+                .get(source_id)
+                .expect("Tree::clone_subtree_into: expecting descendants of start to be valid");
+
+            let copy_id = dest.insert_with_parent(Node::new(node.data.clone()), &parent_id);
+
+            stack.extend(
+                node.children
+                    .iter()
+                    .rev()
+                    .map(|child_id| (child_id, copy_id.clone())),
+            );
+        }
+
+        Ok(new_id)
+    }
+
+    /// Computes the changes needed to go from this `Tree` to `other`.
+    ///
+    /// `Node`s are matched by their `NodeId`, since ids are stable within a
+    /// document: a `NodeId` present in both `Tree`s is treated as the same
+    /// `Node`. A `Node` whose parent differs is reported as `Moved`, and a
+    /// `Node` whose data differs is reported as `DataChanged` (a single `Node`
+    /// can be both). Changes are returned in `NodeId` order.
+    ///
+    /// NOTE: Reordering children under the same parent is not reported, and
+    /// an id that was freed and reused in `other` is matched like any other id.
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut old: Tree<i32> = Tree::new();
+    /// let old_root = old.insert(Node::new(0), AsRoot).unwrap();
+    ///
+    /// let mut new: Tree<i32> = Tree::new();
+    /// let new_root = new.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = new.insert(Node::new(1), UnderNode(&new_root)).unwrap();
+    ///
+    /// assert_eq!(
+    ///     old.diff(&new),
+    ///     vec![TreeChange::Added { id: child_id, parent: Some(old_root) }]
+    /// );
+    /// ```
+    #[must_use]
+    pub fn diff(&self, other: &Self) -> Vec<TreeChange>
+    where
+        T: PartialEq,
+    {
+        let mut changes = vec![];
+
+        for index in 0..std::cmp::max(self.nodes.len(), other.nodes.len()) {
+            let id = NodeId::new(index);
+            let old = self.nodes.get(index).and_then(Option::as_ref);
+            let new = other.nodes.get(index).and_then(Option::as_ref);
+
+            match (old, new) {
+                (Some(old), None) => changes.push(TreeChange::Removed {
+                    id,
+                    parent: old.parent.clone(),
+                }),
+                (None, Some(new)) => changes.push(TreeChange::Added {
+                    id,
+                    parent: new.parent.clone(),
+                }),
+                (Some(old), Some(new)) => {
+                    if old.parent != new.parent {
+                        changes.push(TreeChange::Moved {
+                            id: id.clone(),
+                            from: old.parent.clone(),
+                            to: new.parent.clone(),
+                        });
+                    }
+
+                    if old.data != new.data {
+                        changes.push(TreeChange::DataChanged(id));
+                    }
+                }
+                (None, None) => {}
+            }
+        }
+
+        changes
+    }
+
+    /// Compares the shape and data of two `Tree`s, ignoring `NodeId`s.
+    ///
+    /// Unlike `PartialEq`, which matches `Node`s by their `NodeId`, this walks
+    /// both `Tree`s from their roots, so two `Tree`s built in a different order
+    /// are equal as long as every `Node` has equal data and equal children, in
+    /// the same order.
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut a: Tree<i32> = Tree::new();
+    /// let a_root = a.insert(Node::new(0), AsRoot).unwrap();
+    /// a.insert(Node::new(1), UnderNode(&a_root)).unwrap();
+    ///
+    /// let mut b: Tree<i32> = Tree::new();
+    /// let b_removed = b.insert(Node::new(42), AsRoot).unwrap();
+    /// let b_root = b.insert(Node::new(0), AsRoot).unwrap();
+    /// b.remove_node(b_removed, RemoveBehavior::DropChildren).unwrap();
+    /// b.insert(Node::new(1), UnderNode(&b_root)).unwrap();
+    ///
+    /// # assert_ne!(a, b);
+    /// assert!(a.structurally_eq(&b));
+    /// ```
+    #[must_use]
+    pub fn structurally_eq(&self, other: &Self) -> bool
+    where
+        T: PartialEq,
+    {
+        self.eq_by(other, T::eq)
+    }
+
+    /// Compares two `Tree`s like `structurally_eq`, but with `eq` deciding
+    /// whether the data of two `Node`s is equal.
+    ///
+    /// Handy to compare only some fields of the data, without a `PartialEq`
+    /// that ignores the others.
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut a: Tree<(&str, u32)> = Tree::new();
+    /// let a_root = a.insert(Node::new(("home", 1)), AsRoot).unwrap();
+    /// a.insert(Node::new(("work", 2)), UnderNode(&a_root)).unwrap();
+    ///
+    /// let mut b: Tree<(&str, u32)> = Tree::new();
+    /// let b_root = b.insert(Node::new(("home", 10)), AsRoot).unwrap();
+    /// b.insert(Node::new(("work", 20)), UnderNode(&b_root)).unwrap();
+    ///
+    /// # assert!(!a.structurally_eq(&b));
+    /// assert!(a.eq_by(&b, |a, b| a.0 == b.0));
+    /// ```
+    #[must_use]
+    pub fn eq_by<F>(&self, other: &Self, eq: F) -> bool
+    where
+        F: Fn(&T, &T) -> bool,
+    {
+        match (&self.root, &other.root) {
+            (Some(root), Some(other_root)) => self.subtree_eq(root, other, other_root, &eq),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    fn subtree_eq<F>(&self, node_id: &NodeId, other: &Self, other_id: &NodeId, eq: &F) -> bool
+    where
+        F: Fn(&T, &T) -> bool,
+    {
+        let node = self
+            .get(node_id)
+            .expect("Tree::subtree_eq: invalid node id");
+        let other_node = other
+            .get(other_id)
+            .expect("Tree::subtree_eq: invalid node id");
+
+        eq(&node.data, &other_node.data)
+            && node.children.len() == other_node.children.len()
+            && node
+                .children
+                .iter()
+                .zip(&other_node.children)
+                .all(|(child, other_child)| self.subtree_eq(child, other, other_child, eq))
+    }
+
+    /// Compares two `Tree`s like `structurally_eq`, but treats the children of
+    /// each `Node` as a multiset rather than a sequence, so sibling order is
+    /// ignored.
+    ///
+    /// NOTE: This is O(n log n), as the children of every `Node` are sorted.
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut a: Tree<i32> = Tree::new();
+    /// let a_root = a.insert(Node::new(0), AsRoot).unwrap();
+    /// a.insert(Node::new(1), UnderNode(&a_root)).unwrap();
+    /// a.insert(Node::new(2), UnderNode(&a_root)).unwrap();
+    ///
+    /// let mut b: Tree<i32> = Tree::new();
+    /// let b_root = b.insert(Node::new(0), AsRoot).unwrap();
+    /// b.insert(Node::new(2), UnderNode(&b_root)).unwrap();
+    /// b.insert(Node::new(1), UnderNode(&b_root)).unwrap();
+    ///
+    /// # assert!(!a.structurally_eq(&b));
+    /// assert!(a.eq_unordered(&b));
+    /// ```
+    #[must_use]
+    pub fn eq_unordered(&self, other: &Self) -> bool
+    where
+        T: Ord + Clone,
+    {
+        self.canonical() == other.canonical()
+    }
+
+    /// Renumbers every `Node` in Pre-Order Traversal order, starting with the
+    /// root at index 0, and drops any free slots.
+    ///
+    /// `NodeId`s depend on the insertion and removal history of a `Tree`, so
+    /// two structurally equal `Tree`s can serialize differently. After
+    /// rebuilding, they serialize identically.
+    ///
+    /// Orphaned subtrees are kept, and numbered after the root's subtree.
+    ///
+    /// Returns a map from every old `NodeId` to its new `NodeId`. Any `NodeId`
+    /// held from before the rebuild must be translated through it.
+    ///
+    /// # Panics
+    /// Can panic if a `Node` refers to a `NodeId` that does not exist in the
+    /// `Tree`, but this would be a bug in `Sakura`
+    ///
+    /// ```
+    /// use sakura::*;
+    /// use sakura::InsertBehavior::*;
+    /// use sakura::RemoveBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let temp_id = tree.insert(Node::new(9), UnderNode(&root_id)).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// tree.remove_node(temp_id, DropChildren).unwrap();
+    ///
+    /// let remap = tree.rebuild_indices_preorder();
+    ///
+    /// let new_child_id = &remap[&child_id];
+    /// assert_eq!(tree.get(new_child_id).unwrap().data(), &1);
+    /// # assert_eq!(tree.get(&remap[&root_id]).unwrap().data(), &0);
+    /// ```
+    pub fn rebuild_indices_preorder(&mut self) -> HashMap<NodeId, NodeId> {
+        let mut order: Vec<NodeId> = vec![];
+
+        let orphans = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.as_ref().is_some_and(|node| node.parent.is_none()))
+            .map(|(index, _)| NodeId::new(index))
+            .filter(|id| Some(id) != self.root.as_ref());
+
+        for start in self.root.iter().cloned().chain(orphans) {
+            order.extend(
+                self.traverse_pre_order_ids(&start)
+                    .expect("Tree::rebuild_indices_preorder: start is a valid node id"),
+            );
+        }
+
+        let remap: HashMap<NodeId, NodeId> = order
+            .iter()
+            .enumerate()
+            .map(|(index, id)| (id.clone(), NodeId::new(index)))
+            .collect();
+        let translate = |id: &NodeId| {
+            remap
+                .get(id)
+                .expect("Tree::rebuild_indices_preorder: every node is visited")
+                .clone()
+        };
+
+        let mut nodes = Vec::with_capacity(order.len());
+        for id in &order {
+            let mut node = self.nodes[id.index as usize]
+                .take()
+                .expect("Tree::rebuild_indices_preorder: node visited twice");
+
+            node.parent = node.parent.as_ref().map(translate);
+            node.children = node.children.iter().map(translate).collect();
+
+            nodes.push(Some(node));
+        }
+
+        self.nodes = nodes;
+        self.free_ids.clear();
+        self.root = self.root.as_ref().map(translate);
+
+        remap
+    }
+
+    /// Builds an owned copy of the `Tree` with every `Node`s children sorted,
+    /// so that two `Tree`s differing only in sibling order compare equal.
+    fn canonical(&self) -> Option<Canonical<T>>
+    where
+        T: Ord + Clone,
+    {
+        fn build<T: Ord + Clone>(tree: &Tree<T>, node_id: &NodeId) -> Canonical<T> {
+            let node = tree.get(node_id).expect("Tree::canonical: invalid node id");
+
+            let mut children: Vec<_> = node
+                .children
+                .iter()
+                .map(|child| build(tree, child))
+                .collect();
+            children.sort();
+
+            Canonical {
+                data: node.data.clone(),
+                children,
+            }
+        }
+
+        self.root.as_ref().map(|root| build(self, root))
+    }
+
+    fn move_node_to_root(&mut self, node_id: &NodeId) -> Result<(), NodeIdError> {
+        let old_root = self.root.clone();
+
+        if let Some(parent_id) = self
+            .get(node_id)
+            .expect("Tree::move_node_to_root Expected a valid NodeId")
+            .parent
+            .clone()
+        {
+            self.detach_from_parent(&parent_id, node_id);
+        }
+
+        self.clear_parent(node_id);
+
+        self.root = Some(node_id.clone());
+
+        if let Some(old_root) = old_root {
+            self.move_node_to_parent(&old_root, node_id)?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn insert_with_parent(&mut self, child: Node<T>, parent_id: &NodeId) -> NodeId {
+        let new_child_id = self.insert_new_node(child);
+        self.set_as_parent_and_child(parent_id, &new_child_id);
+        new_child_id
+    }
+
+    /// Moves the `Node`s from a detached `Tree`'s storage under `parent_id`,
+    /// starting with `children` and following their subtrees.
+    fn graft(&mut self, nodes: &mut [Option<Node<T>>], children: Vec<NodeId>, parent_id: &NodeId) {
+        let mut stack: Vec<(NodeId, NodeId)> = children
+            .into_iter()
+            .rev()
+            .map(|child_id| (child_id, parent_id.clone()))
+            .collect();
+
+        while let Some((source_id, parent_id)) = stack.pop() {
+            let node = nodes
+                .get_mut(source_id.index as usize)
+                .and_then(Option::take)
+                .expect("Tree::graft: expecting source_id to be a valid node_id");
+
+            let new_id = self.insert_with_parent(Node::new(node.data), &parent_id);
+
+            stack.extend(
+                node.children
+                    .into_iter()
+                    .rev()
+                    .map(|child_id| (child_id, new_id.clone())),
+            );
+        }
+    }
+
+    fn set_root(&mut self, new_root: Node<T>) -> NodeId {
+        let new_root_id = self.insert_new_node(new_root);
+
+        if let Some(current_root_node_id) = self.root.clone() {
+            self.set_as_parent_and_child(&new_root_id, &current_root_node_id);
+        }
+
+        self.root = Some(new_root_id.clone());
+
+        new_root_id
+    }
+
+    fn find_subtree_root_between_ids<'a>(
+        &'a self,
+        lower_id: &'a NodeId,
+        upper_id: &'a NodeId,
+    ) -> Result<Option<&'a NodeId>, NodeIdError> {
+        let mut lower_id = lower_id;
+
+        // Same bound as in `for_each_ancestor`, only a loop takes more steps.
+        for _ in 0..self.nodes.len() {
+            match self.get(lower_id)?.parent() {
+                Some(lower_parent) if lower_parent == upper_id => return Ok(Some(lower_id)),
+                Some(lower_parent) => lower_id = lower_parent,
+                None => return Ok(None),
+            }
+        }
+
+        Err(NodeIdError::ParentCycle)
+    }
+
+    fn set_as_parent_and_child(&mut self, parent_id: &NodeId, child_id: &NodeId) {
+        self.invalidate_height();
+
+        self.get_mut(parent_id)
+            .expect("Tree::set_as_parent_and_child: parent_id should be inside the Tree.")
+            .add_child(child_id.clone());
+
+        self.get_mut(child_id)
+            .expect("Tree::set_as_parent_and_child: child_id should be inside the Tree.")
+            .set_parent(Some(parent_id.clone()));
+    }
+
+    fn detach_from_parent(&mut self, parent_id: &NodeId, node_id: &NodeId) {
+        self.invalidate_height();
+
+        self.get_mut(parent_id)
+            .expect("Tree::detach_from_parent: parent_id must be present in tree")
+            .children_mut()
+            .retain(|child_id| *child_id != *node_id);
+    }
+
+    fn insert_new_node(&mut self, new_node: Node<T>) -> NodeId {
+        self.invalidate_height();
+
+        if self.free_ids.is_empty() {
+            let new_node_idx = self.nodes.len();
+            self.nodes.push(Some(new_node));
+            NodeId::new(new_node_idx)
+        } else {
+            let new_node_id = self
+                .free_ids
+                .pop()
+                .expect("Tree::insert_new_node: Couldn't pop from vec with len() > 0.");
+
+            self.nodes.push(Some(new_node));
+            self.nodes.swap_remove(new_node_id.index as usize);
+            new_node_id
+        }
+    }
+
+    /// Inserts `node` under the given, currently unused, `NodeId`, growing
+    /// the `Tree` as needed.
+    fn insert_node_at(&mut self, node_id: &NodeId, node: Node<T>) {
+        self.invalidate_height();
+
+        let index = node_id.index as usize;
+        if index < self.nodes.len() {
+            self.free_ids.retain(|free_id| free_id != node_id);
+        } else {
+            // The slots skipped over are free for later inserts.
+            self.free_ids
+                .extend((self.nodes.len()..index).map(NodeId::new));
+            self.nodes.resize_with(index + 1, || None);
+        }
+
+        self.nodes[index] = Some(node);
+    }
+
+    const fn is_valid_node_id(&self, node_id: &NodeId) -> Result<(), NodeIdError> {
+        // Not a bug inside Sakura, the id may come from a corrupt document.
+        if node_id.index as usize >= self.nodes.len() {
+            return Err(NodeIdError::OutOfBounds);
+        }
+
+        // The slot is still there, but its `Node` was removed.
+        if self.nodes.as_slice()[node_id.index as usize].is_none() {
+            return Err(NodeIdError::NodeIdNoLongerValid);
+        }
+
+        Ok(())
+    }
+
+    // We want to have the node_id be consumed by this remove function.
+    #[allow(clippy::needless_pass_by_value)]
+    fn remove_node_internal(&mut self, node_id: NodeId) -> Node<T> {
+        if let Some(root_id) = &self.root
+            && node_id == *root_id
+        {
+            self.root = None;
+        }
+
+        let mut node = self.take_node(node_id.clone());
+
+        if let Some(parent_id) = node.parent() {
+            self.get_mut(parent_id)
+                .expect(
+                    "Tree::remove_node_internal: expecting
+                parent_id to be a valid node_id!",
+                )
+                .children_mut()
+                .retain(|child_id| *child_id != node_id);
+        }
+
+        node.children_mut().clear();
+        node.set_parent(None);
+
+        node
+    }
+
+    fn take_node(&mut self, node_id: NodeId) -> Node<T> {
+        self.invalidate_height();
+
+        self.nodes.push(None);
+
+        let node = self
+            .nodes
+            .swap_remove(node_id.index as usize)
+            .expect("Tree::take_node: expecting node_id to be a valid node_id!");
+
+        self.free_ids.push(node_id);
+
+        node
+    }
+
+    fn clear_parent(&mut self, node_id: &NodeId) {
+        self.set_parent(node_id, None);
+    }
+
+    fn set_parent(&mut self, node_id: &NodeId, parent_id: Option<NodeId>) {
+        self.invalidate_height();
+
+        self.get_mut(node_id)
+            .expect(
+                "Tree::set_parent: expecting node_id to
+            be present inside tree!",
+            )
+            .set_parent(parent_id);
+    }
+
+    fn clear_parent_of_children(&mut self, node_id: &NodeId) {
+        self.set_parent_of_children(node_id, None);
+    }
+
+    fn set_parent_of_children(&mut self, node_id: &NodeId, new_parent: Option<&NodeId>) {
+        for child_id in self
+            .get(node_id)
+            .expect("Tree::set_parent_of_child: expect node_id to be a valid node inside tree.")
+            .children
+            .clone()
+        {
+            self.set_parent(&child_id, new_parent.cloned());
+        }
+    }
+}
+
+/// The characters used to draw the branches in `Tree::write_formatted`.
+struct Glyphs {
+    branch: &'static str,
+    last_branch: &'static str,
+    vertical: &'static str,
+    blank: &'static str,
+}
+
+impl Glyphs {
+    const UNICODE: Self = Self {
+        branch: "├── ",
+        last_branch: "└── ",
+        vertical: "│   ",
+        blank: "    ",
+    };
+
+    const ASCII: Self = Self {
+        branch: "|-- ",
+        last_branch: "`-- ",
+        vertical: "|   ",
+        blank: "    ",
+    };
+}
+
+impl<T: std::fmt::Debug> Tree<T> {
+    /// Write formatted tree representation and nodes with debug formatting.
+    ///
+    ///
+    /// # Errors
+    ///
+    /// Function can error if something goes wrong during debug!
+    ///
+    /// # Panics
+    ///
+    /// Function can error if something goes wrong during debug!
+    ///
+    /// ```
+    /// use sakura::Tree;
+    /// use sakura::Node;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree = Tree::<i32>::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let first_child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let _ = tree.insert(Node::new(2), UnderNode(&first_child_id)).unwrap();
+    /// let _ = tree.insert(Node::new(3), UnderNode(&root_id)).unwrap();
+    /// let mut s = String::new();
+    /// tree.write_formatted(&mut s).unwrap();
+    /// assert_eq!(&s, "\
+    /// 0
+    /// ├── 1
+    /// │   └── 2
+    /// └── 3
+    /// ");
+    /// ```
+    ///
+    /// Writes nothing if the tree is empty.
+    ///
+    /// ```
+    /// use sakura::Tree;
+    ///
+    /// let tree = Tree::<i32>::new();
+    /// let mut s = String::new();
+    /// tree.write_formatted(&mut s).unwrap();
+    /// # assert_eq!(&s, "");
+    /// ```
+    pub fn write_formatted<W: std::fmt::Write>(&self, w: &mut W) -> std::fmt::Result {
+        self.write_formatted_with(w, &Glyphs::UNICODE, |data| format!("{data:?}"))
+    }
+
+    /// Like `write_formatted`, but only uses ASCII characters, for consoles
+    /// and logs that can't render box-drawing characters.
+    ///
+    /// # Errors
+    ///
+    /// Function can error if something goes wrong during debug!
+    ///
+    /// # Panics
+    ///
+    /// Function can error if something goes wrong during debug!
+    ///
+    /// ```
+    /// use sakura::Tree;
+    /// use sakura::Node;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree = Tree::<i32>::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let first_child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let _ = tree.insert(Node::new(2), UnderNode(&first_child_id)).unwrap();
+    /// let _ = tree.insert(Node::new(3), UnderNode(&root_id)).unwrap();
+    /// let mut s = String::new();
+    /// tree.write_formatted_ascii(&mut s).unwrap();
+    /// assert_eq!(&s, "\
+    /// 0
+    /// |-- 1
+    /// |   `-- 2
+    /// `-- 3
+    /// ");
+    /// ```
+    pub fn write_formatted_ascii<W: std::fmt::Write>(&self, w: &mut W) -> std::fmt::Result {
+        self.write_formatted_with(w, &Glyphs::ASCII, |data| format!("{data:?}"))
+    }
+}
+
+impl<T> Tree<T> {
+    /// Like `write_formatted`, but labels every `Node` with `label` instead of
+    /// its debug formatting.
+    ///
+    /// # Errors
+    ///
+    /// Function can error if writing to `w` fails.
+    ///
+    /// # Panics
+    ///
+    /// Can panic if a `NodeId` does not exist in the `Tree`, but this would
+    /// be a bug in `Sakura`
+    ///
+    /// ```
+    /// use sakura::Tree;
+    /// use sakura::Node;
+    /// use sakura::InsertBehavior::*;
+    ///
+    /// let mut tree = Tree::<i32>::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let _ = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let mut s = String::new();
+    /// tree.write_formatted_by(&mut s, |data| format!("#{data}")).unwrap();
+    /// assert_eq!(&s, "\
+    /// #0
+    /// └── #1
+    /// ");
+    /// ```
+    pub fn write_formatted_by<W, F>(&self, w: &mut W, label: F) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+        F: Fn(&T) -> String,
+    {
+        self.write_formatted_with(w, &Glyphs::UNICODE, label)
+    }
+
+    fn write_formatted_with<W, F>(&self, w: &mut W, glyphs: &Glyphs, label: F) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+        F: Fn(&T) -> String,
+    {
+        if let Some(node_id) = self.root_node_id() {
+            let childn = 0;
+            let level = 0;
+            let last = vec![];
+            let mut stack = vec![(node_id, childn, level, last)];
+            while let Some((node_id, childn, level, last)) = stack.pop() {
+                debug_assert_eq!(
+                    last.len(),
+                    level,
+                    "each previous level should indicate whether it has reached the last node"
+                );
+                let node = self
+                    .get(node_id)
+                    .expect("getting node of existing node ref id");
+                if childn == 0 {
+                    for i in 1..level {
+                        if last[i - 1] {
+                            write!(w, "{}", glyphs.blank)?;
+                        } else {
+                            write!(w, "{}", glyphs.vertical)?;
+                        }
+                    }
+                    if level > 0 {
+                        if last[level - 1] {
+                            write!(w, "{}", glyphs.last_branch)?;
+                        } else {
+                            write!(w, "{}", glyphs.branch)?;
+                        }
+                    }
+                    writeln!(w, "{}", label(node.data()))?;
+                }
+                let mut children = node.children().iter().skip(childn);
+                if let Some(child_id) = children.next() {
+                    let mut next_last = last.clone();
+                    if children.next().is_some() {
+                        stack.push((node_id, childn + 1, level, last));
+                        next_last.push(false);
+                    } else {
+                        next_last.push(true);
+                    }
+                    stack.push((child_id, 0, level + 1, next_last));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tree_builder_tests {
+    use super::super::Node;
+    use super::Tree;
+    use super::TreeBuilder;
+
+    #[test]
+    fn test_new() {
+        let tb: TreeBuilder<i32> = TreeBuilder::new();
+        assert!(tb.root.is_none());
+        assert_eq!(tb.node_capacity, 0);
+        assert_eq!(tb.swap_capacity, 0);
+    }
+
+    #[test]
+    fn test_with_root() {
+        let tb: TreeBuilder<i32> = TreeBuilder::new().with_root(Node::new(5));
+
+        assert_eq!(tb.root.unwrap().data(), &5);
+        assert_eq!(tb.node_capacity, 0);
+        assert_eq!(tb.swap_capacity, 0);
+    }
+
+    #[test]
+    fn test_with_node_capacity() {
+        let tb: TreeBuilder<i32> = TreeBuilder::new().with_node_capacity(10);
+
+        assert!(tb.root.is_none());
+        assert_eq!(tb.node_capacity, 10);
+        assert_eq!(tb.swap_capacity, 0);
+    }
+
+    #[test]
+    fn test_with_swap_capacity() {
+        let tb: TreeBuilder<i32> = TreeBuilder::new().with_swap_capacity(10);
+
+        assert!(tb.root.is_none());
+        assert_eq!(tb.node_capacity, 0);
+        assert_eq!(tb.swap_capacity, 10);
+    }
+
+    #[test]
+    fn test_with_all_settings() {
+        let tb: TreeBuilder<i32> = TreeBuilder::new()
+            .with_root(Node::new(5))
+            .with_node_capacity(10)
+            .with_swap_capacity(3);
+
+        assert_eq!(tb.root.unwrap().data(), &5);
+        assert_eq!(tb.node_capacity, 10);
+        assert_eq!(tb.swap_capacity, 3);
+    }
+
+    #[test]
+    fn test_build() {
+        let tree = TreeBuilder::new()
+            .with_root(Node::new(5))
+            .with_node_capacity(10)
+            .with_swap_capacity(3)
+            .build();
+
+        let root = tree.get(tree.root_node_id().unwrap()).unwrap();
+
+        assert_eq!(root.data(), &5);
+        assert_eq!(tree.capacity(), 10);
+        assert_eq!(tree.free_ids.capacity(), 3);
+    }
+
+    #[test]
+    fn test_with_capacity() {
+        let tree: Tree<i32> = Tree::with_capacity(10);
+
+        assert_eq!(tree.capacity(), 10);
+        assert!(tree.is_empty());
+        assert!(tree.root_node_id().is_none());
+    }
+
+    #[test]
+    fn test_with_capacities() {
+        let tb: TreeBuilder<i32> = TreeBuilder::new().with_capacities(10, 3);
+
+        assert!(tb.root.is_none());
+        assert_eq!(tb.node_capacity, 10);
+        assert_eq!(tb.swap_capacity, 3);
+    }
+
+    #[test]
+    fn test_build_capacities() {
+        for (node_capacity, swap_capacity) in [(0, 0), (0, 5), (5, 0), (1, 1), (10, 3)] {
+            let tree: Tree<i32> = TreeBuilder::new()
+                .with_capacities(node_capacity, swap_capacity)
+                .build();
+
+            assert_eq!(tree.capacity(), node_capacity);
+            assert_eq!(tree.free_ids.capacity(), swap_capacity);
+
+            let tree = TreeBuilder::new()
+                .with_root(Node::new(5))
+                .with_capacities(node_capacity, swap_capacity)
+                .build();
+
+            // The root needs a slot of its own.
+            assert_eq!(tree.capacity(), node_capacity.max(1));
+            assert_eq!(tree.free_ids.capacity(), swap_capacity);
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::similar_names)]
+mod tree_tests {
+    use crate::InsertBehavior;
+    use crate::MoveBehavior;
+    use crate::NodeIdError;
+    use crate::RemoveBehavior;
+    use crate::TreeChange;
+
+    use super::super::Node;
+    use super::super::NodeId;
+    use super::Tree;
+    use super::TreeBuilder;
+
+    #[test]
+    fn test_new() {
+        let tree: Tree<i32> = Tree::new();
+
+        assert_eq!(tree.root, None);
+        assert_eq!(tree.nodes.len(), 0);
+        assert_eq!(tree.free_ids.len(), 0);
+    }
+
+    #[test]
+    fn test_get() {
+        let tree = TreeBuilder::new().with_root(Node::new(5)).build();
+
+        let root_id = tree.root.clone().unwrap();
+        let root = tree.get(&root_id).unwrap();
+
+        assert_eq!(root.data(), &5);
+    }
+
+    #[test]
+    fn test_get_mut() {
+        let mut tree = TreeBuilder::new().with_root(Node::new(5)).build();
+
+        let root_id = tree.root.clone().unwrap();
+
+        {
+            let root = tree.get(&root_id).unwrap();
+            assert_eq!(root.data(), &5);
+        }
+
+        {
+            let root = tree.get_mut(&root_id).unwrap();
+            *root.data_mut() = 6;
+        }
+
+        let root = tree.get(&root_id).unwrap();
+        assert_eq!(root.data(), &6);
+    }
+
+    #[test]
+    fn test_set_root() {
+        use InsertBehavior::*;
+
+        let a = 5;
+        let b = 6;
+        let node_a = Node::new(a);
+        let node_b = Node::new(b);
+
+        let mut tree = TreeBuilder::new().build();
+
+        let node_a_id = tree.insert(node_a, AsRoot).unwrap();
+        let root_id = tree.root.clone().unwrap();
+        assert_eq!(node_a_id, root_id);
+
+        {
+            let node_a_ref = tree.get(&node_a_id).unwrap();
+            let root_ref = tree.get(&root_id).unwrap();
+            assert_eq!(node_a_ref.data(), &a);
+            assert_eq!(root_ref.data(), &a);
+        }
+
+        let node_b_id = tree.insert(node_b, AsRoot).unwrap();
+        let root_id = tree.root.clone().unwrap();
+        assert_eq!(node_b_id, root_id);
+
+        {
+            let node_b_ref = tree.get(&node_b_id).unwrap();
+            let root_ref = tree.get(&root_id).unwrap();
+            assert_eq!(node_b_ref.data(), &b);
+            assert_eq!(root_ref.data(), &b);
+
+            let node_b_child_id = node_b_ref.children().first().unwrap();
+            let node_b_child_ref = tree.get(node_b_child_id).unwrap();
+            assert_eq!(node_b_child_ref.data(), &a);
+        }
+    }
+
+    #[test]
+    fn test_root_node_id() {
+        let tree = TreeBuilder::new().with_root(Node::new(5)).build();
+
+        let root_id = tree.root.clone().unwrap();
+        let root_node_id = tree.root_node_id().unwrap();
+
+        assert_eq!(&root_id, root_node_id);
+    }
+
+    #[test]
+    fn test_insert_with_parent() {
+        use InsertBehavior::*;
+
+        let a = 1;
+        let b = 2;
+        let r = 5;
+
+        let mut tree = TreeBuilder::new().with_root(Node::new(r)).build();
+
+        let node_a = Node::new(a);
+        let node_b = Node::new(b);
+
+        let root_id = tree.root.clone().unwrap();
+        let node_a_id = tree.insert(node_a, UnderNode(&root_id)).unwrap();
+        let node_b_id = tree.insert(node_b, UnderNode(&root_id)).unwrap();
+
+        let node_a_ref = tree.get(&node_a_id).unwrap();
+        let node_b_ref = tree.get(&node_b_id).unwrap();
+        assert_eq!(node_a_ref.data(), &a);
+        assert_eq!(node_b_ref.data(), &b);
+
+        assert_eq!(node_a_ref.parent().unwrap().clone(), root_id);
+        assert_eq!(node_b_ref.parent().unwrap().clone(), root_id);
+
+        let root_node_ref = tree.get(&root_id).unwrap();
+        let root_children: &Vec<NodeId> = root_node_ref.children();
+
+        let child_1_id = root_children.first().unwrap();
+        let child_2_id = root_children.get(1).unwrap();
+
+        let child_1_ref = tree.get(child_1_id).unwrap();
+        let child_2_ref = tree.get(child_2_id).unwrap();
+
+        assert_eq!(child_1_ref.data(), &a);
+        assert_eq!(child_2_ref.data(), &b);
+    }
+
+    #[test]
+    fn test_remove_node_lift_children() {
+        use InsertBehavior::*;
+        use RemoveBehavior::*;
+
+        let mut tree = TreeBuilder::new().with_root(Node::new(5)).build();
+
+        let root_id = tree.root.clone().unwrap();
+
+        let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let node_2_id = tree.insert(Node::new(2), UnderNode(&node_1_id)).unwrap();
+        let node_3_id = tree.insert(Node::new(3), UnderNode(&node_1_id)).unwrap();
+
+        let node_1 = tree.remove_node(node_1_id.clone(), LiftChildren).unwrap();
+
+        assert_eq!(Some(&root_id), tree.root_node_id());
+
+        assert_eq!(node_1.data(), &1);
+        assert_eq!(node_1.children().len(), 0);
+        assert!(node_1.parent().is_none());
+        assert!(tree.get(&node_1_id).is_err());
+
+        let root_ref = tree.get(&root_id).unwrap();
+        let node_2_ref = tree.get(&node_2_id).unwrap();
+        let node_3_ref = tree.get(&node_3_id).unwrap();
+
+        assert_eq!(node_2_ref.data(), &2);
+        assert_eq!(node_3_ref.data(), &3);
+
+        assert_eq!(node_2_ref.parent().unwrap(), &root_id);
+        assert_eq!(node_3_ref.parent().unwrap(), &root_id);
+
+        assert!(root_ref.children().contains(&node_2_id));
+        assert!(root_ref.children().contains(&node_3_id));
+    }
+
+    #[test]
+    fn test_remove_node_orphan_children() {
+        use InsertBehavior::*;
+        use RemoveBehavior::*;
+
+        let mut tree = TreeBuilder::new().with_root(Node::new(5)).build();
+
+        let root_id = tree.root.clone().unwrap();
+
+        let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let node_2_id = tree.insert(Node::new(2), UnderNode(&node_1_id)).unwrap();
+        let node_3_id = tree.insert(Node::new(3), UnderNode(&node_1_id)).unwrap();
+
+        let node_1 = tree.remove_node(node_1_id.clone(), OrphanChildren).unwrap();
+
+        assert_eq!(Some(&root_id), tree.root_node_id());
+
+        assert_eq!(node_1.data(), &1);
+        assert_eq!(node_1.children().len(), 0);
+        assert!(node_1.parent().is_none());
+        assert!(tree.get(&node_1_id).is_err());
+
+        let node_2_ref = tree.get(&node_2_id).unwrap();
+        let node_3_ref = tree.get(&node_3_id).unwrap();
+
+        assert_eq!(node_2_ref.data(), &2);
+        assert_eq!(node_3_ref.data(), &3);
+
+        assert!(node_2_ref.parent().is_none());
+        assert!(node_3_ref.parent().is_none());
+    }
+
+    #[test]
+    fn test_remove_root() {
+        use RemoveBehavior::*;
+
+        let mut tree = TreeBuilder::new().with_root(Node::new(5)).build();
+
+        let root_id = tree.root.clone().unwrap();
+        tree.remove_node(root_id, OrphanChildren).unwrap();
+        assert_eq!(None, tree.root_node_id());
+
+        let mut tree = TreeBuilder::new().with_root(Node::new(5)).build();
+
+        let root_id = tree.root.clone().unwrap();
+        tree.remove_node(root_id, LiftChildren).unwrap();
+        assert_eq!(None, tree.root_node_id());
+    }
+
+    #[test]
+    fn test_move_node_to_parent() {
+        use InsertBehavior::*;
+        use MoveBehavior::*;
+
+        let mut tree = Tree::new();
+
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let node_2_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+        let node_3_id = tree.insert(Node::new(3), UnderNode(&node_1_id)).unwrap();
+
+        // Move 3 "across" the tree.
+        tree.move_node(&node_3_id, ToParent(&node_2_id)).unwrap();
+        assert!(tree.get(&root_id).unwrap().children().contains(&node_1_id));
+        assert!(tree.get(&root_id).unwrap().children().contains(&node_2_id));
+        assert!(
+            tree.get(&node_2_id,)
+                .unwrap()
+                .children()
+                .contains(&node_3_id,)
+        );
+
+        // Move 3 "up" the tree.
+        tree.move_node(&node_3_id, ToParent(&root_id)).unwrap();
+        assert!(tree.get(&root_id).unwrap().children().contains(&node_1_id));
+        assert!(tree.get(&root_id).unwrap().children().contains(&node_2_id));
+        assert!(tree.get(&root_id).unwrap().children().contains(&node_3_id));
+
+        // Move 3 "down" (really this is across though) the tree.
+        tree.move_node(&node_3_id, ToParent(&node_1_id)).unwrap();
+        assert!(tree.get(&root_id).unwrap().children().contains(&node_1_id));
+        assert!(tree.get(&root_id).unwrap().children().contains(&node_2_id));
+        assert!(
+            tree.get(&node_1_id,)
+                .unwrap()
+                .children()
+                .contains(&node_3_id,)
+        );
+
+        // Move 1 "down" the tree.
+        tree.move_node(&node_1_id, ToParent(&node_3_id)).unwrap();
+        assert!(tree.get(&root_id).unwrap().children().contains(&node_2_id));
+        assert!(tree.get(&root_id).unwrap().children().contains(&node_3_id));
+        assert!(
+            tree.get(&node_3_id,)
+                .unwrap()
+                .children()
+                .contains(&node_1_id,)
+        );
+
+        // Note: node_1 is at the lowest point in the tree before these insertions.
+        let node_4_id = tree.insert(Node::new(4), UnderNode(&node_1_id)).unwrap();
+        let node_5_id = tree.insert(Node::new(5), UnderNode(&node_4_id)).unwrap();
+
+        // move 3 "down" the tree
+        tree.move_node(&node_3_id, ToParent(&node_5_id)).unwrap();
+        assert!(tree.get(&root_id).unwrap().children().contains(&node_2_id));
+        assert!(tree.get(&root_id).unwrap().children().contains(&node_1_id));
+        assert!(
+            tree.get(&node_1_id,)
+                .unwrap()
+                .children()
+                .contains(&node_4_id,)
+        );
+        assert!(
+            tree.get(&node_4_id,)
+                .unwrap()
+                .children()
+                .contains(&node_5_id,)
+        );
+        assert!(
+            tree.get(&node_5_id,)
+                .unwrap()
+                .children()
+                .contains(&node_3_id,)
+        );
+
+        // move root "down" the tree
+        tree.move_node(&root_id, ToParent(&node_2_id)).unwrap();
+        assert!(tree.get(&node_2_id).unwrap().children().contains(&root_id));
+        assert!(tree.get(&root_id).unwrap().children().contains(&node_1_id));
+        assert!(
+            tree.get(&node_1_id,)
+                .unwrap()
+                .children()
+                .contains(&node_4_id,)
+        );
+        assert!(
+            tree.get(&node_4_id,)
+                .unwrap()
+                .children()
+                .contains(&node_5_id,)
+        );
+        assert!(
+            tree.get(&node_5_id,)
+                .unwrap()
+                .children()
+                .contains(&node_3_id,)
+        );
+        assert_eq!(tree.root_node_id(), Some(&node_2_id));
+    }
+
+    #[test]
+    fn test_move_node_to_root() {
+        use InsertBehavior::*;
+
+        // test move with existing root
+        {
+            let mut tree = Tree::new();
+            let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+            let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+            let node_2_id = tree.insert(Node::new(2), UnderNode(&node_1_id)).unwrap();
+
+            tree.move_node_to_root(&node_2_id).unwrap();
+
+            assert_eq!(tree.root_node_id(), Some(&node_2_id));
+            assert!(tree.get(&node_2_id).unwrap().children().contains(&root_id));
+            assert!(
+                !tree
+                    .get(&node_1_id,)
+                    .unwrap()
+                    .children()
+                    .contains(&node_2_id,)
+            );
         }
 
-        self.root = Some(new_root_id.clone());
+        // Test move with existing root and with orphan.
+        {
+            let mut tree = Tree::new();
+            let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+            let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+            let node_2_id = tree.insert(Node::new(2), UnderNode(&node_1_id)).unwrap();
 
-        new_root_id
-    }
+            tree.remove_node_orphan_children(node_1_id);
+            tree.move_node_to_root(&node_2_id).unwrap();
 
-    fn find_subtree_root_between_ids<'a>(
-        &'a self,
-        lower_id: &'a NodeId,
-        upper_id: &'a NodeId,
-    ) -> Option<&'a NodeId> {
-        if let Some(lower_parent) = self.get(lower_id).unwrap().parent() {
-            if lower_parent == upper_id {
-                return Some(lower_id);
-            }
-            return self.find_subtree_root_between_ids(lower_parent, upper_id);
+            assert_eq!(tree.root_node_id(), Some(&node_2_id));
+            assert!(tree.get(&node_2_id).unwrap().children().contains(&root_id));
+            assert_eq!(tree.get(&root_id).unwrap().children().len(), 0);
         }
 
-        None
-    }
+        // Test move without root and with orphan.
+        {
+            let mut tree = Tree::new();
+            let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+            let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+            let node_2_id = tree.insert(Node::new(2), UnderNode(&node_1_id)).unwrap();
 
-    fn set_as_parent_and_child(&mut self, parent_id: &NodeId, child_id: &NodeId) {
-        self.get_mut(parent_id)
-            .expect("Tree::set_as_parent_and_child: parent_id should be inside the Tree.")
-            .add_child(child_id.clone());
+            tree.remove_node_orphan_children(root_id);
+            tree.move_node_to_root(&node_1_id).unwrap();
 
-        self.get_mut(child_id)
-            .expect("Tree::set_as_parent_and_child: child_id should be inside the Tree.")
-            .set_parent(Some(parent_id.clone()));
+            assert_eq!(tree.root_node_id(), Some(&node_1_id));
+            assert!(
+                tree.get(&node_1_id,)
+                    .unwrap()
+                    .children()
+                    .contains(&node_2_id,)
+            );
+            assert_eq!(tree.get(&node_1_id).unwrap().children().len(), 1);
+        }
     }
 
-    fn detach_from_parent(&mut self, parent_id: &NodeId, node_id: &NodeId) {
-        self.get_mut(parent_id)
-            .expect("Tree::detach_from_parent: parent_id must be present in tree")
-            .children_mut()
-            .retain(|child_id| *child_id != *node_id);
+    #[test]
+    fn test_find_subtree_root_below_upper_id() {
+        use InsertBehavior::*;
+
+        let mut tree = Tree::new();
+
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let node_2_id = tree.insert(Node::new(2), UnderNode(&node_1_id)).unwrap();
+        let node_3_id = tree.insert(Node::new(3), UnderNode(&node_1_id)).unwrap();
+        let node_4_id = tree.insert(Node::new(4), UnderNode(&node_2_id)).unwrap();
+
+        let sub_root = tree.find_subtree_root_between_ids(&node_1_id, &root_id);
+        assert_eq!(sub_root, Ok(Some(&node_1_id)));
+        let sub_root = tree.find_subtree_root_between_ids(&root_id, &node_1_id); //invert for None
+        assert_eq!(sub_root, Ok(None));
+
+        let sub_root = tree.find_subtree_root_between_ids(&node_2_id, &root_id);
+        assert_eq!(sub_root, Ok(Some(&node_1_id)));
+        let sub_root = tree.find_subtree_root_between_ids(&root_id, &node_2_id); //invert for None
+        assert_eq!(sub_root, Ok(None));
+
+        let sub_root = tree.find_subtree_root_between_ids(&node_3_id, &node_1_id);
+        assert_eq!(sub_root, Ok(Some(&node_3_id)));
+        let sub_root = tree.find_subtree_root_between_ids(&node_1_id, &node_3_id); //invert for None
+        assert_eq!(sub_root, Ok(None));
+
+        let sub_root = tree.find_subtree_root_between_ids(&node_4_id, &root_id);
+        assert_eq!(sub_root, Ok(Some(&node_1_id)));
+        let sub_root = tree.find_subtree_root_between_ids(&root_id, &node_4_id); //invert for None
+        assert_eq!(sub_root, Ok(None));
     }
 
-    fn insert_new_node(&mut self, new_node: Node<T>) -> NodeId {
-        if self.free_ids.is_empty() {
-            let new_node_idx = self.nodes.len();
-            self.nodes.push(Some(new_node));
-            NodeId::new(new_node_idx)
-        } else {
-            let new_node_id = self
-                .free_ids
-                .pop()
-                .expect("Tree::insert_new_node: Couldn't pop from vec with len() > 0.");
+    #[test]
+    fn test_tree_height() {
+        use InsertBehavior::*;
+        use RemoveBehavior::*;
 
-            self.nodes.push(Some(new_node));
-            self.nodes.swap_remove(new_node_id.index as usize);
-            new_node_id
-        }
+        // Empty tree.
+        let mut tree = Tree::new();
+        assert_eq!(0, tree.height());
+
+        // The tree with single root node.
+        let root_id = tree.insert(Node::new(1), AsRoot).unwrap();
+        assert_eq!(1, tree.height());
+
+        // Root node with single child.
+        let child_1_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+        assert_eq!(2, tree.height());
+
+        // Root node with two children.
+        let child_2_id = tree.insert(Node::new(3), UnderNode(&root_id)).unwrap();
+        assert_eq!(2, tree.height());
+
+        // Grandson.
+        tree.insert(Node::new(4), UnderNode(&child_1_id)).unwrap();
+        assert_eq!(3, tree.height());
+
+        // Remove child_1 and grandchild.
+        tree.remove_node(child_1_id, DropChildren).unwrap();
+        assert_eq!(2, tree.height());
+
+        // Remove child_2.
+        tree.remove_node(child_2_id, LiftChildren).unwrap();
+        assert_eq!(1, tree.height());
     }
 
-    fn is_valid_node_id(&self, node_id: &NodeId) -> Result<(), NodeIdError> {
-        let idx = node_id.index as usize;
+    #[test]
+    fn test_partial_eq() {
+        use InsertBehavior::*;
 
-        assert!(
-            idx <= self.nodes.len(),
-            "NodeId: {node_id:?} is out of bounds. This is a bug inside
-            Sakura.",
-        );
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+        tree.insert(Node::new(3), UnderNode(&node_1_id)).unwrap();
 
-        if self.nodes.get(idx).is_none() {
-            return Err(NodeIdError::NodeIdNoLongerValid);
+        // Ensure PartialEq doesn't work when the number of used nodes are not equal.
+        {
+            let mut other = Tree::new();
+            let root_id = other.insert(Node::new(0), AsRoot).unwrap();
+            other.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+            other.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+            assert_ne!(tree, other);
         }
 
-        Ok(())
-    }
+        // Ensure PartialEq doesn't work when the data is not equal.
+        {
+            let mut other = Tree::new();
+            let root_id = other.insert(Node::new(0), AsRoot).unwrap();
+            let id = other.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+            other.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+            other.insert(Node::new(4), UnderNode(&id)).unwrap();
+            assert_ne!(tree, other);
+        }
 
-    // We want to have the node_id be consumed by this remove function.
-    #[allow(clippy::needless_pass_by_value)]
-    fn remove_node_internal(&mut self, node_id: NodeId) -> Node<T> {
-        if let Some(root_id) = &self.root
-            && node_id == *root_id
+        // Ensure PartialEq doesn't work when the parents aren't equal.
         {
-            self.root = None;
+            let mut other = Tree::new();
+            let root_id = other.insert(Node::new(0), AsRoot).unwrap();
+            other.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+            let id = other.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+            other.insert(Node::new(3), UnderNode(&id)).unwrap();
+            assert_ne!(tree, other);
         }
 
-        let mut node = self.take_node(node_id.clone());
+        // Ensure PartialEq works even if the number of free spots in Tree.
+        // Node is different.
+        {
+            let mut other = Tree::new();
+            let root_id = other.insert(Node::new(0), AsRoot).unwrap();
+            let id = other.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+            other.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+            other.insert(Node::new(3), UnderNode(&id)).unwrap();
+            let to_delete = other.insert(Node::new(42), UnderNode(&root_id)).unwrap();
+            other.take_node(to_delete);
+            assert_ne!(
+                tree.nodes.iter().filter(|x| x.is_none()).count(),
+                other.nodes.iter().filter(|x| x.is_none()).count()
+            );
+            assert_eq!(tree, other);
+        }
 
-        if let Some(parent_id) = node.parent() {
-            self.get_mut(parent_id)
-                .expect(
-                    "Tree::remove_node_internal: expecting
-                parent_id to be a valid node_id!",
-                )
-                .children_mut()
-                .retain(|child_id| *child_id != node_id);
+        // Ensure PartialEq doesn't work when the Node's index are different.
+        {
+            let mut other = Tree::new();
+            let root_id = other.insert(Node::new(0), AsRoot).unwrap();
+            let to_delete = other.insert(Node::new(42), UnderNode(&root_id)).unwrap();
+            let id = other.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+            other.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+            other.insert(Node::new(3), UnderNode(&id)).unwrap();
+            other.take_node(to_delete);
+            assert_ne!(tree, other);
         }
+    }
 
-        node.children_mut().clear();
-        node.set_parent(None);
+    fn diff_fixture() -> Tree<i32> {
+        use InsertBehavior::*;
+
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+        tree.insert(Node::new(3), UnderNode(&node_1_id)).unwrap();
+
+        tree
+    }
+
+    #[test]
+    fn test_diff_insert() {
+        use InsertBehavior::*;
+
+        let old = diff_fixture();
+        let mut new = diff_fixture();
+
+        let root_id = new.root_node_id().cloned().unwrap();
+        let node_4_id = new.insert(Node::new(4), UnderNode(&root_id)).unwrap();
+
+        assert_eq!(
+            old.diff(&new),
+            vec![TreeChange::Added {
+                id: node_4_id,
+                parent: Some(root_id)
+            }]
+        );
+        assert!(old.diff(&old).is_empty());
+    }
+
+    #[test]
+    fn test_diff_delete() {
+        use RemoveBehavior::*;
+
+        let old = diff_fixture();
+        let mut new = diff_fixture();
 
-        node
+        let root_id = new.root_node_id().cloned().unwrap();
+        let node_2_id = new.get(&root_id).unwrap().children()[1].clone();
+        new.remove_node(node_2_id.clone(), DropChildren).unwrap();
+
+        assert_eq!(
+            old.diff(&new),
+            vec![TreeChange::Removed {
+                id: node_2_id,
+                parent: Some(root_id)
+            }]
+        );
     }
 
-    fn take_node(&mut self, node_id: NodeId) -> Node<T> {
-        self.nodes.push(None);
+    #[test]
+    fn test_diff_move() {
+        use MoveBehavior::*;
 
-        let node = self
-            .nodes
-            .swap_remove(node_id.index as usize)
-            .expect("Tree::take_node: expecting node_id to be a valid node_id!");
+        let old = diff_fixture();
+        let mut new = diff_fixture();
 
-        self.free_ids.push(node_id);
+        let root_id = new.root_node_id().cloned().unwrap();
+        let node_1_id = new.get(&root_id).unwrap().children()[0].clone();
+        let node_2_id = new.get(&root_id).unwrap().children()[1].clone();
+        let node_3_id = new.get(&node_1_id).unwrap().children()[0].clone();
 
-        node
-    }
+        new.move_node(&node_3_id, ToParent(&node_2_id)).unwrap();
 
-    fn clear_parent(&mut self, node_id: &NodeId) {
-        self.set_parent(node_id, None);
+        assert_eq!(
+            old.diff(&new),
+            vec![TreeChange::Moved {
+                id: node_3_id,
+                from: Some(node_1_id),
+                to: Some(node_2_id)
+            }]
+        );
     }
 
-    fn set_parent(&mut self, node_id: &NodeId, parent_id: Option<NodeId>) {
-        self.get_mut(node_id)
-            .expect(
-                "Tree::set_parent: expecting node_id to
-            be present inside tree!",
-            )
-            .set_parent(parent_id);
+    #[test]
+    fn test_diff_data_edit() {
+        let old = diff_fixture();
+        let mut new = diff_fixture();
+
+        let root_id = new.root_node_id().cloned().unwrap();
+        let node_1_id = new.get(&root_id).unwrap().children()[0].clone();
+        new.get_mut(&node_1_id).unwrap().replace_data(42);
+
+        assert_eq!(old.diff(&new), vec![TreeChange::DataChanged(node_1_id)]);
     }
 
-    fn clear_parent_of_children(&mut self, node_id: &NodeId) {
-        self.set_parent_of_children(node_id, None);
+    #[test]
+    fn test_for_each_pre_order() {
+        let tree = diff_fixture();
+        let root_id = tree.root_node_id().unwrap();
+
+        let mut visited = vec![];
+        tree.for_each_pre_order(root_id, |node| visited.push(*node.data()))
+            .unwrap();
+
+        let expected: Vec<i32> = tree
+            .traverse_pre_order(root_id)
+            .unwrap()
+            .map(|node| *node.data())
+            .collect();
+
+        assert_eq!(visited, expected);
+        assert_eq!(visited, vec![0, 1, 3, 2]);
     }
 
-    fn set_parent_of_children(&mut self, node_id: &NodeId, new_parent: Option<&NodeId>) {
-        for child_id in self
-            .get(node_id)
-            .expect("Tree::set_parent_of_child: expect node_id to be a valid node inside tree.")
-            .children
-            .clone()
-        {
-            self.set_parent(&child_id, new_parent.cloned());
-        }
+    #[test]
+    fn test_count_where_removed_id() {
+        let mut tree = diff_fixture();
+        let root_id = tree.root_node_id().cloned().unwrap();
+        let node_1_id = tree.get(&root_id).unwrap().children()[0].clone();
+
+        assert_eq!(tree.count_where(&node_1_id, |_| true), Ok(2));
+
+        tree.remove_node(node_1_id.clone(), RemoveBehavior::DropChildren)
+            .unwrap();
+        assert_eq!(
+            tree.count_where(&node_1_id, |_| true),
+            Err(NodeIdError::NodeIdNoLongerValid)
+        );
     }
-}
 
-impl<T: std::fmt::Debug> Tree<T> {
-    /// Write formatted tree representation and nodes with debug formatting.
-    ///
-    ///
-    /// # Errors
-    ///
-    /// Function can error if something goes wrong during debug!
-    ///
-    /// # Panics
-    ///
-    /// Function can error if something goes wrong during debug!
-    ///
-    /// ```
-    /// use sakura::Tree;
-    /// use sakura::Node;
-    /// use sakura::InsertBehavior::*;
-    ///
-    /// let mut tree = Tree::<i32>::new();
-    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
-    /// let first_child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
-    /// let _ = tree.insert(Node::new(2), UnderNode(&first_child_id)).unwrap();
-    /// let _ = tree.insert(Node::new(3), UnderNode(&root_id)).unwrap();
-    /// let mut s = String::new();
-    /// tree.write_formatted(&mut s).unwrap();
-    /// assert_eq!(&s, "\
-    /// 0
-    /// ├── 1
-    /// │   └── 2
-    /// └── 3
-    /// ");
-    /// ```
-    ///
-    /// Writes nothing if the tree is empty.
-    ///
-    /// ```
-    /// use sakura::Tree;
-    ///
-    /// let tree = Tree::<i32>::new();
-    /// let mut s = String::new();
-    /// tree.write_formatted(&mut s).unwrap();
-    /// # assert_eq!(&s, "");
-    /// ```
-    pub fn write_formatted<W: std::fmt::Write>(&self, w: &mut W) -> std::fmt::Result {
-        if let Some(node_id) = self.root_node_id() {
-            let childn = 0;
-            let level = 0;
-            let last = vec![];
-            let mut stack = vec![(node_id, childn, level, last)];
-            while let Some((node_id, childn, level, last)) = stack.pop() {
-                debug_assert_eq!(
-                    last.len(),
-                    level,
-                    "each previous level should indicate whether it has reached the last node"
-                );
-                let node = self
-                    .get(node_id)
-                    .expect("getting node of existing node ref id");
-                if childn == 0 {
-                    for i in 1..level {
-                        if last[i - 1] {
-                            write!(w, "    ")?;
-                        } else {
-                            write!(w, "│   ")?;
-                        }
-                    }
-                    if level > 0 {
-                        if last[level - 1] {
-                            write!(w, "└── ")?;
-                        } else {
-                            write!(w, "├── ")?;
-                        }
-                    }
-                    writeln!(w, "{:?}", node.data())?;
-                }
-                let mut children = node.children().iter().skip(childn);
-                if let Some(child_id) = children.next() {
-                    let mut next_last = last.clone();
-                    if children.next().is_some() {
-                        stack.push((node_id, childn + 1, level, last));
-                        next_last.push(false);
-                    } else {
-                        next_last.push(true);
-                    }
-                    stack.push((child_id, 0, level + 1, next_last));
-                }
-            }
-        }
-        Ok(())
+    #[test]
+    fn test_for_each_pre_order_removed_id() {
+        let mut tree = diff_fixture();
+        let root_id = tree.root_node_id().cloned().unwrap();
+        let node_2_id = tree.get(&root_id).unwrap().children()[1].clone();
+        tree.remove_node(node_2_id.clone(), RemoveBehavior::DropChildren)
+            .unwrap();
+
+        assert_eq!(
+            tree.for_each_pre_order(&node_2_id, |_| {}),
+            Err(NodeIdError::NodeIdNoLongerValid)
+        );
     }
-}
 
-#[cfg(test)]
-mod tree_builder_tests {
-    use super::super::Node;
-    use super::TreeBuilder;
+    #[test]
+    fn test_replace_subtree() {
+        use InsertBehavior::*;
+
+        let mut tree = diff_fixture();
+        let root_id = tree.root_node_id().cloned().unwrap();
+        let node_1_id = tree.get(&root_id).unwrap().children()[0].clone();
+        let node_3_id = tree.get(&node_1_id).unwrap().children()[0].clone();
+
+        let mut new = Tree::new();
+        let new_root_id = new.insert(Node::new(10), AsRoot).unwrap();
+        let new_child_id = new.insert(Node::new(11), UnderNode(&new_root_id)).unwrap();
+        new.insert(Node::new(12), UnderNode(&new_root_id)).unwrap();
+        new.insert(Node::new(13), UnderNode(&new_child_id)).unwrap();
+
+        tree.replace_subtree(&node_1_id, new).unwrap();
+
+        // The node keeps its id and position, but takes the new root's data.
+        assert_eq!(tree.get(&root_id).unwrap().children()[0], node_1_id);
+        assert_eq!(tree.get(&node_1_id).unwrap().data(), &10);
+
+        // The old descendants are gone (their slots get reused by the graft).
+        assert_eq!(tree.nodes.iter().flatten().count(), 6);
+        assert_ne!(tree.get(&node_3_id).unwrap().data(), &3);
+
+        let subtree: Vec<i32> = tree
+            .traverse_pre_order(&node_1_id)
+            .unwrap()
+            .map(|node| *node.data())
+            .collect();
+        assert_eq!(subtree, vec![10, 11, 13, 12]);
+
+        // Replacing with an empty tree only drops the descendants.
+        tree.replace_subtree(&node_1_id, Tree::new()).unwrap();
+        assert_eq!(tree.get(&node_1_id).unwrap().data(), &10);
+        assert!(tree.get(&node_1_id).unwrap().children().is_empty());
+    }
 
     #[test]
-    fn test_new() {
-        let tb: TreeBuilder<i32> = TreeBuilder::new();
-        assert!(tb.root.is_none());
-        assert_eq!(tb.node_capacity, 0);
-        assert_eq!(tb.swap_capacity, 0);
+    fn test_ancestor_matching() {
+        use InsertBehavior::*;
+
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(100), AsRoot).unwrap();
+        let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let node_2_id = tree.insert(Node::new(2), UnderNode(&node_1_id)).unwrap();
+        let node_3_id = tree.insert(Node::new(3), UnderNode(&node_2_id)).unwrap();
+
+        // Near: the direct parent matches.
+        assert_eq!(
+            tree.ancestor_matching(&node_3_id, |n| *n.data() == 2),
+            Ok(Some(node_2_id.clone()))
+        );
+
+        // Far: only the root matches.
+        assert_eq!(
+            tree.ancestor_matching(&node_3_id, |n| *n.data() >= 100),
+            Ok(Some(root_id.clone()))
+        );
+
+        // The node itself is never tested.
+        assert_eq!(
+            tree.ancestor_matching(&node_3_id, |n| *n.data() == 3),
+            Ok(None)
+        );
+
+        // Not at all.
+        assert_eq!(
+            tree.ancestor_matching(&node_3_id, |n| *n.data() > 1000),
+            Ok(None)
+        );
+        assert_eq!(tree.ancestor_matching(&root_id, |_| true), Ok(None));
     }
 
     #[test]
-    fn test_with_root() {
-        let tb: TreeBuilder<i32> = TreeBuilder::new().with_root(Node::new(5));
+    fn test_eq_unordered() {
+        use InsertBehavior::*;
 
-        assert_eq!(tb.root.unwrap().data(), &5);
-        assert_eq!(tb.node_capacity, 0);
-        assert_eq!(tb.swap_capacity, 0);
+        let tree = diff_fixture();
+
+        // Same nodes, but the root's children are inserted in the opposite order.
+        let mut other = Tree::new();
+        let root_id = other.insert(Node::new(0), AsRoot).unwrap();
+        other.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+        let node_1_id = other.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        other.insert(Node::new(3), UnderNode(&node_1_id)).unwrap();
+
+        assert!(tree.eq_unordered(&other));
+        assert!(!tree.structurally_eq(&other));
+
+        // Moving a node to a different parent is still a difference.
+        let node_3_id = other
+            .children_ids(&node_1_id)
+            .unwrap()
+            .next()
+            .unwrap()
+            .clone();
+        other
+            .move_node(&node_3_id, MoveBehavior::ToParent(&root_id))
+            .unwrap();
+        assert!(!tree.eq_unordered(&other));
+
+        assert!(Tree::<i32>::new().eq_unordered(&Tree::new()));
+        assert!(!tree.eq_unordered(&Tree::new()));
     }
 
     #[test]
-    fn test_with_node_capacity() {
-        let tb: TreeBuilder<i32> = TreeBuilder::new().with_node_capacity(10);
+    fn test_structurally_eq() {
+        use InsertBehavior::*;
 
-        assert!(tb.root.is_none());
-        assert_eq!(tb.node_capacity, 10);
-        assert_eq!(tb.swap_capacity, 0);
+        let tree = diff_fixture();
+
+        // Same shape and data, built with different `NodeId`s.
+        let mut other = Tree::new();
+        let root_id = other.insert(Node::new(0), AsRoot).unwrap();
+        let node_1_id = other.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        other.insert(Node::new(3), UnderNode(&node_1_id)).unwrap();
+        other.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+
+        assert_ne!(tree, other);
+        assert!(tree.structurally_eq(&other));
+
+        other.insert(Node::new(4), UnderNode(&root_id)).unwrap();
+        assert!(!tree.structurally_eq(&other));
     }
 
     #[test]
-    fn test_with_swap_capacity() {
-        let tb: TreeBuilder<i32> = TreeBuilder::new().with_swap_capacity(10);
+    fn test_try_get() {
+        use InsertBehavior::*;
 
-        assert!(tb.root.is_none());
-        assert_eq!(tb.node_capacity, 0);
-        assert_eq!(tb.swap_capacity, 10);
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        tree.remove_node(child_id.clone(), RemoveBehavior::DropChildren)
+            .unwrap();
+        let bogus_id = NodeId { index: 1000 };
+
+        assert_eq!(tree.try_get(&root_id).map(Node::data), Some(&0));
+        assert!(tree.try_get(&child_id).is_none());
+        assert!(tree.try_get(&bogus_id).is_none());
+
+        *tree.try_get_mut(&root_id).unwrap().data_mut() = 5;
+        assert_eq!(tree.try_get(&root_id).map(Node::data), Some(&5));
+        assert!(tree.try_get_mut(&child_id).is_none());
+        assert!(tree.try_get_mut(&bogus_id).is_none());
     }
 
     #[test]
-    fn test_with_all_settings() {
-        let tb: TreeBuilder<i32> = TreeBuilder::new()
-            .with_root(Node::new(5))
-            .with_node_capacity(10)
-            .with_swap_capacity(3);
+    fn test_from_iter() {
+        let tree: Tree<i32> = vec![0, 1, 2, 3].into_iter().collect();
+
+        let root_id = tree.root_node_id().unwrap();
+        assert_eq!(tree.get(root_id).unwrap().data(), &0);
+        assert_eq!(tree.height(), 2);
+        assert_eq!(
+            tree.children(root_id)
+                .unwrap()
+                .map(Node::data)
+                .collect::<Vec<_>>(),
+            vec![&1, &2, &3]
+        );
 
-        assert_eq!(tb.root.unwrap().data(), &5);
-        assert_eq!(tb.node_capacity, 10);
-        assert_eq!(tb.swap_capacity, 3);
+        let empty: Tree<i32> = std::iter::empty().collect();
+        assert!(empty.root_node_id().is_none());
     }
 
     #[test]
-    fn test_build() {
-        let tree = TreeBuilder::new()
-            .with_root(Node::new(5))
-            .with_node_capacity(10)
-            .with_swap_capacity(3)
-            .build();
+    fn test_move_node_to_parent_at_index() {
+        use InsertBehavior::*;
+        use MoveBehavior::*;
 
-        let root = tree.get(tree.root_node_id().unwrap()).unwrap();
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let parent_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let a_id = tree.insert(Node::new(2), UnderNode(&parent_id)).unwrap();
+        let b_id = tree.insert(Node::new(3), UnderNode(&parent_id)).unwrap();
+        let x_id = tree.insert(Node::new(4), UnderNode(&root_id)).unwrap();
+        let y_id = tree.insert(Node::new(5), UnderNode(&root_id)).unwrap();
+        let z_id = tree.insert(Node::new(6), UnderNode(&root_id)).unwrap();
+
+        // Front, under a new parent.
+        tree.move_node(&x_id, ToParentAtIndex(&parent_id, 0))
+            .unwrap();
+        assert_eq!(
+            tree.get(&parent_id).unwrap().children(),
+            &vec![x_id.clone(), a_id.clone(), b_id.clone()]
+        );
+        assert_eq!(tree.get(&x_id).unwrap().parent(), Some(&parent_id));
+        assert!(!tree.get(&root_id).unwrap().children().contains(&x_id));
+
+        // Middle, under a new parent.
+        tree.move_node(&y_id, ToParentAtIndex(&parent_id, 2))
+            .unwrap();
+        assert_eq!(
+            tree.get(&parent_id).unwrap().children(),
+            &vec![x_id.clone(), a_id.clone(), y_id.clone(), b_id.clone()]
+        );
 
-        assert_eq!(root.data(), &5);
-        assert_eq!(tree.capacity(), 10);
-        assert_eq!(tree.free_ids.capacity(), 3);
-    }
-}
+        // End, under a new parent, with the index clamped.
+        tree.move_node(&z_id, ToParentAtIndex(&parent_id, 100))
+            .unwrap();
+        assert_eq!(
+            tree.get(&parent_id).unwrap().children(),
+            &vec![
+                x_id.clone(),
+                a_id.clone(),
+                y_id.clone(),
+                b_id.clone(),
+                z_id.clone()
+            ]
+        );
 
-#[cfg(test)]
-#[allow(clippy::similar_names)]
-mod tree_tests {
-    use crate::InsertBehavior;
-    use crate::MoveBehavior;
-    use crate::RemoveBehavior;
+        // Reordering under the same parent.
+        tree.move_node(&x_id, ToParentAtIndex(&parent_id, 3))
+            .unwrap();
+        assert_eq!(
+            tree.get(&parent_id).unwrap().children(),
+            &vec![a_id.clone(), y_id, b_id, x_id, z_id]
+        );
+        assert_eq!(tree.get(&root_id).unwrap().children(), &vec![parent_id]);
 
-    use super::super::Node;
-    use super::super::NodeId;
-    use super::Tree;
-    use super::TreeBuilder;
+        // An invalid parent is an error.
+        let removed_id = tree.insert(Node::new(7), UnderNode(&root_id)).unwrap();
+        tree.remove_node(removed_id.clone(), RemoveBehavior::DropChildren)
+            .unwrap();
+        assert!(
+            tree.move_node(&a_id, ToParentAtIndex(&removed_id, 0))
+                .is_err()
+        );
+    }
 
     #[test]
-    fn test_new() {
-        let tree: Tree<i32> = Tree::new();
-
-        assert_eq!(tree.root, None);
-        assert_eq!(tree.nodes.len(), 0);
-        assert_eq!(tree.free_ids.len(), 0);
+    fn test_walk_mut() {
+        let mut tree = diff_fixture();
+        let root_id = tree.root_node_id().unwrap().clone();
+        let node_1_id = tree.children_ids(&root_id).unwrap().next().unwrap().clone();
+
+        tree.walk_mut(&node_1_id, |node| *node.data_mut() += 10)
+            .unwrap();
+
+        let data: Vec<_> = tree
+            .traverse_pre_order(&root_id)
+            .unwrap()
+            .map(|node| *node.data())
+            .collect();
+        assert_eq!(data, vec![0, 11, 13, 2]);
+
+        tree.remove_node(node_1_id.clone(), RemoveBehavior::DropChildren)
+            .unwrap();
+        assert!(tree.walk_mut(&node_1_id, |_| {}).is_err());
     }
 
     #[test]
-    fn test_get() {
-        let tree = TreeBuilder::new().with_root(Node::new(5)).build();
+    fn test_move_many() {
+        use InsertBehavior::*;
 
-        let root_id = tree.root.clone().unwrap();
-        let root = tree.get(&root_id).unwrap();
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let target_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let a_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+        let b_id = tree.insert(Node::new(3), UnderNode(&root_id)).unwrap();
+        let c_id = tree.insert(Node::new(4), UnderNode(&a_id)).unwrap();
 
-        assert_eq!(root.data(), &5);
+        tree.move_many(&[b_id.clone(), c_id.clone()], &target_id)
+            .unwrap();
+
+        assert_eq!(
+            tree.get(&target_id).unwrap().children(),
+            &vec![b_id.clone(), c_id.clone()]
+        );
+        assert_eq!(
+            tree.get(&root_id).unwrap().children(),
+            &vec![target_id.clone(), a_id.clone()]
+        );
+        assert!(tree.get(&a_id).unwrap().children().is_empty());
+        assert_eq!(tree.get(&b_id).unwrap().parent(), Some(&target_id));
+        assert_eq!(tree.get(&c_id).unwrap().parent(), Some(&target_id));
     }
 
     #[test]
-    fn test_get_mut() {
-        let mut tree = TreeBuilder::new().with_root(Node::new(5)).build();
+    fn test_move_many_nested_selection() {
+        use InsertBehavior::*;
 
-        let root_id = tree.root.clone().unwrap();
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let target_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let a_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+        let child_id = tree.insert(Node::new(3), UnderNode(&a_id)).unwrap();
 
-        {
-            let root = tree.get(&root_id).unwrap();
-            assert_eq!(root.data(), &5);
-        }
+        // `child_id` travels with `a_id`, instead of being moved on its own.
+        tree.move_many(&[child_id.clone(), a_id.clone()], &target_id)
+            .unwrap();
 
-        {
-            let root = tree.get_mut(&root_id).unwrap();
-            *root.data_mut() = 6;
-        }
+        assert_eq!(
+            tree.get(&target_id).unwrap().children(),
+            &vec![a_id.clone()]
+        );
+        assert_eq!(tree.get(&a_id).unwrap().children(), &vec![child_id]);
 
-        let root = tree.get(&root_id).unwrap();
-        assert_eq!(root.data(), &6);
+        // Moving a node under its own descendant is rejected, and moves nothing.
+        let before = tree.get(&root_id).unwrap().children().clone();
+        assert_eq!(
+            tree.move_many(std::slice::from_ref(&target_id), &a_id),
+            Err(NodeIdError::WouldCreateCycle)
+        );
+        assert_eq!(
+            tree.move_many(std::slice::from_ref(&target_id), &target_id),
+            Err(NodeIdError::WouldCreateCycle)
+        );
+        assert_eq!(tree.get(&root_id).unwrap().children(), &before);
+        assert_eq!(tree.get(&a_id).unwrap().parent(), Some(&target_id));
     }
 
     #[test]
-    fn test_set_root() {
+    fn test_contains() {
         use InsertBehavior::*;
 
-        let a = 5;
-        let b = 6;
-        let node_a = Node::new(a);
-        let node_b = Node::new(b);
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        tree.remove_node(child_id.clone(), RemoveBehavior::DropChildren)
+            .unwrap();
 
-        let mut tree = TreeBuilder::new().build();
+        assert!(tree.contains(&root_id));
+        assert!(!tree.contains(&child_id));
+        assert!(!tree.contains(&NodeId { index: 1000 }));
+    }
 
-        let node_a_id = tree.insert(node_a, AsRoot).unwrap();
-        let root_id = tree.root.clone().unwrap();
-        assert_eq!(node_a_id, root_id);
+    #[test]
+    fn test_root_or_insert_with() {
+        use InsertBehavior::*;
 
-        {
-            let node_a_ref = tree.get(&node_a_id).unwrap();
-            let root_ref = tree.get(&root_id).unwrap();
-            assert_eq!(node_a_ref.data(), &a);
-            assert_eq!(root_ref.data(), &a);
-        }
+        // Creates a root in an empty tree.
+        let mut tree = Tree::new();
+        let root_id = tree.root_or_insert_with(|| 0);
+        assert_eq!(tree.root_node_id(), Some(&root_id));
+        assert_eq!(tree.get(&root_id).unwrap().data(), &0);
+
+        // Returns the existing root, without calling `f`.
+        tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let existing_id = tree.root_or_insert_with(|| unreachable!());
+        assert_eq!(existing_id, root_id);
+        assert_eq!(tree.nodes.iter().flatten().count(), 2);
+    }
 
-        let node_b_id = tree.insert(node_b, AsRoot).unwrap();
-        let root_id = tree.root.clone().unwrap();
-        assert_eq!(node_b_id, root_id);
+    #[test]
+    fn test_swap_data() {
+        let mut tree = diff_fixture();
+        let root_id = tree.root_node_id().unwrap().clone();
+        let mut children = tree.children_ids(&root_id).unwrap().cloned();
+        let node_1_id = children.next().unwrap();
+        let node_2_id = children.next().unwrap();
+        let node_3_id = tree.first_child(&node_1_id).unwrap().unwrap();
+
+        tree.swap_data(&node_3_id, &node_2_id).unwrap();
+
+        let data: Vec<_> = tree
+            .traverse_pre_order(&root_id)
+            .unwrap()
+            .map(|node| *node.data())
+            .collect();
+        assert_eq!(data, vec![0, 1, 2, 3]);
+
+        // The structure is unchanged.
+        assert_eq!(
+            tree.get(&root_id).unwrap().children(),
+            &vec![node_1_id.clone(), node_2_id.clone()]
+        );
+        assert_eq!(tree.get(&node_3_id).unwrap().parent(), Some(&node_1_id));
+        assert!(tree.get(&node_2_id).unwrap().children().is_empty());
 
-        {
-            let node_b_ref = tree.get(&node_b_id).unwrap();
-            let root_ref = tree.get(&root_id).unwrap();
-            assert_eq!(node_b_ref.data(), &b);
-            assert_eq!(root_ref.data(), &b);
+        // Swapping a node with itself is a no-op.
+        tree.swap_data(&node_2_id, &node_2_id).unwrap();
+        assert_eq!(tree.get(&node_2_id).unwrap().data(), &3);
 
-            let node_b_child_id = node_b_ref.children().first().unwrap();
-            let node_b_child_ref = tree.get(node_b_child_id).unwrap();
-            assert_eq!(node_b_child_ref.data(), &a);
-        }
+        tree.remove_node(node_3_id.clone(), RemoveBehavior::DropChildren)
+            .unwrap();
+        assert!(tree.swap_data(&node_3_id, &node_2_id).is_err());
     }
 
     #[test]
-    fn test_root_node_id() {
-        let tree = TreeBuilder::new().with_root(Node::new(5)).build();
+    fn test_rebuild_indices_preorder() {
+        use InsertBehavior::*;
 
-        let root_id = tree.root.clone().unwrap();
-        let root_node_id = tree.root_node_id().unwrap();
+        // Same structure as `diff_fixture`, but with a different history.
+        let mut other = Tree::new();
+        let root_id = other.insert(Node::new(0), AsRoot).unwrap();
+        let temp_id = other.insert(Node::new(9), UnderNode(&root_id)).unwrap();
+        let node_1_id = other.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let node_3_id = other.insert(Node::new(3), UnderNode(&node_1_id)).unwrap();
+        other
+            .remove_node(temp_id, RemoveBehavior::DropChildren)
+            .unwrap();
+        other.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+
+        let mut tree = diff_fixture();
+        assert!(tree.structurally_eq(&other));
+        assert_ne!(
+            serde_json::to_vec(&tree).unwrap(),
+            serde_json::to_vec(&other).unwrap()
+        );
 
-        assert_eq!(&root_id, root_node_id);
+        tree.rebuild_indices_preorder();
+        let remap = other.rebuild_indices_preorder();
+
+        assert_eq!(
+            serde_json::to_vec(&tree).unwrap(),
+            serde_json::to_vec(&other).unwrap()
+        );
+        assert_eq!(remap[&root_id], NodeId::new(0));
+        assert_eq!(remap[&node_1_id], NodeId::new(1));
+        assert_eq!(remap[&node_3_id], NodeId::new(2));
+        assert!(other.free_ids.is_empty());
     }
 
     #[test]
-    fn test_insert_with_parent() {
+    fn test_rebuild_indices_preorder_keeps_orphans() {
         use InsertBehavior::*;
 
-        let a = 1;
-        let b = 2;
-        let r = 5;
+        let mut tree = diff_fixture();
+        let node_1_id = tree.get(&NodeId::new(0)).unwrap().children()[0].clone();
+        tree.remove_node(node_1_id, RemoveBehavior::OrphanChildren)
+            .unwrap();
+        let orphan_id = NodeId::new(3);
+        tree.insert(Node::new(4), UnderNode(&orphan_id)).unwrap();
 
-        let mut tree = TreeBuilder::new().with_root(Node::new(r)).build();
+        let remap = tree.rebuild_indices_preorder();
 
-        let node_a = Node::new(a);
-        let node_b = Node::new(b);
+        assert_eq!(tree.nodes.len(), 4);
+        assert_eq!(remap[&orphan_id], NodeId::new(2));
+        assert_eq!(
+            tree.children_ids(&remap[&orphan_id])
+                .unwrap()
+                .map(|id| *tree.get(id).unwrap().data())
+                .collect::<Vec<_>>(),
+            vec![4]
+        );
+    }
 
-        let root_id = tree.root.clone().unwrap();
-        let node_a_id = tree.insert(node_a, UnderNode(&root_id)).unwrap();
-        let node_b_id = tree.insert(node_b, UnderNode(&root_id)).unwrap();
+    #[test]
+    fn test_slot_and_free_count() {
+        use InsertBehavior::*;
 
-        let node_a_ref = tree.get(&node_a_id).unwrap();
-        let node_b_ref = tree.get(&node_b_id).unwrap();
-        assert_eq!(node_a_ref.data(), &a);
-        assert_eq!(node_b_ref.data(), &b);
+        let mut tree = diff_fixture();
+        assert_eq!(tree.len(), 4);
+        assert_eq!(tree.slot_count(), 4);
+        assert_eq!(tree.free_count(), 0);
+
+        // Drops node 1 and its child 3.
+        let node_1_id = tree.get(&NodeId::new(0)).unwrap().children()[0].clone();
+        tree.remove_node(node_1_id, RemoveBehavior::DropChildren)
+            .unwrap();
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree.slot_count(), 4);
+        assert_eq!(tree.free_count(), 2);
+
+        // Reuses a hole.
+        let root_id = tree.root_node_id().unwrap().clone();
+        tree.insert(Node::new(4), UnderNode(&root_id)).unwrap();
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.slot_count(), 4);
+        assert_eq!(tree.free_count(), 1);
+
+        tree.rebuild_indices_preorder();
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.slot_count(), 3);
+        assert_eq!(tree.free_count(), 0);
+        assert!(!tree.is_empty());
+    }
 
-        assert_eq!(node_a_ref.parent().unwrap().clone(), root_id);
-        assert_eq!(node_b_ref.parent().unwrap().clone(), root_id);
+    #[test]
+    fn test_root_to_leaf_paths() {
+        let tree = diff_fixture();
+        let id = NodeId::new;
 
-        let root_node_ref = tree.get(&root_id).unwrap();
-        let root_children: &Vec<NodeId> = root_node_ref.children();
+        let paths: Vec<Vec<NodeId>> = tree.root_to_leaf_paths().collect();
 
-        let child_1_id = root_children.first().unwrap();
-        let child_2_id = root_children.get(1).unwrap();
+        assert_eq!(paths, vec![vec![id(0), id(1), id(3)], vec![id(0), id(2)]]);
 
-        let child_1_ref = tree.get(child_1_id).unwrap();
-        let child_2_ref = tree.get(child_2_id).unwrap();
+        let empty: Tree<i32> = Tree::new();
+        assert_eq!(empty.root_to_leaf_paths().count(), 0);
+    }
 
-        assert_eq!(child_1_ref.data(), &a);
-        assert_eq!(child_2_ref.data(), &b);
+    #[test]
+    fn test_is_ancestor_of() {
+        let mut tree = diff_fixture();
+        let id = NodeId::new;
+
+        assert!(tree.is_ancestor_of(&id(0), &id(3)).unwrap());
+        assert!(tree.is_ancestor_of(&id(1), &id(3)).unwrap());
+        assert!(!tree.is_ancestor_of(&id(2), &id(3)).unwrap());
+        assert!(!tree.is_ancestor_of(&id(3), &id(0)).unwrap());
+        assert!(!tree.is_ancestor_of(&id(3), &id(3)).unwrap());
+
+        tree.remove_node(id(2), RemoveBehavior::DropChildren)
+            .unwrap();
+        assert!(tree.is_ancestor_of(&id(0), &id(2)).is_err());
     }
 
     #[test]
-    fn test_remove_node_lift_children() {
+    fn test_insert_subtree_as_sibling() {
         use InsertBehavior::*;
-        use RemoveBehavior::*;
 
-        let mut tree = TreeBuilder::new().with_root(Node::new(5)).build();
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let middle_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+        tree.insert(Node::new(3), UnderNode(&root_id)).unwrap();
 
-        let root_id = tree.root.clone().unwrap();
+        let subtree = |data| {
+            let mut other = Tree::new();
+            let other_root_id = other.insert(Node::new(data), AsRoot).unwrap();
+            other
+                .insert(Node::new(data + 1), UnderNode(&other_root_id))
+                .unwrap();
+            other
+        };
 
-        let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
-        let node_2_id = tree.insert(Node::new(2), UnderNode(&node_1_id)).unwrap();
-        let node_3_id = tree.insert(Node::new(3), UnderNode(&node_1_id)).unwrap();
+        let before_id = tree
+            .insert_subtree_as_sibling(&middle_id, subtree(10), false)
+            .unwrap();
+        let after_id = tree
+            .insert_subtree_as_sibling(&middle_id, subtree(20), true)
+            .unwrap();
+
+        let children: Vec<i32> = tree
+            .children(&root_id)
+            .unwrap()
+            .map(|n| *n.data())
+            .collect();
+        assert_eq!(children, vec![1, 10, 2, 20, 3]);
+
+        for (id, child) in [(before_id, 11), (after_id, 21)] {
+            let grandchildren: Vec<i32> = tree.children(&id).unwrap().map(|n| *n.data()).collect();
+            assert_eq!(grandchildren, vec![child]);
+        }
 
-        let node_1 = tree.remove_node(node_1_id.clone(), LiftChildren).unwrap();
+        // Nothing to insert.
+        assert_eq!(
+            tree.insert_subtree_as_sibling(&middle_id, Tree::new(), true),
+            Err(NodeIdError::EmptyTree)
+        );
+        assert_eq!(tree.len(), 8);
+    }
 
-        assert_eq!(Some(&root_id), tree.root_node_id());
+    #[test]
+    fn test_insert_subtree_as_sibling_of_root() {
+        use InsertBehavior::*;
 
-        assert_eq!(node_1.data(), &1);
-        assert_eq!(node_1.children().len(), 0);
-        assert!(node_1.parent().is_none());
-        assert!(tree.get(&node_1_id).is_err());
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
 
-        let root_ref = tree.get(&root_id).unwrap();
-        let node_2_ref = tree.get(&node_2_id).unwrap();
-        let node_3_ref = tree.get(&node_3_id).unwrap();
+        let mut other = Tree::new();
+        other.insert(Node::new(1), AsRoot).unwrap();
 
-        assert_eq!(node_2_ref.data(), &2);
-        assert_eq!(node_3_ref.data(), &3);
+        assert_eq!(
+            tree.insert_subtree_as_sibling(&root_id, other, false),
+            Err(NodeIdError::NoSiblings)
+        );
+        assert_eq!(tree.len(), 1);
+    }
 
-        assert_eq!(node_2_ref.parent().unwrap(), &root_id);
-        assert_eq!(node_3_ref.parent().unwrap(), &root_id);
+    #[test]
+    fn test_estimated_size_bytes() {
+        use InsertBehavior::*;
 
-        assert!(root_ref.children().contains(&node_2_id));
-        assert!(root_ref.children().contains(&node_3_id));
+        let mut tree = Tree::new();
+        let empty = tree.estimated_size_bytes();
+
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        for i in 1..10 {
+            tree.insert(Node::new(i), UnderNode(&root_id)).unwrap();
+        }
+        let grown = tree.estimated_size_bytes();
+        assert!(grown > empty);
+
+        tree.shrink_to_fit();
+        let shrunk = tree.estimated_size_bytes();
+        assert!(shrunk < grown);
+        assert!(shrunk > empty);
+        assert_eq!(tree.capacity(), 10);
     }
 
     #[test]
-    fn test_remove_node_orphan_children() {
+    fn test_reverse_children_recursive() {
         use InsertBehavior::*;
-        use RemoveBehavior::*;
 
-        let mut tree = TreeBuilder::new().with_root(Node::new(5)).build();
+        let mut tree = diff_fixture();
+        let root_id = tree.root_node_id().unwrap().clone();
+        let node_1_id = tree.get(&root_id).unwrap().children()[0].clone();
+        tree.insert(Node::new(4), UnderNode(&node_1_id)).unwrap();
 
-        let root_id = tree.root.clone().unwrap();
+        let pre_order = |tree: &Tree<i32>| -> Vec<i32> {
+            tree.traverse_pre_order(&root_id)
+                .unwrap()
+                .map(|node| *node.data())
+                .collect()
+        };
+        assert_eq!(pre_order(&tree), vec![0, 1, 3, 4, 2]);
 
-        let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
-        let node_2_id = tree.insert(Node::new(2), UnderNode(&node_1_id)).unwrap();
-        let node_3_id = tree.insert(Node::new(3), UnderNode(&node_1_id)).unwrap();
+        tree.reverse_children_recursive(&root_id).unwrap();
+        assert_eq!(pre_order(&tree), vec![0, 2, 1, 4, 3]);
 
-        let node_1 = tree.remove_node(node_1_id.clone(), OrphanChildren).unwrap();
+        // Only the subtree is affected.
+        tree.reverse_children_recursive(&node_1_id).unwrap();
+        assert_eq!(pre_order(&tree), vec![0, 2, 1, 3, 4]);
+    }
 
-        assert_eq!(Some(&root_id), tree.root_node_id());
+    #[test]
+    fn test_drain_where() {
+        // root 0 -> [1 -> [3], 2], draining 1 lifts 3 under the root.
+        let mut tree = diff_fixture();
+        let root_id = NodeId::new(0);
+
+        let drained = tree.drain_where(|node| node.data() % 2 == 1);
+
+        assert_eq!(drained, vec![1, 3]);
+        assert_eq!(tree.len(), 2);
+        assert_eq!(
+            tree.get(&root_id).unwrap().children(),
+            &vec![NodeId::new(2)]
+        );
 
-        assert_eq!(node_1.data(), &1);
-        assert_eq!(node_1.children().len(), 0);
-        assert!(node_1.parent().is_none());
-        assert!(tree.get(&node_1_id).is_err());
+        let mut tree = diff_fixture();
+        let drained = tree.drain_where(|node| *node.data() == 1);
 
-        let node_2_ref = tree.get(&node_2_id).unwrap();
-        let node_3_ref = tree.get(&node_3_id).unwrap();
+        assert_eq!(drained, vec![1]);
+        assert_eq!(
+            tree.get(&root_id).unwrap().children(),
+            &vec![NodeId::new(3), NodeId::new(2)]
+        );
+        assert_eq!(tree.parent(&NodeId::new(3)).unwrap(), Some(root_id));
 
-        assert_eq!(node_2_ref.data(), &2);
-        assert_eq!(node_3_ref.data(), &3);
+        // Nothing matching leaves the tree alone.
+        let mut tree = diff_fixture();
+        assert!(tree.drain_where(|_| false).is_empty());
+        assert_eq!(tree, diff_fixture());
+    }
 
-        assert!(node_2_ref.parent().is_none());
-        assert!(node_3_ref.parent().is_none());
+    #[test]
+    fn test_path_between() {
+        // root 0 -> [1 -> [3], 2]
+        let mut tree = diff_fixture();
+        let ids: Vec<NodeId> = (0..4).map(NodeId::new).collect();
+
+        // Two siblings go through their parent.
+        assert_eq!(
+            tree.path_between(&ids[1], &ids[2]).unwrap(),
+            Some(vec![ids[1].clone(), ids[0].clone(), ids[2].clone()])
+        );
+
+        // A node to its descendant only goes down, and back up the other way.
+        assert_eq!(
+            tree.path_between(&ids[0], &ids[3]).unwrap(),
+            Some(vec![ids[0].clone(), ids[1].clone(), ids[3].clone()])
+        );
+        assert_eq!(
+            tree.path_between(&ids[3], &ids[0]).unwrap(),
+            Some(vec![ids[3].clone(), ids[1].clone(), ids[0].clone()])
+        );
+        assert_eq!(
+            tree.path_between(&ids[2], &ids[2]).unwrap(),
+            Some(vec![ids[2].clone()])
+        );
+        assert_eq!(
+            tree.lowest_common_ancestor(&ids[3], &ids[2]).unwrap(),
+            Some(ids[0].clone())
+        );
+
+        // Removing the root leaves its subtrees disconnected.
+        tree.remove_node(ids[0].clone(), RemoveBehavior::OrphanChildren)
+            .unwrap();
+        assert_eq!(tree.path_between(&ids[3], &ids[2]).unwrap(), None);
+        assert_eq!(tree.lowest_common_ancestor(&ids[3], &ids[2]).unwrap(), None);
+
+        let removed_id = ids[0].clone();
+        assert!(tree.path_between(&removed_id, &ids[2]).is_err());
     }
 
     #[test]
-    fn test_remove_root() {
-        use RemoveBehavior::*;
+    fn test_cached_height() {
+        use InsertBehavior::*;
 
-        let mut tree = TreeBuilder::new().with_root(Node::new(5)).build();
+        // root 0 -> [1 -> [3], 2]
+        let mut tree = diff_fixture();
+        let ids: Vec<NodeId> = (0..4).map(NodeId::new).collect();
+        assert_eq!(tree.cached_height(), tree.height());
+        assert_eq!(tree.cached_height(), 3);
 
-        let root_id = tree.root.clone().unwrap();
-        tree.remove_node(root_id, OrphanChildren).unwrap();
-        assert_eq!(None, tree.root_node_id());
+        let deep_id = tree.insert(Node::new(4), UnderNode(&ids[3])).unwrap();
+        assert_eq!(tree.cached_height(), tree.height());
+        assert_eq!(tree.cached_height(), 4);
 
-        let mut tree = TreeBuilder::new().with_root(Node::new(5)).build();
+        tree.move_node(&deep_id, MoveBehavior::ToParent(&ids[0]))
+            .unwrap();
+        assert_eq!(tree.cached_height(), tree.height());
+        assert_eq!(tree.cached_height(), 3);
 
-        let root_id = tree.root.clone().unwrap();
-        tree.remove_node(root_id, LiftChildren).unwrap();
-        assert_eq!(None, tree.root_node_id());
+        tree.remove_node(ids[1].clone(), RemoveBehavior::DropChildren)
+            .unwrap();
+        assert_eq!(tree.cached_height(), tree.height());
+        assert_eq!(tree.cached_height(), 2);
+
+        tree.insert(Node::new(5), AsRoot).unwrap();
+        assert_eq!(tree.cached_height(), tree.height());
+        assert_eq!(tree.cached_height(), 3);
+
+        tree.move_node(&ids[2], MoveBehavior::ToRoot).unwrap();
+        assert_eq!(tree.cached_height(), tree.height());
+
+        // Editing data keeps the structure, and so the cached height.
+        *tree.get_mut(&ids[2]).unwrap().data_mut() = 10;
+        assert_eq!(tree.cached_height(), tree.height());
     }
 
     #[test]
-    fn test_move_node_to_parent() {
-        use InsertBehavior::*;
-        use MoveBehavior::*;
+    fn test_for_each_ancestor() {
+        // root 0 -> [1 -> [3], 2]
+        let tree = diff_fixture();
 
-        let mut tree = Tree::new();
+        for id in (0..4).map(NodeId::new) {
+            let mut visited = vec![];
+            tree.for_each_ancestor(&id, |node| visited.push(*node.data()))
+                .unwrap();
 
-        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
-        let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
-        let node_2_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
-        let node_3_id = tree.insert(Node::new(3), UnderNode(&node_1_id)).unwrap();
+            let expected: Vec<i32> = tree.ancestors(&id).unwrap().map(|n| *n.data()).collect();
+            assert_eq!(visited, expected);
+        }
 
-        // Move 3 "across" the tree.
-        tree.move_node(&node_3_id, ToParent(&node_2_id)).unwrap();
-        assert!(tree.get(&root_id).unwrap().children().contains(&node_1_id));
-        assert!(tree.get(&root_id).unwrap().children().contains(&node_2_id));
-        assert!(
-            tree.get(&node_2_id,)
-                .unwrap()
-                .children()
-                .contains(&node_3_id,)
-        );
+        let mut visited = vec![];
+        tree.for_each_ancestor(&NodeId::new(3), |node| visited.push(*node.data()))
+            .unwrap();
+        assert_eq!(visited, vec![1, 0]);
+    }
 
-        // Move 3 "up" the tree.
-        tree.move_node(&node_3_id, ToParent(&root_id)).unwrap();
-        assert!(tree.get(&root_id).unwrap().children().contains(&node_1_id));
-        assert!(tree.get(&root_id).unwrap().children().contains(&node_2_id));
-        assert!(tree.get(&root_id).unwrap().children().contains(&node_3_id));
+    #[test]
+    fn test_try_reserve() {
+        let mut tree = diff_fixture();
+        let len = tree.len();
 
-        // Move 3 "down" (really this is across though) the tree.
-        tree.move_node(&node_3_id, ToParent(&node_1_id)).unwrap();
-        assert!(tree.get(&root_id).unwrap().children().contains(&node_1_id));
-        assert!(tree.get(&root_id).unwrap().children().contains(&node_2_id));
-        assert!(
-            tree.get(&node_1_id,)
-                .unwrap()
-                .children()
-                .contains(&node_3_id,)
-        );
+        tree.try_reserve(16).unwrap();
+        assert!(tree.capacity() >= len + 16);
+        assert_eq!(tree, diff_fixture());
 
-        // Move 1 "down" the tree.
-        tree.move_node(&node_1_id, ToParent(&node_3_id)).unwrap();
-        assert!(tree.get(&root_id).unwrap().children().contains(&node_2_id));
-        assert!(tree.get(&root_id).unwrap().children().contains(&node_3_id));
-        assert!(
-            tree.get(&node_3_id,)
-                .unwrap()
-                .children()
-                .contains(&node_1_id,)
+        assert!(tree.try_reserve(usize::MAX).is_err());
+        assert_eq!(tree, diff_fixture());
+    }
+
+    #[test]
+    fn test_set_root_orphaning_old() {
+        let mut tree = Tree::new();
+        let (root_id, orphan_id) = tree.set_root_orphaning_old(Node::new(0));
+        assert_eq!(orphan_id, None);
+        assert_eq!(tree.root_node_id(), Some(&root_id));
+        assert_eq!(tree.len(), 1);
+
+        // root 0 -> [1 -> [3], 2]
+        let mut tree = diff_fixture();
+        let old_root_id = NodeId::new(0);
+
+        let (root_id, orphan_id) = tree.set_root_orphaning_old(Node::new(4));
+        assert_eq!(orphan_id, Some(old_root_id.clone()));
+        assert_eq!(tree.root_node_id(), Some(&root_id));
+        assert_eq!(tree.len(), 5);
+        assert_eq!(tree.height(), 1);
+
+        // The old root keeps its whole subtree, just without a parent.
+        assert_eq!(tree.parent(&old_root_id).unwrap(), None);
+        assert_eq!(
+            tree.collect_subtree_data(&old_root_id).unwrap(),
+            vec![0, 1, 3, 2]
         );
+    }
 
-        // Note: node_1 is at the lowest point in the tree before these insertions.
-        let node_4_id = tree.insert(Node::new(4), UnderNode(&node_1_id)).unwrap();
-        let node_5_id = tree.insert(Node::new(5), UnderNode(&node_4_id)).unwrap();
+    #[test]
+    fn test_common_path_prefix() {
+        use InsertBehavior::*;
 
-        // move 3 "down" the tree
-        tree.move_node(&node_3_id, ToParent(&node_5_id)).unwrap();
-        assert!(tree.get(&root_id).unwrap().children().contains(&node_2_id));
-        assert!(tree.get(&root_id).unwrap().children().contains(&node_1_id));
-        assert!(
-            tree.get(&node_1_id,)
-                .unwrap()
-                .children()
-                .contains(&node_4_id,)
-        );
-        assert!(
-            tree.get(&node_4_id,)
-                .unwrap()
-                .children()
-                .contains(&node_5_id,)
+        // root 0 -> [1 -> [3 -> [4, 5]], 2]
+        let mut tree = diff_fixture();
+        let ids: Vec<NodeId> = (0..4).map(NodeId::new).collect();
+        let a_id = tree.insert(Node::new(4), UnderNode(&ids[3])).unwrap();
+        let b_id = tree.insert(Node::new(5), UnderNode(&ids[3])).unwrap();
+
+        // Nodes sharing a deep prefix.
+        assert_eq!(
+            tree.common_path_prefix(&[a_id.clone(), b_id.clone()])
+                .unwrap(),
+            vec![ids[0].clone(), ids[1].clone(), ids[3].clone()]
         );
-        assert!(
-            tree.get(&node_5_id,)
-                .unwrap()
-                .children()
-                .contains(&node_3_id,)
-        );
-
-        // move root "down" the tree
-        tree.move_node(&root_id, ToParent(&node_2_id)).unwrap();
-        assert!(tree.get(&node_2_id).unwrap().children().contains(&root_id));
-        assert!(tree.get(&root_id).unwrap().children().contains(&node_1_id));
-        assert!(
-            tree.get(&node_1_id,)
-                .unwrap()
-                .children()
-                .contains(&node_4_id,)
+
+        // Nodes sharing only the root.
+        assert_eq!(
+            tree.common_path_prefix(&[a_id.clone(), ids[2].clone(), b_id])
+                .unwrap(),
+            vec![ids[0].clone()]
         );
-        assert!(
-            tree.get(&node_4_id,)
-                .unwrap()
-                .children()
-                .contains(&node_5_id,)
+
+        // A single node gets its full path.
+        assert_eq!(
+            tree.common_path_prefix(std::slice::from_ref(&a_id))
+                .unwrap(),
+            vec![ids[0].clone(), ids[1].clone(), ids[3].clone(), a_id]
         );
+
+        assert!(tree.common_path_prefix(&[]).unwrap().is_empty());
+
+        tree.remove_node(ids[2].clone(), RemoveBehavior::DropChildren)
+            .unwrap();
         assert!(
-            tree.get(&node_5_id,)
-                .unwrap()
-                .children()
-                .contains(&node_3_id,)
+            tree.common_path_prefix(&[ids[1].clone(), ids[2].clone()])
+                .is_err()
         );
-        assert_eq!(tree.root_node_id(), Some(&node_2_id));
     }
 
     #[test]
-    fn test_move_node_to_root() {
+    fn test_position_in_parent() {
         use InsertBehavior::*;
 
-        // test move with existing root
-        {
-            let mut tree = Tree::new();
-            let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
-            let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
-            let node_2_id = tree.insert(Node::new(2), UnderNode(&node_1_id)).unwrap();
+        let mut tree = diff_fixture();
+        let root_id = tree.root_node_id().cloned().unwrap();
+        let node_4_id = tree.insert(Node::new(4), UnderNode(&root_id)).unwrap();
 
-            tree.move_node_to_root(&node_2_id);
+        assert_eq!(tree.position_in_parent(&NodeId::new(1)), Ok(Some((0, 3))));
+        assert_eq!(tree.position_in_parent(&NodeId::new(2)), Ok(Some((1, 3))));
+        assert_eq!(tree.position_in_parent(&node_4_id), Ok(Some((2, 3))));
 
-            assert_eq!(tree.root_node_id(), Some(&node_2_id));
-            assert!(tree.get(&node_2_id).unwrap().children().contains(&root_id));
-            assert!(
-                !tree
-                    .get(&node_1_id,)
-                    .unwrap()
-                    .children()
-                    .contains(&node_2_id,)
-            );
-        }
+        // An only child.
+        assert_eq!(tree.position_in_parent(&NodeId::new(3)), Ok(Some((0, 1))));
 
-        // Test move with existing root and with orphan.
-        {
-            let mut tree = Tree::new();
-            let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
-            let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
-            let node_2_id = tree.insert(Node::new(2), UnderNode(&node_1_id)).unwrap();
+        assert_eq!(tree.position_in_parent(&root_id), Ok(None));
 
-            tree.remove_node_orphan_children(node_1_id);
-            tree.move_node_to_root(&node_2_id);
+        tree.remove_node(node_4_id.clone(), RemoveBehavior::DropChildren)
+            .unwrap();
+        assert_eq!(
+            tree.position_in_parent(&node_4_id),
+            Err(NodeIdError::NodeIdNoLongerValid)
+        );
+    }
 
-            assert_eq!(tree.root_node_id(), Some(&node_2_id));
-            assert!(tree.get(&node_2_id).unwrap().children().contains(&root_id));
-            assert_eq!(tree.get(&root_id).unwrap().children().len(), 0);
-        }
+    #[test]
+    fn test_pre_order_traversal_clone_mid_way() {
+        use InsertBehavior::*;
 
-        // Test move without root and with orphan.
-        {
-            let mut tree = Tree::new();
-            let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
-            let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
-            let node_2_id = tree.insert(Node::new(2), UnderNode(&node_1_id)).unwrap();
+        let mut tree = diff_fixture();
+        tree.insert(Node::new(4), UnderNode(&NodeId::new(3)))
+            .unwrap();
 
-            tree.remove_node_orphan_children(root_id);
-            tree.move_node_to_root(&node_1_id);
+        let mut nodes = tree.traverse_pre_order_ids(&NodeId::new(0)).unwrap();
+        assert_eq!(nodes.next(), Some(NodeId::new(0)));
+        assert_eq!(nodes.next(), Some(NodeId::new(1)));
 
-            assert_eq!(tree.root_node_id(), Some(&node_1_id));
-            assert!(
-                tree.get(&node_1_id,)
-                    .unwrap()
-                    .children()
-                    .contains(&node_2_id,)
+        let rest = nodes.clone();
+        let data = |ids: crate::PreOrderTraversalIds<'_, i32>| -> Vec<i32> {
+            ids.map(|id| *tree.get(&id).unwrap().data()).collect()
+        };
+        assert_eq!(data(nodes), [3, 4, 2]);
+        assert_eq!(data(rest), [3, 4, 2]);
+
+        // Starting below the root only walks that subtree.
+        let subtree: Vec<i32> = tree
+            .traverse_pre_order(&NodeId::new(1))
+            .unwrap()
+            .map(|node| *node.data())
+            .collect();
+        assert_eq!(subtree, [1, 3, 4]);
+    }
+
+    #[test]
+    fn test_clone_subtree_into() {
+        use InsertBehavior::*;
+
+        let mut a = diff_fixture();
+        a.insert(Node::new(4), UnderNode(&NodeId::new(1))).unwrap();
+
+        let mut b = Tree::new();
+        let b_root = b.insert(Node::new(10), AsRoot).unwrap();
+        b.insert(Node::new(11), UnderNode(&b_root)).unwrap();
+
+        let copy = a
+            .clone_subtree_into(&NodeId::new(1), &mut b, &b_root)
+            .unwrap();
+
+        let mut formatted = String::new();
+        b.write_formatted(&mut formatted).unwrap();
+        assert_eq!(formatted, "10\n├── 11\n└── 1\n    ├── 3\n    └── 4\n");
+        assert_eq!(b.parent(&copy), Ok(Some(b_root.clone())));
+
+        // The source is untouched.
+        assert_eq!(a.len(), 5);
+        assert_eq!(a.children_ids(&NodeId::new(1)).unwrap().count(), 2);
+
+        b.remove_node(copy.clone(), RemoveBehavior::DropChildren)
+            .unwrap();
+        assert_eq!(
+            a.clone_subtree_into(&NodeId::new(1), &mut b, &copy),
+            Err(NodeIdError::NodeIdNoLongerValid)
+        );
+    }
+
+    #[test]
+    fn test_out_of_bounds_node_id() {
+        use InsertBehavior::*;
+
+        let mut tree = diff_fixture();
+        // Right past the end, and far past it.
+        for id in [NodeId::new(4), NodeId::new(1_000)] {
+            assert_eq!(tree.get(&id).err(), Some(NodeIdError::OutOfBounds));
+            assert_eq!(tree.get_mut(&id).err(), Some(NodeIdError::OutOfBounds));
+            assert!(!tree.contains(&id));
+            assert_eq!(tree.children(&id).err(), Some(NodeIdError::OutOfBounds));
+            assert_eq!(tree.ancestors(&id).err(), Some(NodeIdError::OutOfBounds));
+            assert_eq!(
+                tree.insert(Node::new(5), UnderNode(&id)),
+                Err(NodeIdError::OutOfBounds)
+            );
+            assert_eq!(
+                tree.remove_node(id, RemoveBehavior::DropChildren).err(),
+                Some(NodeIdError::OutOfBounds)
             );
-            assert_eq!(tree.get(&node_1_id).unwrap().children().len(), 1);
         }
+
+        assert_eq!(tree.len(), 4);
+        assert_eq!(
+            NodeIdError::OutOfBounds.to_string(),
+            "NodeIdError: The given NodeId is out of bounds of the Tree."
+        );
     }
 
     #[test]
-    fn test_find_subtree_root_below_upper_id() {
+    fn test_merge_from_conflict() {
+        let mut tree = diff_fixture();
+        let mut other = diff_fixture();
+        *other.get_mut(&NodeId::new(1)).unwrap().data_mut() = 20;
+        *other.get_mut(&NodeId::new(2)).unwrap().data_mut() = 20;
+
+        let mut conflicts = vec![];
+        tree.merge_from(other, |ours, theirs| {
+            conflicts.push((*ours, theirs));
+            *ours = (*ours).max(theirs);
+        });
+
+        // Called once per shared node, in pre-order.
+        assert_eq!(conflicts, [(0, 0), (1, 20), (3, 3), (2, 20)]);
+        assert_eq!(
+            tree.collect_subtree_data(&NodeId::new(0)).unwrap(),
+            [0, 20, 3, 20]
+        );
+        assert_eq!(tree.len(), 4);
+    }
+
+    #[test]
+    fn test_merge_from_added_nodes() {
         use InsertBehavior::*;
 
+        let mut tree = diff_fixture();
+        // A free slot in `tree` that `other` uses.
+        let node_4_id = tree
+            .insert(Node::new(4), UnderNode(&NodeId::new(2)))
+            .unwrap();
+        tree.remove_node(node_4_id.clone(), RemoveBehavior::DropChildren)
+            .unwrap();
+
+        let mut other = diff_fixture();
+        let node_5_id = other
+            .insert(Node::new(5), UnderNode(&NodeId::new(3)))
+            .unwrap();
+        let node_6_id = other.insert(Node::new(6), UnderNode(&node_5_id)).unwrap();
+        other
+            .insert(Node::new(7), UnderNode(&NodeId::new(0)))
+            .unwrap();
+        assert_eq!(node_5_id, node_4_id);
+
+        let mut calls = 0;
+        tree.merge_from(other, |_, _| calls += 1);
+        assert_eq!(calls, 4);
+
+        let mut formatted = String::new();
+        tree.write_formatted(&mut formatted).unwrap();
+        assert_eq!(
+            formatted,
+            "0\n├── 1\n│   └── 3\n│       └── 5\n│           └── 6\n├── 2\n└── 7\n"
+        );
+        assert_eq!(tree.parent(&node_6_id), Ok(Some(node_5_id)));
+        assert_eq!(tree.free_count(), 0);
+
+        // New nodes still get fresh ids.
+        let node_8_id = tree
+            .insert(Node::new(8), UnderNode(&NodeId::new(0)))
+            .unwrap();
+        assert_eq!(tree.get(&node_8_id).unwrap().data(), &8);
+        assert_eq!(tree.len(), 8);
+    }
+
+    #[test]
+    fn test_merge_from_into_empty() {
         let mut tree = Tree::new();
+        tree.merge_from(diff_fixture(), |_, _| {
+            unreachable!("nothing to conflict with")
+        });
 
-        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
-        let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
-        let node_2_id = tree.insert(Node::new(2), UnderNode(&node_1_id)).unwrap();
-        let node_3_id = tree.insert(Node::new(3), UnderNode(&node_1_id)).unwrap();
-        let node_4_id = tree.insert(Node::new(4), UnderNode(&node_2_id)).unwrap();
+        assert!(tree.structurally_eq(&diff_fixture()));
+        assert_eq!(tree.root_node_id(), Some(&NodeId::new(0)));
+    }
 
-        let sub_root = tree.find_subtree_root_between_ids(&node_1_id, &root_id);
-        assert_eq!(sub_root, Some(&node_1_id));
-        let sub_root = tree.find_subtree_root_between_ids(&root_id, &node_1_id); //invert for None
-        assert_eq!(sub_root, None);
+    #[test]
+    fn test_insert_between() {
+        use InsertBehavior::*;
 
-        let sub_root = tree.find_subtree_root_between_ids(&node_2_id, &root_id);
-        assert_eq!(sub_root, Some(&node_1_id));
-        let sub_root = tree.find_subtree_root_between_ids(&root_id, &node_2_id); //invert for None
-        assert_eq!(sub_root, None);
+        let mut tree = diff_fixture();
+        let node_4_id = tree
+            .insert(Node::new(4), UnderNode(&NodeId::new(0)))
+            .unwrap();
+
+        let group_id = tree
+            .insert_between(&NodeId::new(0), &NodeId::new(2), Node::new(10))
+            .unwrap();
+
+        // Spliced in where 2 was, between 1 and 4.
+        let mut formatted = String::new();
+        tree.write_formatted(&mut formatted).unwrap();
+        assert_eq!(formatted, "0\n├── 1\n│   └── 3\n├── 10\n│   └── 2\n└── 4\n");
+        assert_eq!(tree.parent(&NodeId::new(2)), Ok(Some(group_id.clone())));
+        assert_eq!(tree.position_in_parent(&group_id), Ok(Some((1, 3))));
+        assert_eq!(tree.height(), 3);
+
+        // Wrapping a node with children keeps its subtree.
+        tree.insert_between(&NodeId::new(0), &NodeId::new(1), Node::new(11))
+            .unwrap();
+        assert_eq!(
+            tree.get_path_data(&NodeId::new(3)).unwrap(),
+            [&0, &11, &1, &3]
+        );
 
-        let sub_root = tree.find_subtree_root_between_ids(&node_3_id, &node_1_id);
-        assert_eq!(sub_root, Some(&node_3_id));
-        let sub_root = tree.find_subtree_root_between_ids(&node_1_id, &node_3_id); //invert for None
-        assert_eq!(sub_root, None);
+        // 2 is a grandchild of 0 now.
+        let before = tree.len();
+        assert_eq!(
+            tree.insert_between(&NodeId::new(0), &NodeId::new(2), Node::new(12)),
+            Err(NodeIdError::NotAChild)
+        );
+        assert_eq!(
+            tree.insert_between(&node_4_id, &NodeId::new(0), Node::new(12)),
+            Err(NodeIdError::NotAChild)
+        );
+        assert_eq!(tree.len(), before);
+    }
 
-        let sub_root = tree.find_subtree_root_between_ids(&node_4_id, &root_id);
-        assert_eq!(sub_root, Some(&node_1_id));
-        let sub_root = tree.find_subtree_root_between_ids(&root_id, &node_4_id); //invert for None
-        assert_eq!(sub_root, None);
+    #[test]
+    fn test_node_ids_includes_orphans() {
+        let mut tree = diff_fixture();
+        let root_id = tree.root_node_id().cloned().unwrap();
+        tree.remove_node(NodeId::new(1), RemoveBehavior::OrphanChildren)
+            .unwrap();
+
+        let ids: Vec<NodeId> = tree.node_ids().collect();
+        assert_eq!(ids, vec![NodeId::new(0), NodeId::new(2), NodeId::new(3)]);
+
+        let reachable: Vec<NodeId> = tree.traverse_pre_order_ids(&root_id).unwrap().collect();
+        assert_eq!(reachable, vec![NodeId::new(0), NodeId::new(2)]);
     }
 
     #[test]
-    fn test_tree_height() {
+    fn test_removed_id_errors() {
         use InsertBehavior::*;
-        use RemoveBehavior::*;
 
-        // Empty tree.
-        let mut tree = Tree::new();
-        assert_eq!(0, tree.height());
+        let mut tree = diff_fixture();
+        let root_id = NodeId::new(0);
+        let removed = NodeId::new(2);
+        tree.remove_node(removed.clone(), RemoveBehavior::DropChildren)
+            .unwrap();
+
+        let no_longer_valid = Err(NodeIdError::NodeIdNoLongerValid);
+        assert_eq!(
+            tree.remove_node(removed.clone(), RemoveBehavior::DropChildren)
+                .map(|_| ()),
+            no_longer_valid
+        );
+        assert_eq!(tree.children(&removed).map(|_| ()), no_longer_valid);
+        assert_eq!(tree.children_ids(&removed).map(|_| ()), no_longer_valid);
+        assert_eq!(
+            tree.move_node(&removed, MoveBehavior::ToParent(&root_id)),
+            no_longer_valid
+        );
+        assert_eq!(
+            tree.move_node(&NodeId::new(3), MoveBehavior::ToParent(&removed)),
+            no_longer_valid
+        );
+        assert_eq!(
+            tree.sort_children_by(&removed, |a, b| a.data().cmp(b.data())),
+            no_longer_valid
+        );
 
-        // The tree with single root node.
-        let root_id = tree.insert(Node::new(1), AsRoot).unwrap();
-        assert_eq!(1, tree.height());
+        // Inserting under it doesn't make the new node its own parent.
+        assert_eq!(
+            tree.insert(Node::new(4), UnderNode(&removed)),
+            Err(NodeIdError::NodeIdNoLongerValid)
+        );
+        assert_eq!(tree.len(), 3);
+    }
 
-        // Root node with single child.
-        let child_1_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
-        assert_eq!(2, tree.height());
+    #[test]
+    fn test_take_data() {
+        let mut tree = diff_fixture();
 
-        // Root node with two children.
-        let child_2_id = tree.insert(Node::new(3), UnderNode(&root_id)).unwrap();
-        assert_eq!(2, tree.height());
+        assert_eq!(
+            tree.take_data(NodeId::new(1), RemoveBehavior::LiftChildren),
+            Ok(1)
+        );
+        assert_eq!(tree.children_ids(&NodeId::new(0)).unwrap().count(), 2);
+        assert_eq!(tree.parent(&NodeId::new(3)).unwrap(), Some(NodeId::new(0)));
 
-        // Grandson.
-        tree.insert(Node::new(4), UnderNode(&child_1_id)).unwrap();
-        assert_eq!(3, tree.height());
+        assert_eq!(
+            tree.take_data(NodeId::new(99), RemoveBehavior::DropChildren),
+            Err(NodeIdError::OutOfBounds)
+        );
+        // Taking it again finds the slot empty.
+        assert_eq!(
+            tree.take_data(NodeId::new(1), RemoveBehavior::DropChildren),
+            Err(NodeIdError::NodeIdNoLongerValid)
+        );
+    }
 
-        // Remove child_1 and grandchild.
-        tree.remove_node(child_1_id, DropChildren).unwrap();
-        assert_eq!(2, tree.height());
+    #[test]
+    fn test_ancestors_cycle_terminates() {
+        let mut tree = diff_fixture();
+
+        let mut ancestors = tree.ancestor_ids(&NodeId::new(3)).unwrap();
+        assert_eq!(ancestors.by_ref().count(), 2);
+        assert!(!ancestors.hit_cycle());
+
+        // Corrupt the tree by making the root a child of its grandchild.
+        tree.get_mut(&NodeId::new(0))
+            .unwrap()
+            .set_parent(Some(NodeId::new(3)));
+
+        let mut ids = tree.ancestor_ids(&NodeId::new(3)).unwrap();
+        let visited: Vec<NodeId> = ids.by_ref().cloned().collect();
+        assert!(visited.len() <= tree.len());
+        assert!(ids.hit_cycle());
+
+        let mut nodes = tree.ancestors(&NodeId::new(3)).unwrap();
+        assert!(nodes.by_ref().count() <= tree.len());
+        assert!(nodes.hit_cycle());
+    }
 
-        // Remove child_2.
-        tree.remove_node(child_2_id, LiftChildren).unwrap();
-        assert_eq!(1, tree.height());
+    #[test]
+    fn test_eq_by_ignores_due_dates() {
+        use InsertBehavior::*;
+
+        // Names with due dates, as days.
+        let build = |dues: [Option<u32>; 3]| {
+            let mut tree = Tree::new();
+            let root_id = tree.insert(Node::new(("root", dues[0])), AsRoot).unwrap();
+            let work_id = tree
+                .insert(Node::new(("work", dues[1])), UnderNode(&root_id))
+                .unwrap();
+            tree.insert(Node::new(("report", dues[2])), UnderNode(&work_id))
+                .unwrap();
+            tree
+        };
+        let by_name = |a: &(&str, Option<u32>), b: &(&str, Option<u32>)| a.0 == b.0;
+
+        let a = build([None, Some(1), Some(2)]);
+        let b = build([None, None, Some(30)]);
+        assert!(!a.structurally_eq(&b));
+        assert!(a.eq_by(&b, by_name));
+
+        // The structure still has to match.
+        let mut c = build([None, Some(1), Some(2)]);
+        let root_id = c.root_node_id().cloned().unwrap();
+        c.insert(Node::new(("extra", None)), UnderNode(&root_id))
+            .unwrap();
+        assert!(!a.eq_by(&c, by_name));
     }
 
     #[test]
-    fn test_partial_eq() {
+    fn test_snapshot_restore() {
+        use InsertBehavior::*;
+
+        let mut tree = diff_fixture();
+        let before = tree.clone();
+        let root_id = tree.root_node_id().unwrap().clone();
+
+        let snapshot = tree.snapshot();
+        let child_id = tree.children_ids(&root_id).unwrap().next().unwrap().clone();
+        tree.remove_node(child_id, RemoveBehavior::DropChildren)
+            .unwrap();
+        tree.insert(Node::new(4), UnderNode(&root_id)).unwrap();
+        assert_ne!(tree, before);
+
+        tree.restore(snapshot);
+        assert_eq!(tree, before);
+        assert_eq!(tree.len(), 4);
+    }
+
+    #[test]
+    fn test_children_data_removed_id() {
+        let mut tree = diff_fixture();
+        let root_id = tree.root_node_id().cloned().unwrap();
+        let node_1_id = tree.get(&root_id).unwrap().children()[0].clone();
+
+        assert_eq!(tree.children_data(&node_1_id).unwrap().count(), 1);
+
+        tree.remove_node(node_1_id.clone(), RemoveBehavior::DropChildren)
+            .unwrap();
+        assert!(matches!(
+            tree.children_data(&node_1_id),
+            Err(NodeIdError::NodeIdNoLongerValid)
+        ));
+    }
+
+    #[test]
+    fn test_parent_cycle_errors() {
+        let mut tree = diff_fixture();
+        let node_3_id = NodeId::new(3);
+        let mut visited = 0;
+
+        tree.for_each_ancestor(&node_3_id, |_| visited += 1)
+            .unwrap();
+        assert_eq!(visited, 2);
+
+        // Corrupt the tree by making the root a child of its grandchild.
+        tree.get_mut(&NodeId::new(0))
+            .unwrap()
+            .set_parent(Some(node_3_id.clone()));
+
+        let mut visited = 0;
+        assert_eq!(
+            tree.for_each_ancestor(&node_3_id, |_| visited += 1),
+            Err(NodeIdError::ParentCycle)
+        );
+        assert!(visited <= tree.len());
+        assert_eq!(
+            tree.find_subtree_root_between_ids(&node_3_id, &NodeId::new(2)),
+            Err(NodeIdError::ParentCycle)
+        );
+    }
+
+    #[test]
+    fn test_validate_free_ids() {
+        let mut tree = diff_fixture();
+        tree.remove_node(NodeId::new(2), RemoveBehavior::DropChildren)
+            .unwrap();
+        assert_eq!(tree.validate(), Ok(()));
+
+        for free_ids in [
+            vec![NodeId::new(9)],
+            vec![NodeId::new(2), NodeId::new(2)],
+            vec![NodeId::new(1)],
+        ] {
+            let mut corrupt = tree.clone();
+            corrupt.free_ids = free_ids;
+            assert_eq!(corrupt.validate(), Err(NodeIdError::InvalidFreeId));
+        }
+    }
+
+    #[test]
+    fn test_validate_child_points_back() {
+        // A child whose parent is another node.
+        let mut tree = diff_fixture();
+        tree.get_mut(&NodeId::new(3))
+            .unwrap()
+            .set_parent(Some(NodeId::new(2)));
+        assert_eq!(tree.validate(), Err(NodeIdError::NotAChild));
+
+        // A child listed twice by the same node.
+        let mut tree = diff_fixture();
+        tree.get_mut(&NodeId::new(1))
+            .unwrap()
+            .add_child(NodeId::new(3));
+        assert_eq!(tree.validate(), Err(NodeIdError::NotAChild));
+    }
+
+    #[test]
+    fn test_validate_parent_cycle() {
+        // 1 and 3 list each other as child and parent, away from the root.
+        let mut tree = diff_fixture();
+        tree.get_mut(&NodeId::new(0))
+            .unwrap()
+            .set_children(vec![NodeId::new(2)]);
+        tree.get_mut(&NodeId::new(1))
+            .unwrap()
+            .set_parent(Some(NodeId::new(3)));
+        tree.get_mut(&NodeId::new(3))
+            .unwrap()
+            .add_child(NodeId::new(1));
+
+        assert_eq!(tree.validate(), Err(NodeIdError::ParentCycle));
+    }
+
+    #[test]
+    fn test_validate_unreachable() {
+        // 2 still thinks it is a child of the root, which forgot about it.
+        let mut tree = diff_fixture();
+        tree.get_mut(&NodeId::new(0))
+            .unwrap()
+            .set_children(vec![NodeId::new(1)]);
+        assert_eq!(tree.validate(), Err(NodeIdError::Unreachable));
+
+        // Nodes without a root.
+        let mut tree = diff_fixture();
+        tree.root = None;
+        assert_eq!(tree.validate(), Err(NodeIdError::Unreachable));
+    }
+
+    #[test]
+    fn test_stale_parent_errors() {
+        let mut tree = diff_fixture();
+        let node_3_id = NodeId::new(3);
+
+        // Corrupt the tree by pointing at a parent that was removed.
+        tree.remove_node(NodeId::new(2), RemoveBehavior::DropChildren)
+            .unwrap();
+        tree.get_mut(&node_3_id)
+            .unwrap()
+            .set_parent(Some(NodeId::new(2)));
+
+        assert_eq!(
+            tree.for_each_ancestor(&node_3_id, |_| {}),
+            Err(NodeIdError::NodeIdNoLongerValid)
+        );
+        assert_eq!(
+            tree.find_subtree_root_between_ids(&node_3_id, &NodeId::new(0)),
+            Err(NodeIdError::NodeIdNoLongerValid)
+        );
+    }
+
+    #[test]
+    fn test_drain_where_keeps_position() {
         use InsertBehavior::*;
 
+        // root 0 -> [1, 2 -> [3, 4], 5]
         let mut tree = Tree::new();
         let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
-        let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
-        tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
-        tree.insert(Node::new(3), UnderNode(&node_1_id)).unwrap();
+        tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let node_2_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+        tree.insert(Node::new(3), UnderNode(&node_2_id)).unwrap();
+        tree.insert(Node::new(4), UnderNode(&node_2_id)).unwrap();
+        tree.insert(Node::new(5), UnderNode(&root_id)).unwrap();
+
+        assert_eq!(tree.drain_where(|node| *node.data() == 2), vec![2]);
+
+        let children: Vec<i32> = tree
+            .children(&root_id)
+            .unwrap()
+            .map(|n| *n.data())
+            .collect();
+        assert_eq!(children, vec![1, 3, 4, 5]);
+    }
 
-        // Ensure PartialEq doesn't work when the number of used nodes are not equal.
-        {
-            let mut other = Tree::new();
-            let root_id = other.insert(Node::new(0), AsRoot).unwrap();
-            other.insert(Node::new(1), UnderNode(&root_id)).unwrap();
-            other.insert(Node::new(2), UnderNode(&root_id)).unwrap();
-            assert_ne!(tree, other);
-        }
+    #[test]
+    fn test_drain_where_keeps_root() {
+        // root 0 -> [1 -> [3], 2]
+        let mut tree = diff_fixture();
+        let root_id = NodeId::new(0);
 
-        // Ensure PartialEq doesn't work when the data is not equal.
-        {
-            let mut other = Tree::new();
-            let root_id = other.insert(Node::new(0), AsRoot).unwrap();
-            let id = other.insert(Node::new(1), UnderNode(&root_id)).unwrap();
-            other.insert(Node::new(2), UnderNode(&root_id)).unwrap();
-            other.insert(Node::new(4), UnderNode(&id)).unwrap();
-            assert_ne!(tree, other);
-        }
+        let drained = tree.drain_where(|_| true);
 
-        // Ensure PartialEq doesn't work when the parents aren't equal.
-        {
-            let mut other = Tree::new();
-            let root_id = other.insert(Node::new(0), AsRoot).unwrap();
-            other.insert(Node::new(1), UnderNode(&root_id)).unwrap();
-            let id = other.insert(Node::new(2), UnderNode(&root_id)).unwrap();
-            other.insert(Node::new(3), UnderNode(&id)).unwrap();
-            assert_ne!(tree, other);
-        }
+        assert_eq!(drained, vec![1, 3, 2]);
+        assert_eq!(tree.root_node_id(), Some(&root_id));
+        assert_eq!(tree.len(), 1);
+        assert!(tree.get(&root_id).unwrap().children().is_empty());
+    }
 
-        // Ensure PartialEq works even if the number of free spots in Tree.
-        // Node is different.
-        {
-            let mut other = Tree::new();
-            let root_id = other.insert(Node::new(0), AsRoot).unwrap();
-            let id = other.insert(Node::new(1), UnderNode(&root_id)).unwrap();
-            other.insert(Node::new(2), UnderNode(&root_id)).unwrap();
-            other.insert(Node::new(3), UnderNode(&id)).unwrap();
-            let to_delete = other.insert(Node::new(42), UnderNode(&root_id)).unwrap();
-            other.take_node(to_delete);
-            assert_ne!(
-                tree.nodes.iter().filter(|x| x.is_none()).count(),
-                other.nodes.iter().filter(|x| x.is_none()).count()
-            );
-            assert_eq!(tree, other);
-        }
+    #[test]
+    fn test_reconcile_skips_height_cache() {
+        use automerge::{
+            AutoCommit, ObjType, ROOT, ReadDoc as _, ScalarValue, transaction::Transactable as _,
+        };
 
-        // Ensure PartialEq doesn't work when the Node's index are different.
-        {
-            let mut other = Tree::new();
-            let root_id = other.insert(Node::new(0), AsRoot).unwrap();
-            let to_delete = other.insert(Node::new(42), UnderNode(&root_id)).unwrap();
-            let id = other.insert(Node::new(1), UnderNode(&root_id)).unwrap();
-            other.insert(Node::new(2), UnderNode(&root_id)).unwrap();
-            other.insert(Node::new(3), UnderNode(&id)).unwrap();
-            other.take_node(to_delete);
-            assert_ne!(tree, other);
-        }
+        let mut tree = diff_fixture();
+        assert_eq!(tree.cached_height(), 3);
+
+        // A document written while the cache was still stored.
+        let mut doc = AutoCommit::new();
+        let tree_obj = doc.put_object(ROOT, "tree", ObjType::Map).unwrap();
+        doc.put(&tree_obj, "height_cache", ScalarValue::Null)
+            .unwrap();
+
+        autosurgeon::reconcile_prop(&mut doc, ROOT, "tree", &tree).unwrap();
+
+        let keys: Vec<String> = doc.keys(&tree_obj).collect();
+        assert_eq!(keys, vec!["free_ids", "nodes", "root"]);
+
+        let hydrated: Tree<i32> = autosurgeon::hydrate_prop(&doc, ROOT, "tree").unwrap();
+        assert_eq!(hydrated, tree);
     }
 }
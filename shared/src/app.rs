@@ -1,3 +1,5 @@
+use std::collections::{HashSet, VecDeque};
+
 use chrono::{DateTime, Utc, serde::ts_milliseconds_option::deserialize as ts_milliseconds_option};
 use crux_core::{App, Command, render::render};
 use crux_http::command::Http;
@@ -5,15 +7,108 @@ use facet::Facet;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
-use crate::sse::ServerSentEvents;
+use crate::{
+    notify::{Level, notify},
+    sse::ServerSentEvents,
+    types::{CaseNode, CaseRow, CaseTree, DueDateTime, Group, NodeId, Task},
+};
 
 const API_URL: &str = "https://crux-counter.fly.dev";
 
+/// Aborts an SSE subscription, wrapping the handle from
+/// `Command::abort_handle` as crux doesn't export its type.
+type AbortWatch = Box<dyn Fn() + Send + Sync>;
+
+/// How many edits can be undone, older snapshots are dropped.
+pub const UNDO_LIMIT: usize = 100;
+
 // ANCHOR: model
 /// The data model for the application.
 #[derive(Default, Serialize)]
 pub struct Model {
     count: Count,
+    /// The last error reported by the SSE stream, if watching failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    watch_error: Option<String>,
+    /// Why the last edit was rejected, e.g. a due date that didn't parse.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    edit_error: Option<String>,
+    /// Aborts the running SSE subscription, used to stop watching.
+    #[serde(skip)]
+    watch: Option<AbortWatch>,
+    /// The groups and tasks of the user.
+    tree: CaseTree,
+    /// Pinned nodes of the `tree`, in the order they were pinned.
+    bookmarks: Vec<NodeId>,
+    /// Groups of the `tree` whose contents are hidden in the view.
+    collapsed: HashSet<NodeId>,
+    /// Snapshots of the `tree` to undo and redo edits.
+    #[serde(skip)]
+    history: History,
+    /// What the shell is showing.
+    #[serde(skip)]
+    mode: Mode,
+    /// The node the user is on in `Mode::Explorer`.
+    #[serde(skip)]
+    selected: Option<NodeId>,
+    /// What the user is searching for, empty when not searching.
+    #[serde(skip)]
+    search: String,
+    /// The nodes matching `search`, kept up to date with the `tree`.
+    #[serde(skip)]
+    search_results: Vec<NodeId>,
+}
+
+/// The screens of the application.
+#[derive(Facet, Serialize, Deserialize, Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[repr(C)]
+pub enum Mode {
+    /// The counter.
+    #[default]
+    Home,
+    /// The tree of groups and tasks.
+    Explorer,
+}
+
+/// Bounded undo and redo stacks of `CaseTree` snapshots.
+#[derive(Default)]
+struct History {
+    undo: VecDeque<CaseTree>,
+    redo: Vec<CaseTree>,
+}
+
+impl History {
+    /// Remembers `tree` as it was before an edit, which also forgets
+    /// anything that could have been redone.
+    fn record(&mut self, tree: &CaseTree) {
+        if self.undo.len() == UNDO_LIMIT {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(tree.clone());
+        self.redo.clear();
+    }
+
+    /// Restores the snapshot before the last edit into `tree`.
+    ///
+    /// Returns whether there was anything to undo.
+    fn undo(&mut self, tree: &mut CaseTree) -> bool {
+        let Some(snapshot) = self.undo.pop_back() else {
+            return false;
+        };
+        self.redo.push(std::mem::replace(tree, snapshot));
+        true
+    }
+
+    /// Restores the snapshot of the last undone edit into `tree`.
+    ///
+    /// Returns whether there was anything to redo.
+    fn redo(&mut self, tree: &mut CaseTree) -> bool {
+        let Some(snapshot) = self.redo.pop() else {
+            return false;
+        };
+        self.undo.push_back(std::mem::replace(tree, snapshot));
+        true
+    }
 }
 
 /// Example
@@ -33,6 +128,32 @@ pub struct ViewModel {
     pub text: String,
     /// Whether the text is confirmed server-side or not.
     pub confirmed: bool,
+    /// A snapshot of the `CaseTree`, only available to rust shells.
+    #[serde(skip)]
+    #[facet(opaque)]
+    pub tree: CaseTree,
+    /// The names of the bookmarked nodes, for a sidebar.
+    pub bookmarks: Vec<String>,
+    /// The visible rows of the tree, indented by depth, with groups marked
+    /// as expanded (▾) or collapsed (▸).
+    pub rows: Vec<String>,
+    /// The `NodeId` of each of `rows`, only available to rust shells.
+    #[serde(skip)]
+    #[facet(opaque)]
+    pub row_ids: Vec<NodeId>,
+    /// Why the last edit was rejected, to show to the user.
+    pub error: Option<String>,
+    /// What to show.
+    pub mode: Mode,
+    /// The index of the selected node in `rows`, if it is visible.
+    pub selected: Option<usize>,
+    /// The current search, empty when not searching.
+    pub search: String,
+    /// The nodes whose name matches `search`, in pre-order, only available
+    /// to rust shells.
+    #[serde(skip)]
+    #[facet(opaque)]
+    pub search_results: Vec<NodeId>,
 }
 
 #[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -47,6 +168,81 @@ pub enum Event {
     Decrement,
     /// To be honest, I don't know what this is about.
     StartWatch,
+    /// Stop watching the server for updates.
+    StopWatch,
+    /// Create a new `Task` with the given name under the root group.
+    CreateTask(String),
+    /// Bookmark the node with the given `NodeId`, or remove its bookmark.
+    ///
+    /// Only available to rust shells, like the `CaseTree` in the `ViewModel`.
+    #[serde(skip)]
+    #[facet(skip)]
+    ToggleBookmark(#[facet(opaque)] NodeId),
+    /// Collapse the given group, hiding its contents, or expand it again.
+    ///
+    /// Only available to rust shells, like the `CaseTree` in the `ViewModel`.
+    #[serde(skip)]
+    #[facet(skip)]
+    ToggleCollapse(#[facet(opaque)] NodeId),
+    /// Raise the `Priority` of the given node by `delta` steps, or lower it
+    /// for a negative `delta`, stopping at the highest or lowest one.
+    ///
+    /// Only available to rust shells, like the `CaseTree` in the `ViewModel`.
+    #[serde(skip)]
+    #[facet(skip)]
+    ChangePriority {
+        /// The task or group to change.
+        #[facet(opaque)]
+        node: NodeId,
+        /// How many steps to go up, or down if negative.
+        delta: i8,
+    },
+    /// Replace the description of the given task, groups are left alone.
+    ///
+    /// Only available to rust shells, like the `CaseTree` in the `ViewModel`.
+    #[serde(skip)]
+    #[facet(skip)]
+    SetDescription {
+        /// The task to change.
+        #[facet(opaque)]
+        node: NodeId,
+        /// The new description.
+        description: String,
+    },
+    /// Set the due date of the given task from user input, see
+    /// `DueDateTime::parse` for the accepted formats. Groups are left alone.
+    ///
+    /// Input that doesn't parse is reported in the `ViewModel` instead.
+    ///
+    /// Only available to rust shells, like the `CaseTree` in the `ViewModel`.
+    #[serde(skip)]
+    #[facet(skip)]
+    SetDueDate {
+        /// The task to change.
+        #[facet(opaque)]
+        node: NodeId,
+        /// The due date as typed, or `No Due Date` to clear it.
+        input: String,
+    },
+    /// Show another screen, see `Mode`.
+    SwitchMode(Mode),
+    /// Search the names of tasks and groups, ignoring case. An empty query
+    /// ends the search.
+    Search(String),
+    /// Revert the last edit of the `CaseTree`.
+    Undo,
+    /// Reapply the last edit reverted by `Undo`.
+    Redo,
+    /// Replace the `CaseTree` with a saved one, see
+    /// `CaseTree::import_preserving_ids` for the accepted JSON.
+    ///
+    /// JSON that doesn't import is reported in the `ViewModel` instead, and
+    /// the current tree is kept.
+    ///
+    /// Only available to rust shells, like the `CaseTree` in the `ViewModel`.
+    #[serde(skip)]
+    #[facet(skip)]
+    LoadTree(String),
 
     // Events local to the core.
     /// Set the thing?
@@ -58,13 +254,19 @@ pub enum Event {
     #[serde(skip)]
     #[facet(skip)]
     Update(#[facet(opaque)] Count),
+
+    /// The SSE stream ended with a transport error or a message of an
+    /// unsupported version.
+    #[serde(skip)]
+    #[facet(skip)]
+    WatchFailed(String),
 }
 
 // Have to do this so the method generated by `facet_typegen` don't cause
 // the `missing_docs` lint.
 #[allow(missing_docs)]
 mod inner {
-    use crate::sse::SseRequest;
+    use crate::{notify::NotifyOperation, sse::SseRequest};
     use crux_core::{macros::effect, render::RenderOperation};
     use crux_http::HttpRequest;
     /// The effects that get sent out of the core, which the application
@@ -78,6 +280,8 @@ mod inner {
         Http(HttpRequest),
         /// Server sent event thingy, not too important.
         ServerSentEvents(SseRequest),
+        /// Show the user a transient message.
+        Notify(NotifyOperation),
     }
 }
 
@@ -93,8 +297,10 @@ impl App for Counter {
     type ViewModel = ViewModel;
     type Effect = Effect;
 
+    // One arm per event, splitting them up wouldn't make them any easier to follow.
+    #[allow(clippy::too_many_lines)]
     fn update(&self, msg: Event, model: &mut Model) -> Command<Effect, Event> {
-        match msg {
+        let command = match msg {
             Event::Get => Http::get(API_URL)
                 .expect_json()
                 .build()
@@ -141,11 +347,194 @@ impl App for Counter {
                 render().and(call_api)
             }
             Event::StartWatch => {
+                // A new subscription, the last one's failure is history.
+                model.watch_error = None;
+
                 let base = Url::parse(API_URL).unwrap();
                 let url = base.join("/sse").unwrap();
-                ServerSentEvents::get(url).then_send(Event::Update)
+                let cmd = ServerSentEvents::get(url).then_send(|message| match message {
+                    Ok(count) => Event::Update(count),
+                    Err(e) => Event::WatchFailed(e),
+                });
+
+                // Only ever keep one subscription alive.
+                let handle = cmd.abort_handle();
+                if let Some(abort) = model.watch.replace(Box::new(move || handle.abort())) {
+                    abort();
+                }
+
+                cmd
             }
-        }
+            Event::StopWatch => {
+                if let Some(abort) = model.watch.take() {
+                    abort();
+                }
+
+                Command::done()
+            }
+            Event::WatchFailed(e) => {
+                let message = format!("Sync failed: {e}");
+                model.watch = None;
+                model.watch_error = Some(e);
+
+                render().and(notify(Level::Error, message))
+            }
+            Event::CreateTask(name) => {
+                model.history.record(&model.tree);
+
+                let root_id = model
+                    .tree
+                    .root_or_insert_with(|| CaseNode::Group(Group::new("root")));
+
+                model
+                    .tree
+                    .insert(CaseNode::Task(Task::new(name)), &root_id)
+                    .expect("root id is valid");
+
+                render().and(notify(Level::Info, "Task created"))
+            }
+            Event::ToggleBookmark(id) => {
+                if let Some(index) = model.bookmarks.iter().position(|bookmark| *bookmark == id) {
+                    model.bookmarks.remove(index);
+                } else if model.tree.contains(&id) {
+                    model.bookmarks.push(id);
+                }
+
+                render()
+            }
+            Event::ToggleCollapse(id) => {
+                if model.collapsed.remove(&id) {
+                    render()
+                } else if let Ok(CaseNode::Group(_)) = model.tree.get(&id) {
+                    model.collapsed.insert(id);
+                    render()
+                } else {
+                    Command::done()
+                }
+            }
+            Event::ChangePriority { node, delta } => {
+                let changed = model.tree.get(&node).ok().and_then(|case_node| {
+                    let mut priority = case_node.priority().clone();
+                    for _ in 0..delta.unsigned_abs() {
+                        priority = if delta > 0 {
+                            priority.next_higher()
+                        } else {
+                            priority.next_lower()
+                        };
+                    }
+
+                    // Already at the highest or lowest priority, nothing to undo.
+                    (*case_node.priority() != priority).then_some(priority)
+                });
+
+                if let Some(priority) = changed {
+                    model.history.record(&model.tree);
+
+                    let case_node = model.tree.get_mut(&node).expect("node id is valid");
+                    case_node.set_priority(priority);
+
+                    render()
+                } else {
+                    Command::done()
+                }
+            }
+            Event::SetDescription { node, description } => {
+                if let Ok(CaseNode::Task(task)) = model.tree.get(&node)
+                    && task.description() != description
+                {
+                    model.history.record(&model.tree);
+
+                    if let Ok(CaseNode::Task(task)) = model.tree.get_mut(&node) {
+                        task.set_description(description);
+                    }
+
+                    render()
+                } else {
+                    Command::done()
+                }
+            }
+            Event::SetDueDate { node, input } => match DueDateTime::parse(&input) {
+                Ok(due) => {
+                    model.edit_error = None;
+
+                    if let Ok(CaseNode::Task(task)) = model.tree.get(&node)
+                        && *task.due() != due
+                    {
+                        model.history.record(&model.tree);
+
+                        if let Ok(CaseNode::Task(task)) = model.tree.get_mut(&node) {
+                            task.set_due(due);
+                        }
+                    }
+
+                    render()
+                }
+                Err(e) => {
+                    model.edit_error = Some(e.to_string());
+                    render()
+                }
+            },
+            Event::SwitchMode(mode) => {
+                if mode == model.mode {
+                    Command::done()
+                } else {
+                    if mode == Mode::Explorer {
+                        enter_explorer(model);
+                    }
+                    model.mode = mode;
+
+                    render()
+                }
+            }
+            Event::Search(query) => {
+                model.search = query;
+
+                render()
+            }
+            Event::Undo => {
+                if model.history.undo(&mut model.tree) {
+                    render()
+                } else {
+                    Command::done()
+                }
+            }
+            Event::Redo => {
+                if model.history.redo(&mut model.tree) {
+                    render()
+                } else {
+                    Command::done()
+                }
+            }
+            Event::LoadTree(json) => {
+                match CaseTree::import(&json) {
+                    Ok((tree, ids_kept)) => {
+                        model.edit_error = None;
+                        model.tree = tree;
+                        // The snapshots belong to the tree that was replaced.
+                        model.history = History::default();
+
+                        // Fresh ids may reuse the old ones for unrelated nodes.
+                        if !ids_kept {
+                            model.bookmarks.clear();
+                            model.collapsed.clear();
+                            model.selected = None;
+                        }
+                    }
+                    Err(e) => model.edit_error = Some(e.to_string()),
+                }
+
+                render()
+            }
+        };
+
+        // Ids of removed nodes can be reused, so drop their bookmarks right away.
+        model.bookmarks.retain(|id| model.tree.contains(id));
+        model.collapsed.retain(|id| model.tree.contains(id));
+        model.selected = model.selected.take().filter(|id| model.tree.contains(id));
+        reveal_selection(model);
+        model.search_results = model.tree.search(&model.search);
+
+        command
     }
 
     fn view(&self, model: &Self::Model) -> Self::ViewModel {
@@ -154,27 +543,90 @@ impl App for Counter {
             .updated_at
             .map_or_else(|| " (pending)".to_string(), |d| format!(" ({d})"));
 
+        let rows = model.tree.visible_rows_collapsed(false, &model.collapsed);
+
         Self::ViewModel {
             text: model.count.value.to_string() + &suffix,
             confirmed: model.count.updated_at.is_some(),
+            tree: model.tree.clone(),
+            bookmarks: model
+                .bookmarks
+                .iter()
+                .filter_map(|id| model.tree.get(id).ok())
+                .map(|node| node.name().to_owned())
+                .collect(),
+            rows: rows.iter().map(display_row).collect(),
+            row_ids: rows.iter().map(|row| row.id.clone()).collect(),
+            error: model.edit_error.clone(),
+            mode: model.mode,
+            selected: model
+                .selected
+                .as_ref()
+                .and_then(|selected| rows.iter().position(|row| row.id == *selected)),
+            search: model.search.clone(),
+            search_results: model.search_results.clone(),
         }
     }
 }
 
+/// Sets up `Mode::Explorer` before switching to it, starting at the root
+/// unless something is selected already.
+fn enter_explorer(model: &mut Model) {
+    if model.selected.is_none() {
+        model.selected = model.tree.root_id().cloned();
+    }
+}
+
+/// Moves the selection to its nearest visible ancestor if it was hidden,
+/// e.g. inside a group that was just collapsed or archived.
+fn reveal_selection(model: &mut Model) {
+    let Some(selected) = model.selected.clone() else {
+        return;
+    };
+
+    let rows = model.tree.visible_rows_collapsed(false, &model.collapsed);
+    let is_visible = |id: &NodeId| rows.iter().any(|row| row.id == *id);
+    if is_visible(&selected) {
+        return;
+    }
+
+    model.selected = model
+        .tree
+        .ancestor_ids(&selected)
+        .unwrap_or_default()
+        .into_iter()
+        .find(is_visible);
+}
+
+/// Formats a row of the tree for the `ViewModel`.
+fn display_row(row: &CaseRow<'_>) -> String {
+    let indent = "  ".repeat(row.depth);
+
+    match row.node {
+        CaseNode::Group(group) => {
+            let marker = if row.collapsed { '▸' } else { '▾' };
+            format!("{indent}{marker} {}", group.name())
+        }
+        CaseNode::Task(task) => format!("{indent}  {}", task.name()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use chrono::{TimeZone, Utc};
 
-    use crux_core::{App as _, assert_effect};
+    use crux_core::{App as _, Command, assert_effect};
     use crux_http::{
         protocol::{HttpRequest, HttpResponse, HttpResult},
         testing::ResponseBuilder,
     };
 
-    use super::{Counter, Event, Model};
+    use super::{Counter, Event, Mode, Model, UNDO_LIMIT};
     use crate::{
         Count, Effect,
+        notify::{Level, NotifyOperation},
         sse::{SseRequest, SseResponse},
+        types::{CaseNode, DueDateTime, Group, Priority, Task},
     };
 
     // ANCHOR: simple_tests
@@ -266,6 +718,7 @@ mod tests {
                 value: 1,
                 updated_at: Some(Utc.with_ymd_and_hms(2022, 12, 31, 23, 59, 0).unwrap()),
             },
+            ..Model::default()
         };
 
         // Send an `Increment` event to the app.
@@ -329,6 +782,13 @@ mod tests {
         count:
           value: 2
           updated_at: "2023-01-01T00:00:00Z"
+        tree:
+          tree:
+            root: ~
+            nodes: []
+            free_ids: []
+        bookmarks: []
+        collapsed: []
         "#);
     }
 
@@ -343,6 +803,7 @@ mod tests {
                 value: 0,
                 updated_at: Some(Utc.with_ymd_and_hms(2022, 12, 31, 23, 59, 0).unwrap()),
             },
+            ..Model::default()
         };
 
         // Send a `Decrement` event to the app
@@ -406,6 +867,13 @@ mod tests {
         count:
           value: -1
           updated_at: "2023-01-01T00:00:00Z"
+        tree:
+          tree:
+            root: ~
+            nodes: []
+            free_ids: []
+        bookmarks: []
+        collapsed: []
         "#);
     }
 
@@ -466,4 +934,648 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn server_sent_events_error() {
+        let app = Counter;
+        let mut model = Model::default();
+
+        let mut cmd = app.update(Event::StartWatch, &mut model);
+        let mut request = cmd.effects().next().unwrap().expect_server_sent_events();
+
+        // The shell reports that the stream failed.
+        request
+            .resolve(SseResponse::Error("connection reset".to_string()))
+            .unwrap();
+
+        // The failure surfaces as its own event, and the stream is over.
+        let event = cmd.events().next().unwrap();
+        assert_eq!(event, Event::WatchFailed("connection reset".to_string()));
+        assert!(cmd.events().next().is_none());
+        assert!(cmd.is_done());
+
+        let mut cmd = app.update(event, &mut model);
+        assert_effect!(cmd, Effect::Render(_));
+        assert_eq!(model.watch_error, Some("connection reset".to_string()));
+
+        // Watching again starts over.
+        let _ = app.update(Event::StartWatch, &mut model);
+        assert_eq!(model.watch_error, None);
+    }
+
+    #[test]
+    fn notify() {
+        let app = Counter;
+        let mut model = Model::default();
+        let notification = |mut cmd: Command<Effect, Event>| {
+            cmd.effects()
+                .find_map(|effect| match effect {
+                    Effect::Notify(request) => Some(request.operation),
+                    _ => None,
+                })
+                .unwrap()
+        };
+
+        let cmd = app.update(Event::CreateTask("write tests".to_owned()), &mut model);
+        assert_eq!(
+            notification(cmd),
+            NotifyOperation {
+                level: Level::Info,
+                message: "Task created".to_owned(),
+            }
+        );
+
+        let cmd = app.update(
+            Event::WatchFailed("connection reset".to_owned()),
+            &mut model,
+        );
+        assert_eq!(
+            notification(cmd),
+            NotifyOperation {
+                level: Level::Error,
+                message: "Sync failed: connection reset".to_owned(),
+            }
+        );
+    }
+
+    #[test]
+    fn server_sent_events_done() {
+        let app = Counter;
+        let mut model = Model::default();
+
+        let mut cmd = app.update(Event::StartWatch, &mut model);
+        let mut request = cmd.effects().next().unwrap().expect_server_sent_events();
+
+        // A clean end of the stream produces no event at all.
+        request.resolve(SseResponse::Done).unwrap();
+
+        assert!(cmd.events().next().is_none());
+        assert!(cmd.is_done());
+        assert_eq!(model.watch_error, None);
+    }
+
+    #[test]
+    fn stop_watch() {
+        let app = Counter;
+        let mut model = Model::default();
+
+        let mut cmd = app.update(Event::StartWatch, &mut model);
+        let mut request = cmd.effects().next().unwrap().expect_server_sent_events();
+
+        request
+            .resolve(SseResponse::Chunk(
+                br#"data: {"value":1,"updated_at":1672531200000}
+
+                    "#
+                .to_vec(),
+            ))
+            .unwrap();
+        assert!(matches!(cmd.events().next(), Some(Event::Update(_))));
+
+        // Stop watching, which tears down the subscription.
+        let mut stop = app.update(Event::StopWatch, &mut model);
+        assert!(stop.effects().next().is_none());
+        assert!(model.watch.is_none());
+
+        // Anything the shell still sends is no longer resolved into the core.
+        let _ = request.resolve(SseResponse::Chunk(
+            br#"data: {"value":2,"updated_at":1672531200000}
+
+                "#
+            .to_vec(),
+        ));
+        assert!(cmd.events().next().is_none());
+        assert!(cmd.is_done());
+    }
+
+    #[test]
+    fn create_task() {
+        let app = Counter;
+        let mut model = Model::default();
+
+        let mut cmd = app.update(Event::CreateTask("write tests".to_owned()), &mut model);
+        assert_effect!(cmd, Effect::Render(_));
+
+        let _ = app.update(Event::CreateTask("ship it".to_owned()), &mut model);
+
+        // Both tasks land under a single root group.
+        let names: Vec<_> = app
+            .view(&model)
+            .tree
+            .iter()
+            .filter_map(|(_, node)| match node {
+                CaseNode::Task(task) => Some(task.name().to_owned()),
+                CaseNode::Group(_) => None,
+            })
+            .collect();
+        assert_eq!(names, vec!["write tests", "ship it"]);
+        assert!(matches!(
+            model.tree.get(model.tree.root_id().unwrap()),
+            Ok(CaseNode::Group(_))
+        ));
+    }
+
+    #[test]
+    fn toggle_bookmark() {
+        let app = Counter;
+        let mut model = Model::default();
+
+        let _ = app.update(Event::CreateTask("write tests".to_owned()), &mut model);
+        let root_id = model.tree.root_id().unwrap().clone();
+        let (task_id, _) = model.tree.iter().nth(1).unwrap();
+
+        let mut cmd = app.update(Event::ToggleBookmark(root_id.clone()), &mut model);
+        assert_effect!(cmd, Effect::Render(_));
+        let _ = app.update(Event::ToggleBookmark(task_id.clone()), &mut model);
+        assert_eq!(app.view(&model).bookmarks, vec!["root", "write tests"]);
+
+        let _ = app.update(Event::ToggleBookmark(root_id), &mut model);
+        assert_eq!(app.view(&model).bookmarks, vec!["write tests"]);
+
+        // The bookmark of a removed node is not shown, and dropped by the next update.
+        model.tree.remove(&task_id).unwrap();
+        assert!(app.view(&model).bookmarks.is_empty());
+        let _ = app.update(Event::StopWatch, &mut model);
+        assert!(model.bookmarks.is_empty());
+
+        // So a node reusing the id isn't bookmarked.
+        let _ = app.update(Event::CreateTask("ship it".to_owned()), &mut model);
+        assert!(model.tree.contains(&task_id));
+        assert!(app.view(&model).bookmarks.is_empty());
+    }
+
+    fn task_names(model: &Model) -> Vec<String> {
+        model
+            .tree
+            .iter()
+            .filter_map(|(_, node)| match node {
+                CaseNode::Task(task) => Some(task.name().to_owned()),
+                CaseNode::Group(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn undo_redo() {
+        let app = Counter;
+        let mut model = Model::default();
+
+        let _ = app.update(Event::CreateTask("write tests".to_owned()), &mut model);
+        let _ = app.update(Event::CreateTask("ship it".to_owned()), &mut model);
+
+        let mut cmd = app.update(Event::Undo, &mut model);
+        assert_effect!(cmd, Effect::Render(_));
+        assert_eq!(task_names(&model), vec!["write tests"]);
+
+        let _ = app.update(Event::Undo, &mut model);
+        assert!(model.tree.root_id().is_none());
+
+        // Nothing left to undo, so nothing to render either.
+        let mut cmd = app.update(Event::Undo, &mut model);
+        assert!(cmd.effects().next().is_none());
+
+        let mut cmd = app.update(Event::Redo, &mut model);
+        assert_effect!(cmd, Effect::Render(_));
+        let _ = app.update(Event::Redo, &mut model);
+        assert_eq!(task_names(&model), vec!["write tests", "ship it"]);
+
+        let mut cmd = app.update(Event::Redo, &mut model);
+        assert!(cmd.effects().next().is_none());
+    }
+
+    #[test]
+    fn load_tree() {
+        let app = Counter;
+        let mut saved = Model::default();
+        let _ = app.update(Event::CreateTask("write tests".to_owned()), &mut saved);
+        let json = serde_json::to_string(&saved.tree).unwrap();
+
+        let mut model = Model::default();
+        let _ = app.update(Event::CreateTask("ship it".to_owned()), &mut model);
+
+        let mut cmd = app.update(Event::LoadTree(json), &mut model);
+        assert_effect!(cmd, Effect::Render(_));
+        assert_eq!(task_names(&model), vec!["write tests"]);
+
+        // The edits before loading can't be undone into the loaded tree.
+        let mut cmd = app.update(Event::Undo, &mut model);
+        assert!(cmd.effects().next().is_none());
+
+        // Garbage is reported, and the loaded tree kept.
+        let _ = app.update(Event::LoadTree("{".to_owned()), &mut model);
+        assert!(app.view(&model).error.is_some());
+        assert_eq!(task_names(&model), vec!["write tests"]);
+    }
+
+    #[test]
+    fn load_tree_with_fresh_ids() {
+        let app = Counter;
+        let mut model = Model::default();
+        let _ = app.update(Event::CreateTask("write tests".to_owned()), &mut model);
+        let root_id = model.tree.root_id().unwrap().clone();
+        let (task_id, _) = model.tree.iter().nth(1).unwrap();
+
+        // Loading the same tree keeps what points into it.
+        let json = serde_json::to_string(&model.tree).unwrap();
+        let _ = app.update(Event::ToggleBookmark(task_id.clone()), &mut model);
+        let _ = app.update(Event::ToggleCollapse(root_id.clone()), &mut model);
+        model.selected = Some(root_id.clone());
+        let _ = app.update(Event::LoadTree(json), &mut model);
+        assert_eq!(model.bookmarks, vec![task_id.clone()]);
+        assert!(model.collapsed.contains(&root_id));
+        assert_eq!(model.selected, Some(root_id));
+
+        // A nested export gets fresh ids, the same ones for other nodes here.
+        let mut other = sakura::Tree::new();
+        let home_id = other
+            .insert(
+                sakura::Node::new(CaseNode::Group(Group::new("home"))),
+                sakura::InsertBehavior::AsRoot,
+            )
+            .unwrap();
+        other
+            .insert(
+                sakura::Node::new(CaseNode::Task(Task::new("groceries"))),
+                sakura::InsertBehavior::UnderNode(&home_id),
+            )
+            .unwrap();
+        let json = serde_json::to_string(&sakura::NestedTree::from(&other)).unwrap();
+        let _ = app.update(Event::LoadTree(json), &mut model);
+
+        assert_eq!(task_names(&model), vec!["groceries"]);
+        assert!(model.tree.contains(&task_id));
+        assert!(model.bookmarks.is_empty());
+        assert!(model.collapsed.is_empty());
+        assert_eq!(model.selected, None);
+    }
+
+    #[test]
+    fn edit_clears_redo() {
+        let app = Counter;
+        let mut model = Model::default();
+
+        let _ = app.update(Event::CreateTask("write tests".to_owned()), &mut model);
+        let _ = app.update(Event::CreateTask("ship it".to_owned()), &mut model);
+        let _ = app.update(Event::Undo, &mut model);
+
+        let _ = app.update(Event::CreateTask("celebrate".to_owned()), &mut model);
+        let _ = app.update(Event::Redo, &mut model);
+        assert_eq!(task_names(&model), vec!["write tests", "celebrate"]);
+    }
+
+    #[test]
+    fn undo_history_is_bounded() {
+        let app = Counter;
+        let mut model = Model::default();
+
+        for i in 0..=UNDO_LIMIT {
+            let _ = app.update(Event::CreateTask(i.to_string()), &mut model);
+        }
+
+        for _ in 0..=UNDO_LIMIT {
+            let _ = app.update(Event::Undo, &mut model);
+        }
+
+        // The oldest edit fell out of the history, so it stays.
+        assert_eq!(task_names(&model), vec!["0"]);
+    }
+
+    #[test]
+    fn change_priority() {
+        let app = Counter;
+        let mut model = Model::default();
+
+        let _ = app.update(Event::CreateTask("write tests".to_owned()), &mut model);
+        let (task_id, _) = model.tree.iter().nth(1).unwrap();
+        let priority = |model: &Model| model.tree.get(&task_id).unwrap().priority().clone();
+        assert_eq!(priority(&model), Priority::Medium);
+
+        let up = Event::ChangePriority {
+            node: task_id.clone(),
+            delta: 1,
+        };
+        let mut cmd = app.update(up.clone(), &mut model);
+        assert_effect!(cmd, Effect::Render(_));
+        let _ = app.update(up, &mut model);
+        assert_eq!(priority(&model), Priority::Asap);
+
+        // Stepping down saturates at the lowest priority.
+        let _ = app.update(
+            Event::ChangePriority {
+                node: task_id.clone(),
+                delta: -10,
+            },
+            &mut model,
+        );
+        assert_eq!(priority(&model), Priority::Far);
+
+        // Going past it changes nothing, so there is nothing to undo either.
+        let mut cmd = app.update(
+            Event::ChangePriority {
+                node: task_id.clone(),
+                delta: -1,
+            },
+            &mut model,
+        );
+        assert!(cmd.effects().next().is_none());
+        let _ = app.update(Event::Undo, &mut model);
+        assert_eq!(priority(&model), Priority::Asap);
+        let _ = app.update(Event::Redo, &mut model);
+
+        // Groups have a priority as well.
+        let root_id = model.tree.root_id().unwrap().clone();
+        let _ = app.update(
+            Event::ChangePriority {
+                node: root_id.clone(),
+                delta: -1,
+            },
+            &mut model,
+        );
+        assert_eq!(model.tree.get(&root_id).unwrap().priority(), &Priority::Low);
+
+        // And every step can be undone.
+        let _ = app.update(Event::Undo, &mut model);
+        let _ = app.update(Event::Undo, &mut model);
+        assert_eq!(priority(&model), Priority::Asap);
+    }
+
+    #[test]
+    fn set_description() {
+        let app = Counter;
+        let mut model = Model::default();
+
+        let _ = app.update(Event::CreateTask("write tests".to_owned()), &mut model);
+        let root_id = model.tree.root_id().unwrap().clone();
+        let (task_id, _) = model.tree.iter().nth(1).unwrap();
+
+        let mut cmd = app.update(
+            Event::SetDescription {
+                node: task_id.clone(),
+                description: "unit\nand integration".to_owned(),
+            },
+            &mut model,
+        );
+        assert_effect!(cmd, Effect::Render(_));
+        assert!(matches!(
+            model.tree.get(&task_id),
+            Ok(CaseNode::Task(task)) if task.description() == "unit\nand integration"
+        ));
+
+        // Groups have no description.
+        let mut cmd = app.update(
+            Event::SetDescription {
+                node: root_id,
+                description: "nope".to_owned(),
+            },
+            &mut model,
+        );
+        assert!(cmd.effects().next().is_none());
+    }
+
+    #[test]
+    fn set_due_date() {
+        let app = Counter;
+        let mut model = Model::default();
+
+        let _ = app.update(Event::CreateTask("file taxes".to_owned()), &mut model);
+        let (task_id, _) = model.tree.iter().nth(1).unwrap();
+        let due = |model: &Model| match model.tree.get(&task_id) {
+            Ok(CaseNode::Task(task)) => task.due().clone(),
+            _ => unreachable!("the node is a task"),
+        };
+
+        let mut cmd = app.update(
+            Event::SetDueDate {
+                node: task_id.clone(),
+                input: "2026-04-15 17:00".to_owned(),
+            },
+            &mut model,
+        );
+        assert_effect!(cmd, Effect::Render(_));
+        assert_eq!(
+            due(&model),
+            DueDateTime::parse("2026-04-15 17:00:00").unwrap()
+        );
+        assert!(app.view(&model).error.is_none());
+
+        let _ = app.update(
+            Event::SetDueDate {
+                node: task_id.clone(),
+                input: "No Due Date".to_owned(),
+            },
+            &mut model,
+        );
+        assert_eq!(due(&model), DueDateTime::new(None));
+
+        // Clearing is undoable like any other edit.
+        let _ = app.update(Event::Undo, &mut model);
+        assert!(due(&model).is_some());
+    }
+
+    #[test]
+    fn set_due_date_invalid() {
+        let app = Counter;
+        let mut model = Model::default();
+
+        let _ = app.update(Event::CreateTask("file taxes".to_owned()), &mut model);
+        let (task_id, _) = model.tree.iter().nth(1).unwrap();
+        let due = |model: &Model| match model.tree.get(&task_id) {
+            Ok(CaseNode::Task(task)) => task.due().clone(),
+            _ => unreachable!("the node is a task"),
+        };
+        let _ = app.update(
+            Event::SetDueDate {
+                node: task_id.clone(),
+                input: "2026-04-15".to_owned(),
+            },
+            &mut model,
+        );
+        let before = due(&model);
+
+        let mut cmd = app.update(
+            Event::SetDueDate {
+                node: task_id.clone(),
+                input: "tomorrow-ish".to_owned(),
+            },
+            &mut model,
+        );
+        assert_effect!(cmd, Effect::Render(_));
+        assert_eq!(due(&model), before);
+        assert!(
+            app.view(&model)
+                .error
+                .is_some_and(|error| error.contains("tomorrow-ish"))
+        );
+
+        // The next valid date clears the error.
+        let _ = app.update(
+            Event::SetDueDate {
+                node: task_id,
+                input: "2026-04-16".to_owned(),
+            },
+            &mut model,
+        );
+        assert!(app.view(&model).error.is_none());
+    }
+
+    #[test]
+    fn switch_mode() {
+        let app = Counter;
+        let mut model = Model::default();
+
+        let _ = app.update(Event::CreateTask("groceries".to_owned()), &mut model);
+        assert_eq!(app.view(&model).mode, Mode::Home);
+        assert_eq!(app.view(&model).selected, None);
+
+        let mut cmd = app.update(Event::SwitchMode(Mode::Explorer), &mut model);
+        assert_effect!(cmd, Effect::Render(_));
+        let view = app.view(&model);
+        assert_eq!(view.mode, Mode::Explorer);
+        // The root is the first row.
+        assert_eq!(view.selected, Some(0));
+        assert_eq!(model.selected.as_ref(), model.tree.root_id());
+
+        // Switching to the current mode does nothing.
+        let mut cmd = app.update(Event::SwitchMode(Mode::Explorer), &mut model);
+        assert!(cmd.effects().next().is_none());
+
+        // The selection is kept for the next time.
+        let (task_id, _) = model.tree.iter().nth(1).unwrap();
+        model.selected = Some(task_id.clone());
+        let _ = app.update(Event::SwitchMode(Mode::Home), &mut model);
+        let _ = app.update(Event::SwitchMode(Mode::Explorer), &mut model);
+        assert_eq!(model.selected, Some(task_id));
+        assert_eq!(app.view(&model).selected, Some(1));
+    }
+
+    #[test]
+    fn toggle_collapse() {
+        let app = Counter;
+        let mut model = Model::default();
+
+        let _ = app.update(Event::CreateTask("groceries".to_owned()), &mut model);
+        let root_id = model.tree.root_id().unwrap().clone();
+        let work_id = model
+            .tree
+            .insert(CaseNode::Group(Group::new("work")), &root_id)
+            .unwrap();
+        model
+            .tree
+            .insert(CaseNode::Task(Task::new("report")), &work_id)
+            .unwrap();
+        assert_eq!(
+            app.view(&model).rows,
+            vec!["▾ root", "    groceries", "  ▾ work", "      report"]
+        );
+
+        let mut cmd = app.update(Event::ToggleCollapse(work_id.clone()), &mut model);
+        assert_effect!(cmd, Effect::Render(_));
+        assert_eq!(
+            app.view(&model).rows,
+            vec!["▾ root", "    groceries", "  ▸ work"]
+        );
+
+        let _ = app.update(Event::ToggleCollapse(root_id), &mut model);
+        assert_eq!(app.view(&model).rows, vec!["▸ root"]);
+
+        // Tasks can't be collapsed.
+        let (task_id, _) = model.tree.iter().nth(1).unwrap();
+        let mut cmd = app.update(Event::ToggleCollapse(task_id), &mut model);
+        assert!(cmd.effects().next().is_none());
+
+        // A removed group is dropped from the collapsed ones by the next update.
+        model.tree.remove(&work_id).unwrap();
+        let _ = app.update(Event::StopWatch, &mut model);
+        assert!(!model.collapsed.contains(&work_id));
+    }
+
+    #[test]
+    fn collapse_moves_hidden_selection() {
+        let app = Counter;
+        let mut model = Model::default();
+
+        let _ = app.update(Event::CreateTask("groceries".to_owned()), &mut model);
+        let root_id = model.tree.root_id().unwrap().clone();
+        let work_id = model
+            .tree
+            .insert(CaseNode::Group(Group::new("work")), &root_id)
+            .unwrap();
+        let report_id = model
+            .tree
+            .insert(CaseNode::Task(Task::new("report")), &work_id)
+            .unwrap();
+        model.selected = Some(report_id);
+
+        let _ = app.update(Event::ToggleCollapse(work_id.clone()), &mut model);
+
+        // The selection lands on the collapsed group, instead of disappearing.
+        assert_eq!(model.selected, Some(work_id));
+        assert_eq!(app.view(&model).selected, Some(2));
+    }
+
+    #[test]
+    fn hidden_selection_moves_to_visible_ancestor() {
+        let app = Counter;
+        let mut model = Model::default();
+
+        let _ = app.update(Event::CreateTask("groceries".to_owned()), &mut model);
+        let root_id = model.tree.root_id().unwrap().clone();
+        let work_id = model
+            .tree
+            .insert(CaseNode::Group(Group::new("work")), &root_id)
+            .unwrap();
+        let report_id = model
+            .tree
+            .insert(CaseNode::Task(Task::new("report")), &work_id)
+            .unwrap();
+
+        // Archived groups are filtered out of the rows, along with their contents.
+        model.selected = Some(report_id.clone());
+        if let Ok(CaseNode::Group(work)) = model.tree.get_mut(&work_id) {
+            work.set_archived(true);
+        }
+        let _ = app.update(Event::SwitchMode(Mode::Explorer), &mut model);
+        assert_eq!(model.selected, Some(root_id.clone()));
+        assert_eq!(app.view(&model).selected, Some(0));
+
+        // Collapsing everything from the root up skips the collapsed group in between.
+        if let Ok(CaseNode::Group(work)) = model.tree.get_mut(&work_id) {
+            work.set_archived(false);
+        }
+        model.selected = Some(report_id);
+        let _ = app.update(Event::ToggleCollapse(work_id), &mut model);
+        let _ = app.update(Event::ToggleCollapse(root_id.clone()), &mut model);
+        assert_eq!(model.selected, Some(root_id));
+        assert_eq!(app.view(&model).rows, vec!["▸ root"]);
+    }
+
+    #[test]
+    fn search() {
+        let app = Counter;
+        let mut model = Model::default();
+
+        let _ = app.update(Event::CreateTask("Groceries".to_owned()), &mut model);
+        let _ = app.update(Event::CreateTask("Taxes".to_owned()), &mut model);
+        let root_id = model.tree.root_id().unwrap().clone();
+        let work_id = model
+            .tree
+            .insert(CaseNode::Group(Group::new("Work groceries")), &root_id)
+            .unwrap();
+        let (groceries_id, _) = model.tree.iter().nth(1).unwrap();
+
+        let mut cmd = app.update(Event::Search("GROCER".to_owned()), &mut model);
+        assert_effect!(cmd, Effect::Render(_));
+        let view = app.view(&model);
+        assert_eq!(view.search, "GROCER");
+        assert_eq!(view.search_results, vec![groceries_id.clone(), work_id]);
+
+        // Results follow edits to the tree.
+        let _ = app.update(Event::Undo, &mut model);
+        assert_eq!(app.view(&model).search_results, vec![groceries_id]);
+
+        let _ = app.update(Event::Search(String::new()), &mut model);
+        let view = app.view(&model);
+        assert_eq!(view.search, "");
+        assert!(view.search_results.is_empty());
+    }
 }
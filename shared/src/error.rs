@@ -6,6 +6,30 @@ pub enum Error {
     /// Any errors pertaining to `NodeId` handling
     #[error("Node Id error! Could be invalid.")]
     NodeIdError(#[from] sakura::NodeIdError),
+
+    /// A string that doesn't name a `Priority`.
+    #[error("invalid priority \"{0}\", expected one of asap, high, medium, low, far or none")]
+    InvalidPriority(String),
+
+    /// A string that doesn't describe a `DueDateTime`.
+    #[error("invalid due date \"{0}\", expected YYYY-MM-DD, YYYY-MM-DD HH:MM or \"No Due Date\"")]
+    InvalidDueDate(String),
+
+    /// An SSE message in an envelope of a version this build doesn't know.
+    #[error("unsupported SSE message version {0}, expected {max}", max = crate::sse::SSE_VERSION)]
+    UnsupportedSseVersion(String),
+
+    /// An SSE message that couldn't be decoded.
+    #[error("invalid SSE message: {0}")]
+    InvalidSseMessage(String),
+
+    /// Moving a node under itself or one of its descendants was requested.
+    #[error("can't move a node under itself or one of its descendants")]
+    WouldCreateCycle,
+
+    /// A `CaseTree` export that couldn't be read.
+    #[error("invalid import: {0}")]
+    InvalidImport(String),
 }
 
 /// Result type used across this crate.
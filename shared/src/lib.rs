@@ -14,6 +14,9 @@ mod ffi;
 /// Server sent events, will be removed
 pub mod sse;
 
+/// Transient messages for the user
+pub mod notify;
+
 /// Data structures
 pub mod types;
 
@@ -0,0 +1,42 @@
+use crux_core::{Command, Request, capability::Operation};
+use facet::Facet;
+use serde::{Deserialize, Serialize};
+
+/// How much a notification matters to the user.
+#[repr(C)]
+#[derive(Facet, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Level {
+    /// Something went as expected, e.g. a task was created.
+    Info,
+    /// Something the user may want to look into.
+    Warning,
+    /// Something failed, e.g. syncing with the server.
+    Error,
+}
+
+/// Asks the shell to show a transient message, e.g. as a toast. The shell
+/// decides when to dismiss it.
+#[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct NotifyOperation {
+    /// How much the message matters.
+    pub level: Level,
+    /// What to tell the user.
+    pub message: String,
+}
+
+impl Operation for NotifyOperation {
+    type Output = ();
+}
+
+/// Shows `message` to the user for a little while.
+pub fn notify<Effect, Event>(level: Level, message: impl Into<String>) -> Command<Effect, Event>
+where
+    Effect: From<Request<NotifyOperation>> + Send + 'static,
+    Event: Send + 'static,
+{
+    Command::notify_shell(NotifyOperation {
+        level,
+        message: message.into(),
+    })
+    .into()
+}
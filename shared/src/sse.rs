@@ -1,12 +1,13 @@
-use std::{convert::From, future};
+use std::{convert::From, future, iter};
 
 use async_sse::{Event as SseEvent, decode};
 use async_std::io::Cursor;
 use facet::Facet;
-use futures::{Stream, StreamExt};
+use futures::{Stream, StreamExt, stream};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
 
-use crux_core::{Request, capability::Operation, command::StreamBuilder};
+use crux_core::{Command, Request, capability::Operation, command::StreamBuilder};
 
 /// LOL.
 #[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -23,6 +24,8 @@ pub enum SseResponse {
     Chunk(Vec<u8>),
     /// LOL.
     Done,
+    /// The stream failed on the shell side (e.g. the connection dropped).
+    Error(String),
 }
 
 impl SseResponse {
@@ -31,43 +34,287 @@ impl SseResponse {
     pub const fn is_done(&self) -> bool {
         matches!(self, Self::Done)
     }
+
+    /// Whether the shell reported a transport error.
+    #[must_use]
+    pub const fn is_error(&self) -> bool {
+        matches!(self, Self::Error(_))
+    }
 }
 
 impl Operation for SseRequest {
     type Output = SseResponse;
 }
 
+/// The newest version of the SSE message envelope this build understands.
+pub const SSE_VERSION: u8 = 1;
+
+/// Decodes the data of an SSE message as `T`.
+///
+/// Messages are wrapped in a versioned envelope, `{ "v": 1, "payload": .. }`,
+/// so that a backend speaking a newer protocol is reported instead of its
+/// messages failing to decode one by one. A message without the envelope is
+/// taken as version 0, the bare payload older backends send.
+///
+/// # Errors
+/// Errors with `Error::UnsupportedSseVersion` for an envelope of any other
+/// version than `SSE_VERSION`, and with `Error::InvalidSseMessage` if `data` isn't JSON
+/// or the payload isn't a `T`.
+pub fn from_slice<T: DeserializeOwned>(data: &[u8]) -> crate::Result<T> {
+    let invalid = |e: serde_json::Error| crate::Error::InvalidSseMessage(e.to_string());
+
+    let message: Value = serde_json::from_slice(data).map_err(invalid)?;
+    let payload = match message {
+        Value::Object(mut envelope) if is_envelope(&envelope) => match envelope["v"].as_u64() {
+            Some(v) if v == u64::from(SSE_VERSION) => {
+                envelope.remove("payload").unwrap_or_default()
+            }
+            _ => {
+                return Err(crate::Error::UnsupportedSseVersion(
+                    envelope["v"].to_string(),
+                ));
+            }
+        },
+        bare => bare,
+    };
+
+    serde_json::from_value(payload).map_err(invalid)
+}
+
+/// Whether `object` is an envelope, i.e. has exactly a `v` and a `payload`.
+fn is_envelope(object: &serde_json::Map<String, Value>) -> bool {
+    object.len() == 2 && object.contains_key("v") && object.contains_key("payload")
+}
+
 /// SSE bullshit.
 pub struct ServerSentEvents;
 
 impl ServerSentEvents {
-    /// LOL.
+    /// Subscribes to the SSE stream at `url`, decoding each message as `T`
+    /// with `from_slice`.
+    ///
+    /// The stream ends without an item when the shell responds with
+    /// `SseResponse::Done`, and ends with a final `Err` carrying the message
+    /// when the shell responds with `SseResponse::Error`, or when a message
+    /// comes in an envelope of an unsupported version. Messages that merely
+    /// fail to decode are skipped, keeping the stream open.
     pub fn get<Effect, Event, T>(
         url: impl Into<String>,
-    ) -> StreamBuilder<Effect, Event, impl Stream<Item = T>>
+    ) -> StreamBuilder<Effect, Event, impl Stream<Item = Result<T, String>>>
     where
         Effect: From<Request<SseRequest>> + Send + 'static,
         Event: Send + 'static,
-        T: Send + DeserializeOwned,
+        T: Send + DeserializeOwned + 'static,
     {
         let url = url.into();
 
         StreamBuilder::new(|ctx| {
             ctx.stream_from_shell(SseRequest { url })
                 .take_while(|response| future::ready(!response.is_done()))
-                .flat_map(|response| {
-                    let SseResponse::Chunk(data) = response else {
-                        unreachable!()
-                    };
-
-                    decode(Cursor::new(data))
+                .flat_map(|response| match response {
+                    SseResponse::Chunk(data) => decode(Cursor::new(data))
+                        .filter_map(|sse_event| async {
+                            sse_event.ok().and_then(|event| match event {
+                                SseEvent::Message(msg) => match from_slice(msg.data()) {
+                                    Ok(message) => Some(Ok(message)),
+                                    Err(e @ crate::Error::UnsupportedSseVersion(_)) => {
+                                        Some(Err(e.to_string()))
+                                    }
+                                    // A single malformed message isn't worth giving up on.
+                                    Err(_) => None,
+                                },
+                                SseEvent::Retry(_) => None, // Do we need to worry about this?
+                            })
+                        })
+                        .left_stream(),
+                    SseResponse::Error(message) => {
+                        stream::once(future::ready(Err(message))).right_stream()
+                    }
+                    SseResponse::Done => unreachable!(),
                 })
-                .filter_map(|sse_event| async {
-                    sse_event.ok().and_then(|event| match event {
-                        SseEvent::Message(msg) => serde_json::from_slice(msg.data()).ok(),
-                        SseEvent::Retry(_) => None, // Do we need to worry about this?
-                    })
+                // End right after an error, without waiting for the shell to
+                // send anything else: `None` marks the end for `take_while`.
+                .flat_map(|message: Result<T, String>| {
+                    let failed = message.is_err();
+                    stream::iter(iter::once(Some(message)).chain(failed.then_some(None)))
                 })
+                .take_while(|message| future::ready(message.is_some()))
+                .filter_map(future::ready)
         })
     }
+
+    /// Subscribes to the SSE stream at `url`, sending every decoded `T` to the
+    /// app as the `Event` built by `make_event`.
+    ///
+    /// This is a shorthand over `ServerSentEvents::get` for the common case:
+    /// a terminal stream error is dropped, so use `get` when the app needs to
+    /// react to failures.
+    pub fn subscribe<Effect, Event, T>(
+        url: impl Into<String>,
+        make_event: impl Fn(T) -> Event + Send + 'static,
+    ) -> Command<Effect, Event>
+    where
+        Effect: From<Request<SseRequest>> + Send + 'static,
+        Event: Send + 'static,
+        T: Send + DeserializeOwned + 'static,
+    {
+        let url = url.into();
+
+        Command::new(|ctx| async move {
+            let mut messages = Box::pin(Self::get(url).into_stream(ctx.clone()));
+
+            while let Some(message) = messages.next().await {
+                if let Ok(message) = message {
+                    ctx.send_event(make_event(message));
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crux_core::Command;
+    use serde::Deserialize;
+
+    use super::{ServerSentEvents, SseRequest, SseResponse, from_slice};
+    use crate::{Effect, Error};
+
+    #[derive(Deserialize, Debug, PartialEq, Eq)]
+    struct Message {
+        value: i32,
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum Event {
+        Received(Message),
+        Failed(String),
+    }
+
+    #[test]
+    fn subscribe_maps_messages_to_events() {
+        let mut cmd: Command<Effect, Event> =
+            ServerSentEvents::subscribe("http://localhost/sse", Event::Received);
+
+        let mut request = cmd.effects().next().unwrap().expect_server_sent_events();
+        assert_eq!(
+            request.operation,
+            SseRequest {
+                url: "http://localhost/sse".to_string(),
+            }
+        );
+
+        request
+            .resolve(SseResponse::Chunk(b"data: {\"value\":1}\n\n".to_vec()))
+            .unwrap();
+        request
+            .resolve(SseResponse::Chunk(b"data: {\"value\":2}\n\n".to_vec()))
+            .unwrap();
+
+        assert_eq!(
+            cmd.events().collect::<Vec<_>>(),
+            vec![
+                Event::Received(Message { value: 1 }),
+                Event::Received(Message { value: 2 }),
+            ]
+        );
+
+        request.resolve(SseResponse::Done).unwrap();
+        assert!(cmd.is_done());
+    }
+
+    #[test]
+    fn from_slice_known_version() {
+        assert_eq!(
+            from_slice::<Message>(br#"{"v":1,"payload":{"value":1}}"#).unwrap(),
+            Message { value: 1 }
+        );
+    }
+
+    #[test]
+    fn from_slice_unknown_version() {
+        assert!(matches!(
+            from_slice::<Message>(br#"{"v":2,"payload":{"value":1}}"#),
+            Err(Error::UnsupportedSseVersion(v)) if v == "2"
+        ));
+        assert!(matches!(
+            from_slice::<Message>(br#"{"v":"one","payload":{"value":1}}"#),
+            Err(Error::UnsupportedSseVersion(_))
+        ));
+    }
+
+    #[test]
+    fn from_slice_missing_envelope() {
+        // Bare payloads are what backends sent before the envelope.
+        assert_eq!(
+            from_slice::<Message>(br#"{"value":1}"#).unwrap(),
+            Message { value: 1 }
+        );
+        assert!(matches!(
+            from_slice::<Message>(br#"{"v":1}"#),
+            Err(Error::InvalidSseMessage(_))
+        ));
+        assert!(matches!(
+            from_slice::<Message>(b"not json"),
+            Err(Error::InvalidSseMessage(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_message_is_skipped() {
+        let mut cmd: Command<Effect, Event> = ServerSentEvents::get("http://localhost/sse")
+            .then_send(|message| match message {
+                Ok(message) => Event::Received(message),
+                Err(e) => Event::Failed(e),
+            });
+
+        let mut request = cmd.effects().next().unwrap().expect_server_sent_events();
+        request
+            .resolve(SseResponse::Chunk(b"data: not json\n\n".to_vec()))
+            .unwrap();
+        request
+            .resolve(SseResponse::Chunk(
+                b"data: {\"v\":1,\"payload\":{\"value\":2}}\n\n".to_vec(),
+            ))
+            .unwrap();
+
+        assert_eq!(
+            cmd.events().collect::<Vec<_>>(),
+            vec![Event::Received(Message { value: 2 })]
+        );
+        assert!(!cmd.is_done());
+    }
+
+    #[test]
+    fn unknown_version_ends_stream() {
+        let mut cmd: Command<Effect, Event> = ServerSentEvents::get("http://localhost/sse")
+            .then_send(|message| match message {
+                Ok(message) => Event::Received(message),
+                Err(e) => Event::Failed(e),
+            });
+
+        let mut request = cmd.effects().next().unwrap().expect_server_sent_events();
+        request
+            .resolve(SseResponse::Chunk(
+                b"data: {\"v\":1,\"payload\":{\"value\":1}}\n\n".to_vec(),
+            ))
+            .unwrap();
+        request
+            .resolve(SseResponse::Chunk(
+                b"data: {\"v\":2,\"payload\":{\"value\":2}}\n\n".to_vec(),
+            ))
+            .unwrap();
+        request
+            .resolve(SseResponse::Chunk(b"data: {\"value\":3}\n\n".to_vec()))
+            .unwrap();
+
+        assert_eq!(
+            cmd.events().collect::<Vec<_>>(),
+            vec![
+                Event::Received(Message { value: 1 }),
+                Event::Failed(Error::UnsupportedSseVersion("2".to_owned()).to_string()),
+            ]
+        );
+        assert!(cmd.is_done());
+    }
 }
@@ -1,7 +1,7 @@
-use std::ops::Deref;
+use std::{cmp::Ordering, ops::Deref};
 
 use autosurgeon::{Hydrate, Reconcile, reconcile::NoKey};
-use chrono::{NaiveDateTime, format::StrftimeItems};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, format::StrftimeItems};
 use serde::{Deserialize, Serialize};
 
 /// Representation of a Due Date.
@@ -16,17 +16,53 @@ use serde::{Deserialize, Serialize};
 pub struct DueDateTime(Option<NaiveDateTime>);
 
 impl DueDateTime {
-    // TODO: could make this take in a string and then return
-    // a parse error for all
-    // of our supported datetime stuff.
     #[allow(dead_code)]
     pub(crate) const fn new(inner: Option<NaiveDateTime>) -> Self {
         Self(inner)
     }
+
+    /// Parses a due date typed by the user.
+    ///
+    /// Accepts `YYYY-MM-DD HH:MM[:SS]` (or with a `T` in between), a bare
+    /// `YYYY-MM-DD` which is due at the very end of that day, and
+    /// `No Due Date` for no due date at all. Surrounding whitespace is
+    /// ignored.
+    ///
+    /// # Errors
+    /// Errors with `Error::InvalidDueDate` if `input` is none of the above.
+    pub fn parse(input: &str) -> crate::Result<Self> {
+        let trimmed = input.trim();
+
+        if trimmed == NO_DUE_DATE {
+            return Ok(Self(None));
+        }
+
+        DATE_TIME_INPUT_FMTS
+            .iter()
+            .find_map(|fmt| NaiveDateTime::parse_from_str(trimmed, fmt).ok())
+            .or_else(|| {
+                NaiveDate::parse_from_str(trimmed, DATE_INPUT_FMT)
+                    .ok()
+                    .map(|date| date.and_time(END_OF_DAY))
+            })
+            .map(|date_time| Self(Some(date_time)))
+            .ok_or_else(|| crate::Error::InvalidDueDate(input.to_owned()))
+    }
 }
 
 const NO_DUE_DATE: &str = "No Due Date";
 
+const DATE_TIME_INPUT_FMTS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+];
+
+const DATE_INPUT_FMT: &str = "%Y-%m-%d";
+
+const END_OF_DAY: NaiveTime = NaiveTime::from_hms_opt(23, 59, 59).expect("a valid time");
+
 const DATE_TIME_COMPRESSED_FMT: &str = "%Y%m%d%H%M%S";
 
 impl Reconcile for DueDateTime {
@@ -61,6 +97,24 @@ impl Hydrate for DueDateTime {
     }
 }
 
+/// Tasks without a due date sort after every task that has one.
+impl Ord for DueDateTime {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.0, other.0) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+}
+
+impl PartialOrd for DueDateTime {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Deref for DueDateTime {
     type Target = Option<NaiveDateTime>;
 
@@ -75,6 +129,8 @@ mod tests {
     use autosurgeon::{Hydrate, Reconcile, hydrate, reconcile};
     use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 
+    use std::cmp::Ordering;
+
     use super::DueDateTime;
 
     #[test]
@@ -126,4 +182,57 @@ mod tests {
             assert_eq!(result, expected);
         }
     }
+
+    #[test]
+    fn ordering() {
+        let earlier = DueDateTime::new(Some(NaiveDateTime::new(
+            NaiveDate::from_ymd_opt(2006, 1, 31).unwrap(),
+            NaiveTime::from_hms_opt(1, 2, 3).unwrap(),
+        )));
+        let later = DueDateTime::new(Some(NaiveDateTime::new(
+            NaiveDate::from_ymd_opt(2006, 2, 1).unwrap(),
+            NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
+        )));
+        let undated = DueDateTime::new(None);
+
+        // Two dated.
+        assert!(earlier < later);
+
+        // Dated vs. undated, no due date sorts last.
+        assert!(later < undated);
+        assert!(undated > earlier);
+
+        // Two undated are equal.
+        assert_eq!(undated.cmp(&DueDateTime::new(None)), Ordering::Equal);
+    }
+
+    #[test]
+    fn parse() {
+        let at = |d: u32, h: u32, m: u32, s: u32| {
+            DueDateTime::new(Some(NaiveDateTime::new(
+                NaiveDate::from_ymd_opt(2026, 1, d).unwrap(),
+                NaiveTime::from_hms_opt(h, m, s).unwrap(),
+            )))
+        };
+
+        assert_eq!(
+            DueDateTime::parse("2026-01-10 09:30").unwrap(),
+            at(10, 9, 30, 0)
+        );
+        assert_eq!(
+            DueDateTime::parse(" 2026-01-10T09:30:15 ").unwrap(),
+            at(10, 9, 30, 15)
+        );
+        assert_eq!(
+            DueDateTime::parse("2026-01-10").unwrap(),
+            at(10, 23, 59, 59)
+        );
+        assert_eq!(
+            DueDateTime::parse("No Due Date").unwrap(),
+            DueDateTime::new(None)
+        );
+
+        let error = DueDateTime::parse("next tuesday").unwrap_err();
+        assert!(matches!(error, crate::Error::InvalidDueDate(ref s) if s == "next tuesday"));
+    }
 }
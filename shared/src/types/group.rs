@@ -5,11 +5,54 @@ use serde::{Deserialize, Serialize};
 
 use crate::types::Priority;
 
-#[derive(Debug, Serialize, Deserialize, Hydrate, Reconcile, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, Hydrate, Reconcile, PartialEq, Eq)]
 /// Represents a `Group`.
 pub struct Group {
     name: String,
     priority: Priority,
+    #[serde(default)]
+    #[autosurgeon(missing = "Default::default")]
+    archived: bool,
+}
+
+impl Group {
+    /// Creates a new `Group` with the default priority.
+    #[must_use]
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            priority: Priority::default(),
+            archived: false,
+        }
+    }
+
+    /// Returns the `Priority` of this `Group`.
+    #[must_use]
+    pub const fn priority(&self) -> &Priority {
+        &self.priority
+    }
+
+    /// Sets the `Priority` of this `Group`.
+    pub const fn set_priority(&mut self, priority: Priority) {
+        self.priority = priority;
+    }
+
+    /// Returns the name of this `Group`.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether this `Group`, and with it everything below it, is archived.
+    #[must_use]
+    pub const fn is_archived(&self) -> bool {
+        self.archived
+    }
+
+    /// Archives this `Group`, or brings it back.
+    pub const fn set_archived(&mut self, archived: bool) {
+        self.archived = archived;
+    }
 }
 
 impl Ord for Group {
@@ -23,3 +66,35 @@ impl PartialOrd for Group {
         Some(self.cmp(other))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use automerge::AutoCommit;
+    use autosurgeon::{Reconcile, hydrate, reconcile};
+
+    use super::Group;
+    use crate::types::Priority;
+
+    #[test]
+    fn hydrate_without_archived() {
+        // A group as written before it could be archived.
+        #[derive(Reconcile)]
+        struct OldGroup {
+            name: String,
+            priority: Priority,
+        }
+
+        let mut doc = AutoCommit::new();
+        reconcile(
+            &mut doc,
+            &OldGroup {
+                name: "group".to_owned(),
+                priority: Priority::default(),
+            },
+        )
+        .unwrap();
+
+        let group: Group = hydrate(&doc).unwrap();
+        assert_eq!(group, Group::new("group"));
+    }
+}
@@ -7,9 +7,15 @@ pub use group::Group;
 mod due_date_time;
 pub use due_date_time::DueDateTime;
 
+mod recurrence;
+pub use recurrence::Recurrence;
+
 mod priority;
 pub use priority::Priority;
 
 mod tree;
 
-pub use tree::CaseTree;
+pub use tree::{CaseNode, CaseRow, CaseStats, CaseTree};
+
+/// Identifies a node of a `CaseTree`.
+pub use sakura::NodeId;
@@ -1,10 +1,11 @@
-use std::cmp::Ordering;
+use std::{cmp::Ordering, fmt, str::FromStr};
 
 use autosurgeon::{Hydrate, Reconcile};
 use serde::{Deserialize, Serialize};
 
 /// The various priority levels of a `Task`.
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Reconcile, Hydrate, Default)]
+#[non_exhaustive]
 pub enum Priority {
     /// Highest Priority, needs to get done As Soon As Possible
     Asap,
@@ -20,6 +21,8 @@ pub enum Priority {
     /// My rationale for this is things that are more like goals rather
     /// than tasks.
     Far,
+    /// For unprioritized items, which always sort below everything else.
+    None,
 }
 
 impl Priority {
@@ -30,6 +33,7 @@ impl Priority {
     pub const fn p_value(&self) -> u8 {
         // Fibonacci numbers
         match self {
+            Self::None => 0,
             Self::Far => 2,
             Self::Low => 3,
             Self::Medium => 5,
@@ -37,6 +41,84 @@ impl Priority {
             Self::Asap => 13,
         }
     }
+
+    /// Returns the color of the priority as `(red, green, blue)`, for
+    /// frontends to color tasks by without their own palette.
+    ///
+    /// Goes from red for `Asap` to blue for `Far`, unprioritized items are
+    /// grey.
+    #[must_use]
+    pub const fn rgb(&self) -> (u8, u8, u8) {
+        match self {
+            Self::Asap => (255, 0, 0),
+            Self::High => (255, 165, 0),
+            Self::Medium => (255, 255, 0),
+            Self::Low => (0, 255, 0),
+            Self::Far => (0, 0, 255),
+            Self::None => (128, 128, 128),
+        }
+    }
+
+    /// Returns the priority one step above this one, staying at `Asap`.
+    ///
+    /// Unprioritized items step up to `Far`.
+    #[must_use]
+    pub const fn next_higher(&self) -> Self {
+        match self {
+            Self::None => Self::Far,
+            Self::Far => Self::Low,
+            Self::Low => Self::Medium,
+            Self::Medium => Self::High,
+            Self::High | Self::Asap => Self::Asap,
+        }
+    }
+
+    /// Returns the priority one step below this one, staying at `Far`.
+    ///
+    /// Stepping down never unprioritizes an item, so `None` stays `None`.
+    #[must_use]
+    pub const fn next_lower(&self) -> Self {
+        match self {
+            Self::Asap => Self::High,
+            Self::High => Self::Medium,
+            Self::Medium => Self::Low,
+            Self::Low | Self::Far => Self::Far,
+            Self::None => Self::None,
+        }
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Asap => "asap",
+            Self::High => "high",
+            Self::Medium => "medium",
+            Self::Low => "low",
+            Self::Far => "far",
+            Self::None => "none",
+        };
+
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Priority {
+    type Err = crate::Error;
+
+    /// Parses the names written by `Display`, ignoring case. `med` is
+    /// accepted as a shorthand for `medium`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "asap" => Ok(Self::Asap),
+            "high" => Ok(Self::High),
+            "medium" | "med" => Ok(Self::Medium),
+            "low" => Ok(Self::Low),
+            "far" => Ok(Self::Far),
+            "none" => Ok(Self::None),
+            _ => Err(crate::Error::InvalidPriority(s.to_owned())),
+        }
+    }
 }
 
 impl Ord for Priority {
@@ -50,3 +132,107 @@ impl PartialOrd for Priority {
         Some(self.cmp(other))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use automerge::AutoCommit;
+    use autosurgeon::{Hydrate, Reconcile, hydrate, reconcile};
+
+    use super::Priority;
+
+    #[test]
+    fn none_sorts_below_far() {
+        assert_eq!(Priority::None.p_value(), 0);
+        assert!(Priority::None < Priority::Far);
+
+        let mut priorities = vec![Priority::Far, Priority::None, Priority::Asap];
+        priorities.sort();
+        assert_eq!(
+            priorities,
+            vec![Priority::None, Priority::Far, Priority::Asap]
+        );
+    }
+
+    #[test]
+    fn reconcile_priority() {
+        #[derive(Debug, Reconcile, Hydrate, Clone, PartialEq, Eq)]
+        struct Map {
+            priority: Priority,
+        }
+
+        for priority in [Priority::None, Priority::Far, Priority::Asap] {
+            let map = Map { priority };
+
+            let mut doc = AutoCommit::new();
+            reconcile(&mut doc, &map).unwrap();
+
+            let result: Map = hydrate(&doc).unwrap();
+            assert_eq!(result, map);
+        }
+    }
+
+    #[test]
+    fn parse_priority() {
+        for (s, priority) in [
+            ("asap", Priority::Asap),
+            ("high", Priority::High),
+            ("medium", Priority::Medium),
+            ("med", Priority::Medium),
+            ("low", Priority::Low),
+            ("far", Priority::Far),
+            ("none", Priority::None),
+        ] {
+            assert_eq!(s.parse::<Priority>().unwrap(), priority);
+        }
+
+        assert_eq!("HiGh".parse::<Priority>().unwrap(), Priority::High);
+        assert_eq!("MED".parse::<Priority>().unwrap(), Priority::Medium);
+    }
+
+    #[test]
+    fn parse_invalid_priority() {
+        let error = "urgent".parse::<Priority>().unwrap_err();
+
+        assert!(matches!(error, crate::Error::InvalidPriority(ref s) if s == "urgent"));
+        assert_eq!(
+            error.to_string(),
+            "invalid priority \"urgent\", expected one of asap, high, medium, low, far or none"
+        );
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for priority in [
+            Priority::Asap,
+            Priority::High,
+            Priority::Medium,
+            Priority::Low,
+            Priority::Far,
+            Priority::None,
+        ] {
+            assert_eq!(priority.to_string().parse::<Priority>().unwrap(), priority);
+        }
+    }
+
+    #[test]
+    fn step_priority() {
+        assert_eq!(Priority::Medium.next_higher(), Priority::High);
+        assert_eq!(Priority::Medium.next_higher().next_higher(), Priority::Asap);
+        assert_eq!(Priority::Asap.next_higher(), Priority::Asap);
+        assert_eq!(Priority::None.next_higher(), Priority::Far);
+
+        assert_eq!(Priority::Low.next_lower(), Priority::Far);
+        assert_eq!(Priority::Far.next_lower(), Priority::Far);
+        assert_eq!(Priority::None.next_lower(), Priority::None);
+    }
+
+    #[test]
+    fn priority_rgb() {
+        assert_eq!(Priority::Asap.rgb(), (255, 0, 0));
+        assert_eq!(Priority::High.rgb(), (255, 165, 0));
+        assert_eq!(Priority::Medium.rgb(), (255, 255, 0));
+        assert_eq!(Priority::Low.rgb(), (0, 255, 0));
+        assert_eq!(Priority::Far.rgb(), (0, 0, 255));
+        assert_eq!(Priority::None.rgb(), (128, 128, 128));
+    }
+}
@@ -0,0 +1,157 @@
+use autosurgeon::{Hydrate, HydrateError, Reconcile, reconcile::NoKey};
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+
+/// How often a `Task` repeats.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+pub enum Recurrence {
+    /// The task does not repeat.
+    #[default]
+    None,
+    /// The task repeats every day.
+    Daily,
+    /// The task repeats every week.
+    Weekly,
+    /// The task repeats every month, on the same day when possible.
+    Monthly,
+    /// The task repeats after a custom interval.
+    Every(#[serde(with = "seconds")] Duration),
+}
+
+const NONE: &str = "None";
+const DAILY: &str = "Daily";
+const WEEKLY: &str = "Weekly";
+const MONTHLY: &str = "Monthly";
+const EVERY_PREFIX: &str = "Every:";
+
+impl Reconcile for Recurrence {
+    type Key<'a> = NoKey;
+
+    fn reconcile<R: autosurgeon::Reconciler>(&self, mut reconciler: R) -> Result<(), R::Error> {
+        let recurrence_as_str = match self {
+            Self::None => NONE.to_owned(),
+            Self::Daily => DAILY.to_owned(),
+            Self::Weekly => WEEKLY.to_owned(),
+            Self::Monthly => MONTHLY.to_owned(),
+            Self::Every(interval) => format!("{EVERY_PREFIX}{}", interval.num_seconds()),
+        };
+
+        reconciler.str(recurrence_as_str)
+    }
+}
+
+impl Hydrate for Recurrence {
+    fn hydrate_string(string: &'_ str) -> Result<Self, HydrateError> {
+        let recurrence = match string {
+            NONE => Self::None,
+            DAILY => Self::Daily,
+            WEEKLY => Self::Weekly,
+            MONTHLY => Self::Monthly,
+            every => Self::Every(
+                every
+                    .strip_prefix(EVERY_PREFIX)
+                    .and_then(|seconds| seconds.parse().ok())
+                    .and_then(Duration::try_seconds)
+                    .ok_or_else(|| {
+                        HydrateError::unexpected(
+                            "None, Daily, Weekly, Monthly or Every:<seconds>",
+                            every.to_owned(),
+                        )
+                    })?,
+            ),
+        };
+        Ok(recurrence)
+    }
+}
+
+/// (De)serializes a `Duration` as a whole number of seconds.
+mod seconds {
+    use chrono::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(duration.num_seconds())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let seconds = i64::deserialize(deserializer)?;
+        Duration::try_seconds(seconds)
+            .ok_or_else(|| serde::de::Error::custom(format!("{seconds} seconds is out of range")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use automerge::AutoCommit;
+    use autosurgeon::{Hydrate, Reconcile, hydrate, hydrate_prop, reconcile};
+    use chrono::Duration;
+
+    use super::Recurrence;
+
+    #[test]
+    fn reconcile_recurrence() {
+        #[derive(Debug, Reconcile, Hydrate, Clone, PartialEq, Eq)]
+        // A "map" encoded struct for automerge, as the root of any document
+        // must be presentable as a "map", i.e. a struct.
+        struct Map {
+            recurrence: Recurrence,
+        }
+
+        for recurrence in [
+            Recurrence::None,
+            Recurrence::Daily,
+            Recurrence::Weekly,
+            Recurrence::Monthly,
+            Recurrence::Every(Duration::hours(36)),
+        ] {
+            let map = Map { recurrence };
+
+            let expected = map.clone();
+
+            let mut doc = AutoCommit::new();
+
+            reconcile(&mut doc, &map).unwrap();
+
+            let result: Map = hydrate(&doc).unwrap();
+
+            assert_eq!(result, expected);
+        }
+    }
+
+    #[test]
+    fn hydrate_invalid_recurrence() {
+        #[derive(Debug, Reconcile)]
+        struct Raw {
+            recurrence: String,
+        }
+
+        for recurrence in ["Every:soon", "Every:9223372036854775807"] {
+            let mut doc = AutoCommit::new();
+            reconcile(
+                &mut doc,
+                &Raw {
+                    recurrence: recurrence.to_owned(),
+                },
+            )
+            .unwrap();
+
+            let result: Result<Recurrence, _> = hydrate_prop(&doc, &automerge::ROOT, "recurrence");
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn serde_recurrence() {
+        let every = Recurrence::Every(Duration::minutes(90));
+        let json = serde_json::to_string(&every).unwrap();
+
+        assert_eq!(json, r#"{"Every":5400}"#);
+        assert_eq!(serde_json::from_str::<Recurrence>(&json).unwrap(), every);
+    }
+
+    #[test]
+    fn serde_out_of_range_recurrence() {
+        let result = serde_json::from_str::<Recurrence>(r#"{"Every":9223372036854775807}"#);
+        assert!(result.is_err());
+    }
+}
@@ -1,18 +1,148 @@
 use std::cmp::Ordering;
 
 use autosurgeon::{Hydrate, Reconcile};
+use chrono::{Duration, Months, NaiveDateTime};
 use serde::{Deserialize, Serialize};
 
-use crate::types::{DueDateTime, Priority};
+use crate::types::{DueDateTime, Priority, Recurrence};
 
 /// Represents a `Task`
-#[derive(Debug, Serialize, Deserialize, Hydrate, Reconcile, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, Hydrate, Reconcile, PartialEq, Eq)]
 pub struct Task {
     name: String,
     due: DueDateTime,
     priority: Priority,
     description: String,
     finished: bool,
+    #[serde(default)]
+    #[autosurgeon(missing = "Default::default")]
+    recurrence: Recurrence,
+    #[serde(default)]
+    #[autosurgeon(missing = "Default::default")]
+    archived: bool,
+}
+
+impl Task {
+    /// Creates a new, unfinished `Task` with no due date and the default priority.
+    #[must_use]
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            due: DueDateTime::new(None),
+            priority: Priority::default(),
+            description: String::new(),
+            finished: false,
+            recurrence: Recurrence::None,
+            archived: false,
+        }
+    }
+
+    /// Returns the `Priority` of this `Task`.
+    #[must_use]
+    pub const fn priority(&self) -> &Priority {
+        &self.priority
+    }
+
+    /// Sets the `Priority` of this `Task`.
+    pub const fn set_priority(&mut self, priority: Priority) {
+        self.priority = priority;
+    }
+
+    /// Returns the name of this `Task`.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the free-form description of this `Task`.
+    #[must_use]
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Sets the free-form description of this `Task`.
+    pub fn set_description(&mut self, description: impl Into<String>) {
+        self.description = description.into();
+    }
+
+    /// Whether this `Task` has been finished.
+    #[must_use]
+    pub const fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Marks this `Task` as finished, or not.
+    pub const fn set_finished(&mut self, finished: bool) {
+        self.finished = finished;
+    }
+
+    /// Whether this `Task` is archived, i.e. kept but hidden by default.
+    #[must_use]
+    pub const fn is_archived(&self) -> bool {
+        self.archived
+    }
+
+    /// Archives this `Task`, or brings it back.
+    pub const fn set_archived(&mut self, archived: bool) {
+        self.archived = archived;
+    }
+
+    /// Returns the `DueDateTime` of this `Task`.
+    #[must_use]
+    pub const fn due(&self) -> &DueDateTime {
+        &self.due
+    }
+
+    /// Sets the `DueDateTime` of this `Task`.
+    pub const fn set_due(&mut self, due: DueDateTime) {
+        self.due = due;
+    }
+
+    /// Returns how often this `Task` repeats.
+    #[must_use]
+    pub const fn recurrence(&self) -> &Recurrence {
+        &self.recurrence
+    }
+
+    /// Sets how often this `Task` repeats.
+    pub const fn set_recurrence(&mut self, recurrence: Recurrence) {
+        self.recurrence = recurrence;
+    }
+
+    /// Returns the due date of the next occurrence of this `Task`.
+    ///
+    /// Returns `None` if the task doesn't repeat, has no due date, or the next
+    /// date would be out of range.
+    #[must_use]
+    pub fn next_occurrence(&self) -> Option<DueDateTime> {
+        let due = (*self.due)?;
+
+        let next = match &self.recurrence {
+            Recurrence::None => None,
+            Recurrence::Daily => due.checked_add_signed(Duration::days(1)),
+            Recurrence::Weekly => due.checked_add_signed(Duration::weeks(1)),
+            Recurrence::Monthly => due.checked_add_months(Months::new(1)),
+            Recurrence::Every(interval) => due.checked_add_signed(*interval),
+        }?;
+
+        Some(DueDateTime::new(Some(next)))
+    }
+
+    /// Whether this `Task` is past its due date at `now`.
+    ///
+    /// Always `false` for tasks without a due date, and for finished tasks.
+    #[must_use]
+    pub fn is_overdue(&self, now: NaiveDateTime) -> bool {
+        !self.finished && self.due.is_some_and(|due| due < now)
+    }
+
+    /// Returns how long until this `Task` is due, negative if it is past due.
+    ///
+    /// Returns `None` for tasks without a due date.
+    #[must_use]
+    pub fn time_until_due(&self, now: NaiveDateTime) -> Option<Duration> {
+        self.due.map(|due| due - now)
+    }
 }
 
 impl Ord for Task {
@@ -26,3 +156,173 @@ impl PartialOrd for Task {
         Some(self.cmp(other))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use automerge::AutoCommit;
+    use autosurgeon::{Reconcile, hydrate, reconcile};
+    use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+
+    use super::Task;
+    use crate::types::{DueDateTime, Priority, Recurrence};
+
+    fn at(hour: u32) -> NaiveDateTime {
+        NaiveDateTime::new(
+            NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+            NaiveTime::from_hms_opt(hour, 0, 0).unwrap(),
+        )
+    }
+
+    fn task_due_at(hour: u32) -> Task {
+        let mut task = Task::new("task");
+        task.set_due(DueDateTime::new(Some(at(hour))));
+        task
+    }
+
+    #[test]
+    fn overdue() {
+        let task = task_due_at(9);
+
+        assert!(task.is_overdue(at(10)));
+        assert_eq!(task.time_until_due(at(10)), Some(Duration::hours(-1)));
+    }
+
+    #[test]
+    fn future_due() {
+        let task = task_due_at(12);
+
+        assert!(!task.is_overdue(at(10)));
+        assert_eq!(task.time_until_due(at(10)), Some(Duration::hours(2)));
+    }
+
+    #[test]
+    fn finished_but_past() {
+        let mut task = task_due_at(9);
+        task.finished = true;
+
+        assert!(!task.is_overdue(at(10)));
+        assert_eq!(task.time_until_due(at(10)), Some(Duration::hours(-1)));
+    }
+
+    #[test]
+    fn toggle_archive() {
+        let mut task = Task::new("task");
+        assert!(!task.is_archived());
+
+        task.set_archived(true);
+        assert!(task.is_archived());
+
+        task.set_archived(false);
+        assert!(!task.is_archived());
+    }
+
+    #[test]
+    fn no_due_date() {
+        let task = Task::new("task");
+
+        assert!(!task.is_overdue(at(10)));
+        assert_eq!(task.time_until_due(at(10)), None);
+    }
+
+    fn next_with(recurrence: Recurrence) -> Option<DueDateTime> {
+        let mut task = task_due_at(9);
+        task.set_recurrence(recurrence);
+        task.next_occurrence()
+    }
+
+    #[test]
+    fn next_occurrence() {
+        let due = at(9);
+
+        assert_eq!(next_with(Recurrence::None), None);
+        assert_eq!(
+            next_with(Recurrence::Daily),
+            Some(DueDateTime::new(Some(due + Duration::days(1))))
+        );
+        assert_eq!(
+            next_with(Recurrence::Weekly),
+            Some(DueDateTime::new(Some(due + Duration::weeks(1))))
+        );
+        assert_eq!(
+            next_with(Recurrence::Monthly),
+            Some(DueDateTime::new(Some(NaiveDateTime::new(
+                NaiveDate::from_ymd_opt(2026, 2, 1).unwrap(),
+                NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            ))))
+        );
+        assert_eq!(
+            next_with(Recurrence::Every(Duration::hours(36))),
+            Some(DueDateTime::new(Some(due + Duration::hours(36))))
+        );
+    }
+
+    #[test]
+    fn next_occurrence_without_due_date() {
+        let mut task = Task::new("task");
+        task.set_recurrence(Recurrence::Daily);
+
+        assert_eq!(task.next_occurrence(), None);
+    }
+
+    #[test]
+    fn hydrate_without_recurrence() {
+        // A task as written before it could repeat.
+        #[derive(Reconcile)]
+        struct OldTask {
+            name: String,
+            due: DueDateTime,
+            priority: Priority,
+            description: String,
+            finished: bool,
+            archived: bool,
+        }
+
+        let mut doc = AutoCommit::new();
+        reconcile(
+            &mut doc,
+            &OldTask {
+                name: "task".to_owned(),
+                due: DueDateTime::new(None),
+                priority: Priority::default(),
+                description: String::new(),
+                finished: false,
+                archived: false,
+            },
+        )
+        .unwrap();
+
+        let task: Task = hydrate(&doc).unwrap();
+        assert_eq!(task, Task::new("task"));
+    }
+
+    #[test]
+    fn hydrate_without_archived() {
+        // A task as written before it could be archived.
+        #[derive(Reconcile)]
+        struct OldTask {
+            name: String,
+            due: DueDateTime,
+            priority: Priority,
+            description: String,
+            finished: bool,
+            recurrence: Recurrence,
+        }
+
+        let mut doc = AutoCommit::new();
+        reconcile(
+            &mut doc,
+            &OldTask {
+                name: "task".to_owned(),
+                due: DueDateTime::new(None),
+                priority: Priority::default(),
+                description: String::new(),
+                finished: false,
+                recurrence: Recurrence::None,
+            },
+        )
+        .unwrap();
+
+        let task: Task = hydrate(&doc).unwrap();
+        assert_eq!(task, Task::new("task"));
+    }
+}
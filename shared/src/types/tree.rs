@@ -1,23 +1,133 @@
+use std::collections::HashSet;
+
 use autosurgeon::{Hydrate, Reconcile};
-use sakura::{Node, NodeId, Tree};
+use chrono::NaiveDateTime;
+use sakura::{MoveBehavior, NestedTree, Node, NodeId, RemoveBehavior, Tree, TreeBuilder};
 use serde::{Deserialize, Serialize};
 
-use crate::types::{Group, Task};
+use crate::{
+    Error,
+    types::{DueDateTime, Group, Priority, Task},
+};
+
+/// Shared stand-in for nodes without a due date.
+static UNDATED: DueDateTime = DueDateTime::new(None);
 
 /// The core data structure for the CASE application.
 /// Stores groups and tasks in nodes.
-#[derive(Debug, Serialize, Deserialize, Hydrate, Reconcile)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Hydrate, Reconcile)]
 pub struct CaseTree {
     tree: Tree<CaseNode>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Hydrate, Reconcile)]
+/// A single entry in the `CaseTree`.
+///
+/// Serializes with its fields next to a `"type"` tag, e.g.
+/// `{ "type": "task", "name": ... }`, which is friendlier to FFI consumers
+/// than an externally tagged enum. Automerge documents are unaffected.
+#[derive(Debug, Clone, Serialize, Deserialize, Hydrate, Reconcile, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
 pub enum CaseNode {
+    /// A `Task`, usually a leaf.
     Task(Task),
+    /// A `Group` holding tasks and other groups.
     Group(Group),
 }
 
+/// A node of a `CaseTree` flattened into a row, e.g. of a list view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaseRow<'a> {
+    /// The `NodeId` of the node.
+    pub id: NodeId,
+    /// How far below the root the node is, the root being at depth 0.
+    pub depth: usize,
+    /// The node itself.
+    pub node: &'a CaseNode,
+    /// Whether the node is collapsed, i.e. its descendants are left out.
+    pub collapsed: bool,
+}
+
+/// Counts over a whole `CaseTree`, e.g. for a status screen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CaseStats {
+    /// The number of tasks.
+    pub tasks: usize,
+    /// The number of groups, including the root.
+    pub groups: usize,
+    /// The number of finished tasks.
+    pub finished: usize,
+    /// The number of tasks that are overdue.
+    pub overdue: usize,
+    /// The depth of the deepest node, the root being at depth 0.
+    pub max_depth: usize,
+}
+
+impl CaseNode {
+    /// Whether the task or group is archived.
+    #[must_use]
+    pub const fn is_archived(&self) -> bool {
+        match self {
+            Self::Task(task) => task.is_archived(),
+            Self::Group(group) => group.is_archived(),
+        }
+    }
+
+    /// Returns the `Priority` of the task or group.
+    #[must_use]
+    pub const fn priority(&self) -> &Priority {
+        match self {
+            Self::Task(task) => task.priority(),
+            Self::Group(group) => group.priority(),
+        }
+    }
+
+    /// Sets the `Priority` of the task or group.
+    pub const fn set_priority(&mut self, priority: Priority) {
+        match self {
+            Self::Task(task) => task.set_priority(priority),
+            Self::Group(group) => group.set_priority(priority),
+        }
+    }
+
+    /// Returns the name of the task or group.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Task(task) => task.name(),
+            Self::Group(group) => group.name(),
+        }
+    }
+
+    /// Returns the due date of a task, groups never have one.
+    fn due(&self) -> &DueDateTime {
+        match self {
+            Self::Task(task) => task.due(),
+            Self::Group(_) => &UNDATED,
+        }
+    }
+}
+
 impl CaseTree {
+    /// Creates a new `CaseTree` with the given root.
+    #[must_use]
+    pub fn new(root: CaseNode) -> Self {
+        Self {
+            tree: TreeBuilder::new().with_root(Node::new(root)).build(),
+        }
+    }
+
+    /// Returns the `NodeId` of the root, if the tree has one.
+    #[must_use]
+    pub const fn root_id(&self) -> Option<&NodeId> {
+        self.tree.root_node_id()
+    }
+
+    /// Returns the `NodeId` of the root, creating it from `f()` if the tree
+    /// is empty.
+    pub fn root_or_insert_with(&mut self, f: impl FnOnce() -> CaseNode) -> NodeId {
+        self.tree.root_or_insert_with(f)
+    }
+
     /// # Errors
     /// could error if the parent node is invalid!
     pub fn insert(&mut self, node: CaseNode, parent: &NodeId) -> crate::Result<NodeId> {
@@ -27,4 +137,938 @@ impl CaseTree {
             .tree
             .insert(node, sakura::InsertBehavior::UnderNode(parent))?)
     }
+
+    /// Removes the node with the given `NodeId`, along with its subtree.
+    ///
+    /// # Errors
+    /// could error if the node id is invalid!
+    pub fn remove(&mut self, id: &NodeId) -> crate::Result<CaseNode> {
+        let node = self
+            .tree
+            .remove_node(id.clone(), RemoveBehavior::DropChildren)?;
+
+        Ok(node.data().clone())
+    }
+
+    /// Whether the tree still has a node with the given `NodeId`.
+    #[must_use]
+    pub fn contains(&self, id: &NodeId) -> bool {
+        self.tree.contains(id)
+    }
+
+    /// Moves the node with the given `NodeId`, along with its subtree, under
+    /// `new_parent`.
+    ///
+    /// # Errors
+    /// Errors with `Error::WouldCreateCycle` if `new_parent` is the node
+    /// itself or one of its descendants, leaving the tree untouched. Could
+    /// also error if either node id is invalid!
+    pub fn move_task(&mut self, node: &NodeId, new_parent: &NodeId) -> crate::Result<()> {
+        if node == new_parent || self.tree.is_ancestor_of(node, new_parent)? {
+            return Err(Error::WouldCreateCycle);
+        }
+
+        Ok(self
+            .tree
+            .move_node(node, MoveBehavior::ToParent(new_parent))?)
+    }
+
+    /// Returns the `NodeId`s of the ancestors of the given node, from its
+    /// parent up to the root.
+    ///
+    /// # Errors
+    /// could error if the node id is invalid!
+    pub fn ancestor_ids(&self, id: &NodeId) -> crate::Result<Vec<NodeId>> {
+        Ok(self.tree.ancestor_ids(id)?.cloned().collect())
+    }
+
+    /// Returns the `CaseNode` with the given `NodeId`.
+    ///
+    /// # Errors
+    /// could error if the node id is invalid!
+    pub fn get(&self, id: &NodeId) -> crate::Result<&CaseNode> {
+        Ok(self.tree.get(id)?.data())
+    }
+
+    /// Returns the `CaseNode` with the given `NodeId` mutably.
+    ///
+    /// # Errors
+    /// could error if the node id is invalid!
+    pub fn get_mut(&mut self, id: &NodeId) -> crate::Result<&mut CaseNode> {
+        Ok(self.tree.get_mut(id)?.data_mut())
+    }
+
+    /// Iterates over every `CaseNode` in pre-order, starting from the root.
+    ///
+    /// Yields nothing if the tree has no root.
+    ///
+    /// # Panics
+    /// Panics if the tree is corrupt, i.e. its traversal yields a removed node.
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &CaseNode)> {
+        self.tree
+            .root_node_id()
+            .and_then(|root_id| self.tree.traverse_pre_order_ids(root_id).ok())
+            .into_iter()
+            .flatten()
+            .map(|id| {
+                let node = self
+                    .tree
+                    .get(&id)
+                    .expect("traversal only yields valid node ids")
+                    .data();
+
+                (id, node)
+            })
+    }
+
+    /// Flattens the tree into rows, in pre-order.
+    ///
+    /// Unless `include_archived` is set, archived nodes are left out together
+    /// with their whole subtree, so archiving a group hides everything in it.
+    #[must_use]
+    pub fn visible_rows(&self, include_archived: bool) -> Vec<CaseRow<'_>> {
+        self.visible_rows_collapsed(include_archived, &HashSet::new())
+    }
+
+    /// Same as `visible_rows`, but the descendants of the nodes in `collapsed`
+    /// are left out as well, while the collapsed nodes themselves are kept.
+    ///
+    /// # Panics
+    /// Panics if the tree is corrupt, i.e. a child id points at a removed node.
+    #[must_use]
+    pub fn visible_rows_collapsed(
+        &self,
+        include_archived: bool,
+        collapsed: &HashSet<NodeId>,
+    ) -> Vec<CaseRow<'_>> {
+        let mut rows = vec![];
+        let mut stack: Vec<(NodeId, usize)> = self
+            .root_id()
+            .map(|id| (id.clone(), 0))
+            .into_iter()
+            .collect();
+
+        while let Some((id, depth)) = stack.pop() {
+            let node = self
+                .tree
+                .get(&id)
+                .expect("traversal only yields valid node ids");
+
+            if node.data().is_archived() && !include_archived {
+                continue;
+            }
+
+            let is_collapsed = collapsed.contains(&id);
+            if !is_collapsed {
+                stack.extend(
+                    node.children()
+                        .iter()
+                        .rev()
+                        .map(|child| (child.clone(), depth + 1)),
+                );
+            }
+            rows.push(CaseRow {
+                id,
+                depth,
+                node: node.data(),
+                collapsed: is_collapsed,
+            });
+        }
+
+        rows
+    }
+
+    /// Flips whether the task with the given `NodeId` is finished.
+    ///
+    /// With `cascade`, the tasks below it follow its new state. Groups have
+    /// no state of their own, so toggling one only does anything with
+    /// `cascade`: every task below it is marked finished, or unfinished if
+    /// they all are already.
+    ///
+    /// # Errors
+    /// could error if the node id is invalid!
+    pub fn toggle_finished(&mut self, id: &NodeId, cascade: bool) -> crate::Result<()> {
+        let finished = match self.get(id)? {
+            CaseNode::Task(task) => !task.is_finished(),
+            CaseNode::Group(_) if cascade => self
+                .tree
+                .traverse_pre_order(id)?
+                .any(|node| matches!(node.data(), CaseNode::Task(task) if !task.is_finished())),
+            CaseNode::Group(_) => return Ok(()),
+        };
+
+        if cascade {
+            self.tree.walk_mut(id, |node| {
+                if let CaseNode::Task(task) = node.data_mut() {
+                    task.set_finished(finished);
+                }
+            })?;
+        } else if let CaseNode::Task(task) = self.get_mut(id)? {
+            task.set_finished(finished);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the highest `Priority` found in the subtree of the given
+    /// `NodeId`, counting the priorities of groups as well as tasks.
+    ///
+    /// A group without any children simply has its own priority.
+    ///
+    /// # Errors
+    /// could error if the node id is invalid!
+    ///
+    /// # Panics
+    /// Never in practice, a subtree always holds at least its own root.
+    pub fn rollup_priority(&self, id: &NodeId) -> crate::Result<Priority> {
+        Ok(self
+            .tree
+            .traverse_pre_order(id)?
+            .map(|node| node.data().priority())
+            .max()
+            .expect("a subtree always holds its own root")
+            .clone())
+    }
+
+    /// Returns the `NodeId`s of every task that is overdue at `now`, in
+    /// pre-order.
+    #[must_use]
+    pub fn overdue(&self, now: NaiveDateTime) -> Vec<NodeId> {
+        self.iter()
+            .filter(|(_, node)| matches!(node, CaseNode::Task(task) if task.is_overdue(now)))
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// Returns the `NodeId`s of every task and group whose name contains
+    /// `query`, ignoring case, in pre-order.
+    ///
+    /// An empty `query` matches nothing.
+    #[must_use]
+    pub fn search(&self, query: &str) -> Vec<NodeId> {
+        if query.is_empty() {
+            return vec![];
+        }
+
+        let query = query.to_lowercase();
+        self.iter()
+            .filter(|(_, node)| node.name().to_lowercase().contains(&query))
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// Summarizes the tree in a single traversal, counting tasks that are
+    /// overdue at `now`.
+    ///
+    /// # Panics
+    /// Panics if the tree is corrupt, i.e. a child id points at a removed node.
+    #[must_use]
+    pub fn stats(&self, now: NaiveDateTime) -> CaseStats {
+        let mut stats = CaseStats::default();
+        let mut stack: Vec<(&NodeId, usize)> =
+            self.root_id().map(|id| (id, 0)).into_iter().collect();
+
+        while let Some((id, depth)) = stack.pop() {
+            let node = self
+                .tree
+                .get(id)
+                .expect("traversal only yields valid node ids");
+
+            match node.data() {
+                CaseNode::Task(task) => {
+                    stats.tasks += 1;
+                    stats.finished += usize::from(task.is_finished());
+                    stats.overdue += usize::from(task.is_overdue(now));
+                }
+                CaseNode::Group(_) => stats.groups += 1,
+            }
+            stats.max_depth = stats.max_depth.max(depth);
+
+            stack.extend(node.children().iter().map(|child| (child, depth + 1)));
+        }
+
+        stats
+    }
+
+    /// Writes the tree with box-drawing characters, labelling groups with their
+    /// name and tasks with a `[x]`/`[ ]` checkbox followed by their name.
+    ///
+    /// Writes nothing if the tree is empty.
+    ///
+    /// # Errors
+    /// Can error if writing to `w` fails.
+    pub fn write_formatted<W: std::fmt::Write>(&self, w: &mut W) -> std::fmt::Result {
+        self.tree.write_formatted_by(w, |node| match node {
+            CaseNode::Task(task) => {
+                let checkbox = if task.is_finished() { "[x]" } else { "[ ]" };
+                format!("{checkbox} {}", task.name())
+            }
+            CaseNode::Group(group) => group.name().to_owned(),
+        })
+    }
+
+    /// Orders the children of every node by due date, soonest first.
+    ///
+    /// The sort is stable, so nodes without a due date (including groups)
+    /// keep their relative order after the dated ones.
+    ///
+    /// # Panics
+    /// Panics if the tree is corrupt, i.e. its traversal yields a removed node.
+    pub fn sort_by_due(&mut self) {
+        let ids: Vec<NodeId> = self.iter().map(|(id, _)| id).collect();
+
+        for id in ids {
+            self.tree
+                .sort_children_by(&id, |a, b| a.data().due().cmp(b.data().due()))
+                .expect("traversal only yields valid node ids");
+        }
+    }
+
+    /// Removes every group without a task anywhere below it, returning the
+    /// `NodeId`s of the removed groups in pre-order.
+    ///
+    /// The groups below such a group hold no tasks either, so they go along
+    /// with it. The root is kept even if it ends up empty.
+    ///
+    /// # Panics
+    /// Panics if the tree is corrupt, i.e. its traversal yields a removed node.
+    pub fn prune_empty_groups(&mut self) -> Vec<NodeId> {
+        let ids: Vec<NodeId> = self.iter().map(|(id, _)| id).collect();
+
+        // Children come after their parent in pre-order, so walking it
+        // backwards settles every child first.
+        let mut has_task = HashSet::new();
+        for id in ids.iter().rev() {
+            let node = self
+                .tree
+                .get(id)
+                .expect("traversal only yields valid node ids");
+            if matches!(node.data(), CaseNode::Task(_))
+                || node.children().iter().any(|child| has_task.contains(child))
+            {
+                has_task.insert(id.clone());
+            }
+        }
+
+        let root_id = self.root_id().cloned();
+        let pruned: Vec<NodeId> = ids
+            .into_iter()
+            .filter(|id| !has_task.contains(id) && Some(id) != root_id.as_ref())
+            .collect();
+
+        for id in &pruned {
+            // Already gone if an ancestor was pruned before it.
+            if self.contains(id) {
+                self.remove(id).expect("pruned ids are valid node ids");
+            }
+        }
+
+        pruned
+    }
+
+    /// Reads a `CaseTree` back from JSON, keeping its `NodeId`s when the
+    /// export has them.
+    ///
+    /// A `CaseTree` serialized as is keeps its exact slot layout, free slots
+    /// included, so ids stored elsewhere (e.g. collapsed nodes) stay valid.
+    /// A `NestedTree` export has no ids, so fresh ones are assigned instead.
+    ///
+    /// # Errors
+    /// Errors with `Error::InvalidImport` if `json` is neither, or with
+    /// `Error::NodeIdError` if it isn't a consistent tree, see `Tree::validate`.
+    pub fn import_preserving_ids(json: &str) -> crate::Result<Self> {
+        Self::import(json).map(|(tree, _)| tree)
+    }
+
+    /// Like `import_preserving_ids`, also returning whether the `NodeId`s
+    /// were kept, as ids stored elsewhere only stay valid if they were.
+    ///
+    /// # Errors
+    /// Errors like `import_preserving_ids`.
+    pub fn import(json: &str) -> crate::Result<(Self, bool)> {
+        let value: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| Error::InvalidImport(e.to_string()))?;
+
+        if value.get("tree").is_none() {
+            let nested: NestedTree<CaseNode> =
+                serde_json::from_value(value).map_err(|e| Error::InvalidImport(e.to_string()))?;
+
+            return Ok((
+                Self {
+                    tree: nested.into(),
+                },
+                false,
+            ));
+        }
+
+        let imported: Self =
+            serde_json::from_value(value).map_err(|e| Error::InvalidImport(e.to_string()))?;
+
+        // Ids are used as indices and links are trusted everywhere else, so
+        // make sure they hold before handing the tree out.
+        imported.tree.validate()?;
+
+        Ok((imported, true))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use automerge::AutoCommit;
+    use autosurgeon::{Hydrate, Reconcile, hydrate, reconcile};
+    use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+    use sakura::{NestedTree, NodeIdError};
+
+    use super::{CaseNode, CaseStats, CaseTree};
+    use crate::{
+        Error,
+        types::{DueDateTime, Group, NodeId, Priority, Task},
+    };
+
+    fn task_due_on(name: &str, day: u32) -> CaseNode {
+        let mut task = Task::new(name);
+        task.set_due(DueDateTime::new(Some(NaiveDateTime::new(
+            NaiveDate::from_ymd_opt(2026, 1, day).unwrap(),
+            NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+        ))));
+
+        CaseNode::Task(task)
+    }
+
+    #[test]
+    fn iter_pre_order() {
+        let mut tree = CaseTree::new(CaseNode::Group(Group::new("root")));
+        let (root_id, _) = tree.iter().next().unwrap();
+
+        let work_id = tree
+            .insert(CaseNode::Group(Group::new("work")), &root_id)
+            .unwrap();
+        let report_id = tree
+            .insert(CaseNode::Task(Task::new("report")), &work_id)
+            .unwrap();
+        let groceries_id = tree
+            .insert(CaseNode::Task(Task::new("groceries")), &root_id)
+            .unwrap();
+
+        let ids: Vec<_> = tree.iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![root_id, work_id, report_id.clone(), groceries_id]);
+
+        assert_eq!(
+            tree.get(&report_id).unwrap(),
+            &CaseNode::Task(Task::new("report"))
+        );
+    }
+
+    #[test]
+    fn iter_empty() {
+        let tree = CaseTree::default();
+        assert_eq!(tree.iter().count(), 0);
+    }
+
+    #[test]
+    fn sort_by_due() {
+        let mut tree = CaseTree::new(CaseNode::Group(Group::new("root")));
+        let (root_id, _) = tree.iter().next().unwrap();
+
+        let undated_id = tree
+            .insert(CaseNode::Task(Task::new("someday")), &root_id)
+            .unwrap();
+        let later_id = tree.insert(task_due_on("later", 20), &root_id).unwrap();
+        let earlier_id = tree.insert(task_due_on("earlier", 10), &root_id).unwrap();
+
+        tree.sort_by_due();
+
+        let ids: Vec<_> = tree.iter().map(|(id, _)| id).skip(1).collect();
+        assert_eq!(ids, vec![earlier_id, later_id, undated_id]);
+    }
+
+    #[test]
+    fn overdue() {
+        let mut tree = CaseTree::new(CaseNode::Group(Group::new("root")));
+        let (root_id, _) = tree.iter().next().unwrap();
+
+        tree.insert(CaseNode::Task(Task::new("someday")), &root_id)
+            .unwrap();
+        let earlier_id = tree.insert(task_due_on("earlier", 10), &root_id).unwrap();
+        tree.insert(task_due_on("later", 20), &root_id).unwrap();
+
+        let now = NaiveDateTime::new(
+            NaiveDate::from_ymd_opt(2026, 1, 15).unwrap(),
+            NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+        );
+
+        assert_eq!(tree.overdue(now), vec![earlier_id]);
+    }
+
+    #[test]
+    fn write_formatted() {
+        let mut tree = CaseTree::new(CaseNode::Group(Group::new("root")));
+        let (root_id, _) = tree.iter().next().unwrap();
+
+        let work_id = tree
+            .insert(CaseNode::Group(Group::new("work")), &root_id)
+            .unwrap();
+        let mut report = Task::new("report");
+        report.set_finished(true);
+        tree.insert(CaseNode::Task(report), &work_id).unwrap();
+        tree.insert(CaseNode::Task(Task::new("groceries")), &root_id)
+            .unwrap();
+
+        let mut s = String::new();
+        tree.write_formatted(&mut s).unwrap();
+
+        assert_eq!(
+            s,
+            "\
+root
+├── work
+│   └── [x] report
+└── [ ] groceries
+"
+        );
+    }
+
+    #[test]
+    fn move_task() {
+        let mut tree = CaseTree::new(CaseNode::Group(Group::new("root")));
+        let (root_id, _) = tree.iter().next().unwrap();
+
+        let work_id = tree
+            .insert(CaseNode::Group(Group::new("work")), &root_id)
+            .unwrap();
+        let report_id = tree
+            .insert(CaseNode::Task(Task::new("report")), &root_id)
+            .unwrap();
+
+        tree.move_task(&report_id, &work_id).unwrap();
+
+        let ids: Vec<_> = tree.iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![root_id, work_id, report_id]);
+    }
+
+    #[test]
+    fn move_task_cycle() {
+        let mut tree = CaseTree::new(CaseNode::Group(Group::new("root")));
+        let (root_id, _) = tree.iter().next().unwrap();
+
+        let work_id = tree
+            .insert(CaseNode::Group(Group::new("work")), &root_id)
+            .unwrap();
+        let project_id = tree
+            .insert(CaseNode::Group(Group::new("project")), &work_id)
+            .unwrap();
+
+        assert!(matches!(
+            tree.move_task(&work_id, &project_id),
+            Err(Error::WouldCreateCycle)
+        ));
+        assert!(matches!(
+            tree.move_task(&work_id, &work_id),
+            Err(Error::WouldCreateCycle)
+        ));
+
+        // Nothing moved.
+        let ids: Vec<_> = tree.iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![root_id, work_id, project_id]);
+    }
+
+    #[test]
+    fn visible_rows() {
+        let mut tree = CaseTree::new(CaseNode::Group(Group::new("root")));
+        let (root_id, _) = tree.iter().next().unwrap();
+
+        let mut old = Group::new("old");
+        old.set_archived(true);
+        let old_id = tree.insert(CaseNode::Group(old), &root_id).unwrap();
+        tree.insert(CaseNode::Task(Task::new("done long ago")), &old_id)
+            .unwrap();
+
+        let mut archived = Task::new("archived");
+        archived.set_archived(true);
+        tree.insert(CaseNode::Task(archived), &root_id).unwrap();
+        tree.insert(CaseNode::Task(Task::new("report")), &root_id)
+            .unwrap();
+
+        let rows = |include_archived| -> Vec<(usize, String)> {
+            tree.visible_rows(include_archived)
+                .into_iter()
+                .map(|row| (row.depth, row.node.name().to_owned()))
+                .collect()
+        };
+
+        // The archived group hides its whole subtree.
+        assert_eq!(
+            rows(false),
+            vec![(0, "root".to_owned()), (1, "report".to_owned())]
+        );
+        assert_eq!(
+            rows(true),
+            vec![
+                (0, "root".to_owned()),
+                (1, "old".to_owned()),
+                (2, "done long ago".to_owned()),
+                (1, "archived".to_owned()),
+                (1, "report".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn visible_rows_empty() {
+        assert!(CaseTree::default().visible_rows(true).is_empty());
+    }
+
+    #[test]
+    fn rollup_priority() {
+        let mut tree = CaseTree::new(CaseNode::Group(Group::new("root")));
+        let (root_id, _) = tree.iter().next().unwrap();
+
+        let mut low = Group::new("someday");
+        low.set_priority(Priority::Low);
+        let group_id = tree.insert(CaseNode::Group(low), &root_id).unwrap();
+        let mut urgent = Task::new("urgent");
+        urgent.set_priority(Priority::Asap);
+        tree.insert(CaseNode::Task(urgent), &group_id).unwrap();
+        tree.insert(CaseNode::Task(Task::new("report")), &group_id)
+            .unwrap();
+
+        let mut far = Group::new("goals");
+        far.set_priority(Priority::Far);
+        let empty_id = tree.insert(CaseNode::Group(far), &root_id).unwrap();
+
+        assert_eq!(tree.rollup_priority(&group_id).unwrap(), Priority::Asap);
+        assert_eq!(tree.rollup_priority(&root_id).unwrap(), Priority::Asap);
+        assert_eq!(tree.rollup_priority(&empty_id).unwrap(), Priority::Far);
+    }
+
+    fn is_finished(tree: &CaseTree, id: &NodeId) -> bool {
+        matches!(tree.get(id), Ok(CaseNode::Task(task)) if task.is_finished())
+    }
+
+    #[test]
+    fn toggle_finished_task() {
+        let mut tree = CaseTree::new(CaseNode::Group(Group::new("root")));
+        let (root_id, _) = tree.iter().next().unwrap();
+        let task_id = tree
+            .insert(CaseNode::Task(Task::new("report")), &root_id)
+            .unwrap();
+        let sub_id = tree
+            .insert(CaseNode::Task(Task::new("outline")), &task_id)
+            .unwrap();
+
+        tree.toggle_finished(&task_id, false).unwrap();
+        assert!(is_finished(&tree, &task_id));
+        assert!(!is_finished(&tree, &sub_id));
+
+        tree.toggle_finished(&task_id, false).unwrap();
+        assert!(!is_finished(&tree, &task_id));
+
+        // A group without cascade is left alone.
+        tree.toggle_finished(&root_id, false).unwrap();
+        assert!(!is_finished(&tree, &task_id));
+        assert!(!is_finished(&tree, &sub_id));
+    }
+
+    #[test]
+    fn toggle_finished_cascade() {
+        let mut tree = CaseTree::new(CaseNode::Group(Group::new("root")));
+        let (root_id, _) = tree.iter().next().unwrap();
+        let work_id = tree
+            .insert(CaseNode::Group(Group::new("work")), &root_id)
+            .unwrap();
+        let report_id = tree
+            .insert(CaseNode::Task(Task::new("report")), &work_id)
+            .unwrap();
+        let mut done = Task::new("review");
+        done.set_finished(true);
+        let review_id = tree.insert(CaseNode::Task(done), &work_id).unwrap();
+        let other_id = tree
+            .insert(CaseNode::Task(Task::new("groceries")), &root_id)
+            .unwrap();
+
+        // Something is left to do, so everything gets finished.
+        tree.toggle_finished(&work_id, true).unwrap();
+        assert!(is_finished(&tree, &report_id));
+        assert!(is_finished(&tree, &review_id));
+        assert!(!is_finished(&tree, &other_id));
+
+        // Once all are finished, they are all reopened.
+        tree.toggle_finished(&work_id, true).unwrap();
+        assert!(!is_finished(&tree, &report_id));
+        assert!(!is_finished(&tree, &review_id));
+
+        // Tasks take their subtasks along.
+        let sub_id = tree
+            .insert(CaseNode::Task(Task::new("outline")), &report_id)
+            .unwrap();
+        tree.toggle_finished(&report_id, true).unwrap();
+        assert!(is_finished(&tree, &report_id));
+        assert!(is_finished(&tree, &sub_id));
+    }
+
+    #[test]
+    fn stats() {
+        let mut tree = CaseTree::new(CaseNode::Group(Group::new("root")));
+        let (root_id, _) = tree.iter().next().unwrap();
+
+        let work_id = tree
+            .insert(CaseNode::Group(Group::new("work")), &root_id)
+            .unwrap();
+        tree.insert(task_due_on("report", 1), &work_id).unwrap();
+        tree.insert(task_due_on("review", 20), &work_id).unwrap();
+
+        let mut done = Task::new("done");
+        done.set_finished(true);
+        tree.insert(CaseNode::Task(done), &root_id).unwrap();
+
+        let CaseNode::Task(mut late) = task_due_on("late but done", 2) else {
+            unreachable!()
+        };
+        late.set_finished(true);
+        tree.insert(CaseNode::Task(late), &work_id).unwrap();
+
+        let now = NaiveDateTime::new(
+            NaiveDate::from_ymd_opt(2026, 1, 10).unwrap(),
+            NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
+        );
+
+        assert_eq!(
+            tree.stats(now),
+            CaseStats {
+                tasks: 4,
+                groups: 2,
+                finished: 2,
+                overdue: 1,
+                max_depth: 2,
+            }
+        );
+        assert_eq!(CaseTree::default().stats(now), CaseStats::default());
+    }
+
+    #[test]
+    fn visible_rows_collapsed() {
+        let mut tree = CaseTree::new(CaseNode::Group(Group::new("root")));
+        let (root_id, _) = tree.iter().next().unwrap();
+
+        let work_id = tree
+            .insert(CaseNode::Group(Group::new("work")), &root_id)
+            .unwrap();
+        tree.insert(CaseNode::Task(Task::new("report")), &work_id)
+            .unwrap();
+        tree.insert(CaseNode::Task(Task::new("groceries")), &root_id)
+            .unwrap();
+
+        let collapsed = HashSet::from([work_id]);
+        let rows: Vec<_> = tree
+            .visible_rows_collapsed(false, &collapsed)
+            .into_iter()
+            .map(|row| (row.node.name().to_owned(), row.collapsed))
+            .collect();
+
+        assert_eq!(
+            rows,
+            vec![
+                ("root".to_owned(), false),
+                ("work".to_owned(), true),
+                ("groceries".to_owned(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn case_node_json_tag() {
+        let task = CaseNode::Task(Task::new("report"));
+        let json = serde_json::to_value(&task).unwrap();
+        assert_eq!(json["type"], "task");
+        assert_eq!(json["name"], "report");
+        assert_eq!(serde_json::from_value::<CaseNode>(json).unwrap(), task);
+
+        let group = CaseNode::Group(Group::new("work"));
+        let json = serde_json::to_value(&group).unwrap();
+        assert_eq!(json["type"], "group");
+        assert_eq!(json["name"], "work");
+        assert_eq!(serde_json::from_value::<CaseNode>(json).unwrap(), group);
+    }
+
+    #[test]
+    fn case_node_automerge_round_trip() {
+        #[derive(Debug, Reconcile, Hydrate, PartialEq, Eq)]
+        struct Doc {
+            nodes: Vec<CaseNode>,
+        }
+
+        let mut task = Task::new("report");
+        task.set_priority(Priority::High);
+        task.set_description("quarterly numbers");
+        let doc = Doc {
+            nodes: vec![CaseNode::Task(task), CaseNode::Group(Group::new("work"))],
+        };
+
+        let mut automerge = AutoCommit::new();
+        reconcile(&mut automerge, &doc).unwrap();
+        let hydrated: Doc = hydrate(&automerge).unwrap();
+        assert_eq!(hydrated, doc);
+
+        // And the JSON of the hydrated nodes still carries the tag.
+        let json = serde_json::to_value(&hydrated.nodes).unwrap();
+        assert_eq!(json[0]["type"], "task");
+        assert_eq!(json[1]["type"], "group");
+    }
+
+    #[test]
+    fn import_preserving_ids_round_trip() {
+        let mut tree = CaseTree::new(CaseNode::Group(Group::new("root")));
+        let root_id = tree.root_id().unwrap().clone();
+        let work_id = tree
+            .insert(CaseNode::Group(Group::new("work")), &root_id)
+            .unwrap();
+        let report_id = tree
+            .insert(CaseNode::Task(Task::new("report")), &root_id)
+            .unwrap();
+        tree.remove(&work_id).unwrap();
+
+        let json = serde_json::to_string(&tree).unwrap();
+        let mut imported = CaseTree::import_preserving_ids(&json).unwrap();
+
+        assert_eq!(imported.root_id(), Some(&root_id));
+        assert_eq!(imported.get(&report_id).unwrap().name(), "report");
+        assert!(!imported.contains(&work_id));
+
+        // The free slot survived too, so both trees hand out the same id next.
+        let node = CaseNode::Task(Task::new("groceries"));
+        assert_eq!(
+            imported.insert(node.clone(), &root_id).unwrap(),
+            tree.insert(node, &root_id).unwrap()
+        );
+    }
+
+    #[test]
+    fn import_preserving_ids_rejects_corrupt_trees() {
+        type Corrupt = fn(&mut serde_json::Value);
+
+        // root 0 -> [work 1 -> [report 2]], with the free slot 3.
+        let mut tree = CaseTree::new(CaseNode::Group(Group::new("root")));
+        let root_id = tree.root_id().unwrap().clone();
+        let work_id = tree
+            .insert(CaseNode::Group(Group::new("work")), &root_id)
+            .unwrap();
+        tree.insert(CaseNode::Task(Task::new("report")), &work_id)
+            .unwrap();
+        let groceries_id = tree
+            .insert(CaseNode::Task(Task::new("groceries")), &root_id)
+            .unwrap();
+        tree.remove(&groceries_id).unwrap();
+
+        let valid = serde_json::to_value(&tree).unwrap();
+        let cases: [(Corrupt, NodeIdError); 7] = [
+            (
+                |json| json["tree"]["free_ids"] = serde_json::json!([{ "index": 7 }]),
+                NodeIdError::InvalidFreeId,
+            ),
+            (
+                |json| {
+                    json["tree"]["free_ids"] = serde_json::json!([{ "index": 3 }, { "index": 3 }]);
+                },
+                NodeIdError::InvalidFreeId,
+            ),
+            (
+                |json| json["tree"]["free_ids"] = serde_json::json!([{ "index": 2 }]),
+                NodeIdError::InvalidFreeId,
+            ),
+            (
+                |json| json["tree"]["nodes"][2]["parent"] = serde_json::json!({ "index": 0 }),
+                NodeIdError::NotAChild,
+            ),
+            (
+                // work and report are each other's parent, cut off from the root.
+                |json| {
+                    json["tree"]["nodes"][0]["children"] = serde_json::json!([]);
+                    json["tree"]["nodes"][1]["parent"] = serde_json::json!({ "index": 2 });
+                    json["tree"]["nodes"][2]["children"] = serde_json::json!([{ "index": 1 }]);
+                },
+                NodeIdError::ParentCycle,
+            ),
+            (
+                |json| json["tree"]["nodes"][0]["children"] = serde_json::json!([]),
+                NodeIdError::Unreachable,
+            ),
+            (
+                |json| json["tree"]["nodes"][1]["children"] = serde_json::json!([{ "index": 5 }]),
+                NodeIdError::OutOfBounds,
+            ),
+        ];
+        assert!(CaseTree::import_preserving_ids(&valid.to_string()).is_ok());
+
+        for (corrupt, expected) in cases {
+            let mut json = valid.clone();
+            corrupt(&mut json);
+            assert!(matches!(
+                CaseTree::import_preserving_ids(&json.to_string()),
+                Err(Error::NodeIdError(e)) if e == expected
+            ));
+        }
+    }
+
+    #[test]
+    fn import_preserving_ids_nested_fallback() {
+        let mut tree = CaseTree::new(CaseNode::Group(Group::new("root")));
+        let root_id = tree.root_id().unwrap().clone();
+        tree.insert(CaseNode::Task(Task::new("report")), &root_id)
+            .unwrap();
+
+        let json = serde_json::to_string(&NestedTree::from(&tree.tree)).unwrap();
+        let imported = CaseTree::import_preserving_ids(&json).unwrap();
+        let names: Vec<_> = imported.iter().map(|(_, node)| node.name()).collect();
+        assert_eq!(names, vec!["root", "report"]);
+
+        assert!(matches!(
+            CaseTree::import_preserving_ids("[1, 2]"),
+            Err(Error::InvalidImport(_))
+        ));
+    }
+
+    #[test]
+    fn prune_empty_group_chain() {
+        let mut tree = CaseTree::new(CaseNode::Group(Group::new("root")));
+        let root_id = tree.root_id().unwrap().clone();
+        let mut chain = vec![];
+        let mut parent_id = root_id.clone();
+        for name in ["a", "b", "c"] {
+            parent_id = tree
+                .insert(CaseNode::Group(Group::new(name)), &parent_id)
+                .unwrap();
+            chain.push(parent_id.clone());
+        }
+
+        assert_eq!(tree.prune_empty_groups(), chain);
+        assert!(chain.iter().all(|id| !tree.contains(id)));
+
+        // The root stays, even though it is empty now.
+        assert_eq!(tree.root_id(), Some(&root_id));
+        assert!(tree.prune_empty_groups().is_empty());
+    }
+
+    #[test]
+    fn prune_keeps_group_with_task() {
+        let mut tree = CaseTree::new(CaseNode::Group(Group::new("root")));
+        let root_id = tree.root_id().unwrap().clone();
+        let work_id = tree
+            .insert(CaseNode::Group(Group::new("work")), &root_id)
+            .unwrap();
+        let reports_id = tree
+            .insert(CaseNode::Group(Group::new("reports")), &work_id)
+            .unwrap();
+        tree.insert(CaseNode::Task(Task::new("report")), &reports_id)
+            .unwrap();
+        let empty_id = tree
+            .insert(CaseNode::Group(Group::new("empty")), &work_id)
+            .unwrap();
+
+        assert_eq!(tree.prune_empty_groups(), vec![empty_id]);
+
+        let names: Vec<_> = tree.iter().map(|(_, node)| node.name()).collect();
+        assert_eq!(names, vec!["root", "work", "reports", "report"]);
+    }
 }